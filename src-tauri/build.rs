@@ -1,5 +1,7 @@
 /// Entry point for the crate's build script that runs the Tauri build process.
 ///
+/// Skipped without the `app` feature, when only the platform-independent core is built.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -7,5 +9,6 @@
 /// tauri_build::build();
 /// ```
 fn main() {
+    #[cfg(feature = "app")]
     tauri_build::build()
 }
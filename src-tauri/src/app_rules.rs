@@ -0,0 +1,294 @@
+//! Per-application rules: pause, defer or allow breaks while a given application is focused.
+//!
+//! Rules are configured as `[[app_rules]]` tables (see [`crate::config::AppRule`]) and
+//! evaluated against the focused application by the timer loop's 30-second poll. Detection is
+//! best-effort and read-only: `None` means "unknown", and no rule applies.
+//!
+//! - macOS: the frontmost application's bundle ID and name via `NSWorkspace`; the window title
+//!   via System Events, only when a rule needs it (requires Accessibility permission).
+//! - Linux (X11): the active window's `WM_CLASS`, title and process name via `xprop` and
+//!   `/proc`.
+
+use crate::config::AppRule;
+use crate::timer::FocusedRule;
+use regex::Regex;
+
+/// The focused application as seen by the platform backend.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusedApp {
+    /// Identifiers a rule's `app` may name: bundle ID and name on macOS; window class, instance
+    /// and process name on Linux.
+    pub ids: Vec<String>,
+    /// Title of the focused window, if it could be read.
+    pub title: Option<String>,
+}
+
+/// The first rule in `rules` that matches `app`.
+///
+/// A rule's `app` is compared case-insensitively with every identifier; its `title` is a
+/// regular expression searched in the window title. An empty field matches anything, and a
+/// title pattern never matches when the title is unknown.
+pub fn matching<'a>(rules: &'a [AppRule], app: &FocusedApp) -> Option<&'a AppRule> {
+    rules.iter().find(|rule| {
+        let app_matches = rule.app.trim().is_empty()
+            || app
+                .ids
+                .iter()
+                .any(|id| id.eq_ignore_ascii_case(rule.app.trim()));
+        let title_matches = rule.title.is_empty()
+            || match (&app.title, Regex::new(&rule.title)) {
+                (Some(title), Ok(pattern)) => pattern.is_match(title),
+                _ => false,
+            };
+        app_matches && title_matches
+    })
+}
+
+/// Reads the focused application and returns the rule that applies to it, for
+/// [`crate::timer::Input::AppRule`]. Blocking; the timer loop runs it on a blocking thread.
+pub fn evaluate(rules: &[AppRule]) -> Option<FocusedRule> {
+    let wants_title = rules.iter().any(|r| !r.title.is_empty());
+    let app = focused_app(wants_title)?;
+    matching(rules, &app).map(|rule| FocusedRule {
+        action: rule.action,
+        app: rule.label().to_string(),
+    })
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::FocusedApp;
+    use crate::subprocess::output;
+
+    const FRONT_WINDOW_TITLE: &str = r#"
+        tell application "System Events"
+            return name of front window of (first process whose frontmost is true)
+        end tell
+    "#;
+
+    pub fn focused_app(wants_title: bool) -> Option<FocusedApp> {
+        use objc2_app_kit::NSWorkspace;
+
+        let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+        let ids = [app.bundleIdentifier(), app.localizedName()]
+            .into_iter()
+            .flatten()
+            .map(|s| s.to_string())
+            .collect();
+        let title = if wants_title {
+            output("osascript", &["-e", FRONT_WINDOW_TITLE])
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+        } else {
+            None
+        };
+        Some(FocusedApp { ids, title })
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use super::FocusedApp;
+    use crate::subprocess::output;
+
+    pub fn focused_app(_wants_title: bool) -> Option<FocusedApp> {
+        let window = parse_active_window(&output("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?)?;
+        let props = output(
+            "xprop",
+            &["-id", &window, "WM_CLASS", "_NET_WM_NAME", "_NET_WM_PID"],
+        )?;
+        let mut app = parse_window_properties(&props);
+        if let Some(pid) = parse_property(&props, "_NET_WM_PID") {
+            if let Ok(comm) = std::fs::read_to_string(format!("/proc/{pid}/comm")) {
+                app.ids.push(comm.trim().to_string());
+            }
+        }
+        Some(app)
+    }
+
+    /// Parses `_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007`; `0x0` means no window.
+    pub fn parse_active_window(output: &str) -> Option<String> {
+        let id = output.rsplit_once('#')?.1.trim();
+        (id.starts_with("0x") && id != "0x0").then(|| id.to_string())
+    }
+
+    /// Raw value of `NAME(TYPE) = value` in `xprop` output.
+    pub fn parse_property<'a>(output: &'a str, name: &str) -> Option<&'a str> {
+        output.lines().find_map(|line| {
+            let (key, value) = line.split_once(" = ")?;
+            (key.split('(').next() == Some(name)).then(|| value.trim())
+        })
+    }
+
+    /// Reads `WM_CLASS(STRING) = "instance", "Class"` and `_NET_WM_NAME(UTF8_STRING) = "title"`.
+    pub fn parse_window_properties(output: &str) -> FocusedApp {
+        FocusedApp {
+            ids: parse_property(output, "WM_CLASS")
+                .map(quoted_strings)
+                .unwrap_or_default(),
+            title: parse_property(output, "_NET_WM_NAME")
+                .and_then(|v| quoted_strings(v).into_iter().next()),
+        }
+    }
+
+    /// The double-quoted strings in an `xprop` value, with `\"` and `\\` unescaped.
+    pub fn quoted_strings(value: &str) -> Vec<String> {
+        let mut strings = Vec::new();
+        let mut chars = value.chars();
+        while chars.any(|c| c == '"') {
+            let mut s = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => s.extend(chars.next()),
+                    c => s.push(c),
+                }
+            }
+            strings.push(s);
+        }
+        strings
+    }
+}
+
+#[cfg(target_os = "linux")]
+use linux::focused_app;
+#[cfg(target_os = "macos")]
+use macos::focused_app;
+
+/// Focus detection is unsupported on this platform.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn focused_app(_wants_title: bool) -> Option<FocusedApp> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppAction;
+
+    fn rule(app: &str, title: &str, action: AppAction) -> AppRule {
+        AppRule {
+            app: app.to_string(),
+            title: title.to_string(),
+            action,
+        }
+    }
+
+    fn focused(ids: &[&str], title: Option<&str>) -> FocusedApp {
+        FocusedApp {
+            ids: ids.iter().map(|s| s.to_string()).collect(),
+            title: title.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn matching_compares_app_ids_and_searches_titles() {
+        let rules = [
+            rule("com.obsproject.obs-studio", "", AppAction::Pause),
+            rule("", "(?i)recording", AppAction::Defer),
+            rule(" Firefox ", "YouTube", AppAction::Allow),
+            rule("", "([", AppAction::Pause),
+        ];
+        let cases: &[(&[&str], Option<&str>, Option<usize>)] = &[
+            // App id, case-insensitively and against every identifier.
+            (&["COM.OBSPROJECT.OBS-STUDIO"], None, Some(0)),
+            (
+                &["obs", "com.obsproject.obs-studio"],
+                Some("Scene"),
+                Some(0),
+            ),
+            // Title pattern alone, anywhere in the title.
+            (&["kitty"], Some("Screen RECORDING in progress"), Some(1)),
+            // Both fields must match; the configured app is trimmed.
+            (&["firefox"], Some("Music - YouTube"), Some(2)),
+            (&["firefox"], Some("Docs"), None),
+            (&["chromium"], Some("Music - YouTube"), None),
+            // A title pattern never matches an unknown title, and an invalid one never matches.
+            (&["firefox"], None, None),
+            (&[], None, None),
+        ];
+        for (ids, title, want) in cases {
+            let got = matching(&rules, &focused(ids, *title));
+            assert_eq!(
+                got.map(|r| r as *const AppRule),
+                want.map(|i| &rules[i] as *const AppRule),
+                "ids {ids:?}, title {title:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn matching_takes_the_first_matching_rule() {
+        let rules = [
+            rule("code", "", AppAction::Allow),
+            rule("code", "", AppAction::Pause),
+        ];
+        let got = matching(&rules, &focused(&["code"], None)).unwrap();
+        assert_eq!(got.action, AppAction::Allow);
+        assert!(matching(&[], &focused(&["code"], None)).is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parse_active_window_reads_the_window_id() {
+        use super::linux::parse_active_window;
+
+        let cases = [
+            (
+                "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n",
+                Some("0x3a00007"),
+            ),
+            ("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n", None),
+            ("_NET_ACTIVE_WINDOW:  not found.\n", None),
+            ("_NET_ACTIVE_WINDOW(WINDOW): window id # \n", None),
+            ("", None),
+        ];
+        for (output, want) in cases {
+            assert_eq!(parse_active_window(output).as_deref(), want, "{output:?}");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parse_window_properties_reads_class_and_title() {
+        use super::linux::parse_window_properties;
+
+        let app = parse_window_properties(concat!(
+            "WM_CLASS(STRING) = \"Navigator\", \"firefox\"\n",
+            "_NET_WM_NAME(UTF8_STRING) = \"Say \\\"hi\\\", \\\\ bye — Mozilla Firefox\"\n",
+            "_NET_WM_PID(CARDINAL) = 4242\n",
+        ));
+        assert_eq!(app.ids, ["Navigator", "firefox"]);
+        assert_eq!(
+            app.title.as_deref(),
+            Some("Say \"hi\", \\ bye — Mozilla Firefox")
+        );
+
+        // Properties the window does not set.
+        let app = parse_window_properties(concat!(
+            "WM_CLASS:  not found.\n",
+            "WM_NAME(STRING) = \"legacy title\"\n",
+            "_NET_WM_NAME:  not found.\n",
+        ));
+        assert_eq!(app, FocusedApp::default());
+
+        // An empty class, and a title that is an empty string.
+        let app = parse_window_properties(concat!(
+            "WM_CLASS(STRING) = \"\", \"\"\n",
+            "_NET_WM_NAME(UTF8_STRING) = \"\"\n",
+        ));
+        assert_eq!(app.ids, ["", ""]);
+        assert_eq!(app.title.as_deref(), Some(""));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parse_property_matches_the_whole_name() {
+        use super::linux::parse_property;
+
+        let output = "WM_NAME(STRING) = \"a\"\n_NET_WM_PID(CARDINAL) = 4242\n";
+        assert_eq!(parse_property(output, "_NET_WM_PID"), Some("4242"));
+        assert_eq!(parse_property(output, "NAME"), None);
+        assert_eq!(parse_property(output, "_NET_WM_NAME"), None);
+    }
+}
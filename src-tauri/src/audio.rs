@@ -1,4 +1,5 @@
 use crate::commands::AppState;
+use crate::config::SoundChoice;
 use objc2::rc::Retained;
 use objc2_app_kit::NSSound;
 use objc2_foundation::{MainThreadMarker, NSString};
@@ -14,22 +15,15 @@ static CURRENT_SOUND: Mutex<Option<SoundWrapper>> = Mutex::new(None);
 
 pub fn play_break_sound(app: &AppHandle) {
     let app_state = app.state::<AppState>();
-    let sound_name = {
+    let sound = {
         let cfg = app_state.config.lock().unwrap_or_else(|e| e.into_inner());
-        cfg.sound.clone()
+        cfg.sound
     };
 
-    if sound_name == "off" {
-        return;
-    }
-
-    let system_sound = match sound_name.as_str() {
-        "chime" => "Glass",
-        "whitenoise" => "Blow",
-        _ => {
-            log::warn!("Unknown sound name: '{}'", sound_name);
-            return;
-        }
+    let system_sound = match sound {
+        SoundChoice::Off => return,
+        SoundChoice::Chime => "Glass",
+        SoundChoice::WhiteNoise => "Blow",
     };
 
     let _ = app.run_on_main_thread(move || {
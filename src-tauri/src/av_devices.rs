@@ -0,0 +1,382 @@
+//! Camera and microphone in-use detection, the last layer of meeting detection.
+//!
+//! Best-effort and read-only: an empty list means "nothing in use, or unknown".
+//!
+//! - macOS: CoreMediaIO and CoreAudio's "device is running somewhere" properties, which cover
+//!   every process without extra permissions (but do not say which process).
+//! - Linux: open file handles on `/dev/video*` and ALSA capture devices (`/dev/snd/pcmC*D*c`)
+//!   under `/proc/<pid>/fd`. Only processes whose `fd` directory is readable are seen, which for
+//!   an unprivileged user means their own — the ones that matter. Sound servers keep the ALSA
+//!   capture devices open whether or not anything records, so their handles are left out.
+
+/// Kind of capture device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Camera,
+    Microphone,
+}
+
+impl std::fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Camera => "camera",
+            Self::Microphone => "microphone",
+        })
+    }
+}
+
+/// A capture device in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceUse {
+    pub kind: DeviceKind,
+    /// Device path on Linux, device name on macOS.
+    pub device: String,
+    /// Name of the process using it, when the platform says.
+    pub process: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::{DeviceKind, DeviceUse};
+    use std::ffi::c_void;
+
+    /// `AudioObjectPropertyAddress` / `CMIOObjectPropertyAddress`.
+    #[repr(C)]
+    struct PropertyAddress {
+        selector: u32,
+        scope: u32,
+        element: u32,
+    }
+
+    const fn fourcc(code: &[u8; 4]) -> u32 {
+        u32::from_be_bytes(*code)
+    }
+
+    /// `kAudioObjectSystemObject` / `kCMIOObjectSystemObject`.
+    const SYSTEM_OBJECT: u32 = 1;
+    const DEVICES: u32 = fourcc(b"dev#");
+    const SCOPE_GLOBAL: u32 = fourcc(b"glob");
+    const SCOPE_INPUT: u32 = fourcc(b"inpt");
+    const STREAMS: u32 = fourcc(b"stm#");
+    const IS_RUNNING_SOMEWHERE: u32 = fourcc(b"gone");
+    const ELEMENT_MAIN: u32 = 0;
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        fn AudioObjectGetPropertyDataSize(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: *mut u32,
+        ) -> i32;
+        fn AudioObjectGetPropertyData(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: *mut u32,
+            data: *mut c_void,
+        ) -> i32;
+    }
+
+    #[link(name = "CoreMediaIO", kind = "framework")]
+    extern "C" {
+        fn CMIOObjectGetPropertyDataSize(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: *mut u32,
+        ) -> i32;
+        fn CMIOObjectGetPropertyData(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: u32,
+            used: *mut u32,
+            data: *mut c_void,
+        ) -> i32;
+    }
+
+    fn address(selector: u32, scope: u32) -> PropertyAddress {
+        PropertyAddress {
+            selector,
+            scope,
+            element: ELEMENT_MAIN,
+        }
+    }
+
+    /// Size in bytes of a CoreAudio property, or 0 if it cannot be read.
+    fn audio_size(object: u32, addr: &PropertyAddress) -> u32 {
+        let mut size = 0u32;
+        // SAFETY: `addr` and `size` are valid for the duration of the call.
+        let status =
+            unsafe { AudioObjectGetPropertyDataSize(object, addr, 0, std::ptr::null(), &mut size) };
+        if status == 0 {
+            size
+        } else {
+            0
+        }
+    }
+
+    /// Reads a CoreAudio property made of `u32`s (object IDs or a flag).
+    fn audio_u32s(object: u32, addr: &PropertyAddress) -> Vec<u32> {
+        let mut size = audio_size(object, addr);
+        let mut data = vec![0u32; size as usize / 4];
+        if data.is_empty() {
+            return data;
+        }
+        // SAFETY: `data` holds `size` bytes.
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                object,
+                addr,
+                0,
+                std::ptr::null(),
+                &mut size,
+                data.as_mut_ptr().cast(),
+            )
+        };
+        data.truncate(size as usize / 4);
+        if status == 0 {
+            data
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Reads a CoreMediaIO property made of `u32`s (object IDs or a flag).
+    fn cmio_u32s(object: u32, addr: &PropertyAddress) -> Vec<u32> {
+        let mut size = 0u32;
+        // SAFETY: `addr` and `size` are valid for the duration of the call.
+        let status =
+            unsafe { CMIOObjectGetPropertyDataSize(object, addr, 0, std::ptr::null(), &mut size) };
+        if status != 0 || size < 4 {
+            return Vec::new();
+        }
+        let mut data = vec![0u32; size as usize / 4];
+        let mut used = 0u32;
+        // SAFETY: `data` holds `size` bytes.
+        let status = unsafe {
+            CMIOObjectGetPropertyData(
+                object,
+                addr,
+                0,
+                std::ptr::null(),
+                size,
+                &mut used,
+                data.as_mut_ptr().cast(),
+            )
+        };
+        data.truncate(used as usize / 4);
+        if status == 0 {
+            data
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn devices_in_use() -> Vec<DeviceUse> {
+        let running = address(IS_RUNNING_SOMEWHERE, SCOPE_GLOBAL);
+        let mut uses = Vec::new();
+        for camera in cmio_u32s(SYSTEM_OBJECT, &address(DEVICES, SCOPE_GLOBAL)) {
+            if cmio_u32s(camera, &running).first().is_some_and(|&r| r != 0) {
+                uses.push(DeviceUse {
+                    kind: DeviceKind::Camera,
+                    device: format!("camera {camera}"),
+                    process: None,
+                });
+            }
+        }
+        for device in audio_u32s(SYSTEM_OBJECT, &address(DEVICES, SCOPE_GLOBAL)) {
+            let has_input = audio_size(device, &address(STREAMS, SCOPE_INPUT)) > 0;
+            if has_input
+                && audio_u32s(device, &running)
+                    .first()
+                    .is_some_and(|&r| r != 0)
+            {
+                uses.push(DeviceUse {
+                    kind: DeviceKind::Microphone,
+                    device: format!("audio device {device}"),
+                    process: None,
+                });
+            }
+        }
+        uses
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use super::{DeviceKind, DeviceUse};
+    use std::path::Path;
+
+    /// Processes that hold ALSA capture devices open on behalf of their clients; the clients
+    /// themselves show up through `pactl` (see [`crate::meeting`]).
+    const SOUND_SERVERS: &[&str] = &["pipewire", "pipewire-pulse", "pulseaudio", "wireplumber"];
+
+    pub fn devices_in_use() -> Vec<DeviceUse> {
+        devices_in_use_under(Path::new("/proc"))
+    }
+
+    /// Capture devices held open by the processes under the proc tree at `root`, one entry per
+    /// process and device. Processes that exit mid-scan or cannot be inspected are skipped.
+    pub fn devices_in_use_under(root: &Path) -> Vec<DeviceUse> {
+        let Ok(entries) = std::fs::read_dir(root) else {
+            return Vec::new();
+        };
+        let mut uses = Vec::new();
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if !entry
+                .file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+            {
+                continue;
+            }
+            let Ok(fds) = std::fs::read_dir(dir.join("fd")) else {
+                continue;
+            };
+            let process = std::fs::read_to_string(dir.join("comm"))
+                .ok()
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty());
+            for fd in fds.flatten() {
+                let Ok(target) = std::fs::read_link(fd.path()) else {
+                    continue;
+                };
+                let Some(kind) = classify(&target) else {
+                    continue;
+                };
+                if kind == DeviceKind::Microphone
+                    && process
+                        .as_deref()
+                        .is_some_and(|p| SOUND_SERVERS.contains(&p))
+                {
+                    continue;
+                }
+                let device = target.display().to_string();
+                if !uses
+                    .iter()
+                    .any(|u: &DeviceUse| u.device == device && u.process == process)
+                {
+                    uses.push(DeviceUse {
+                        kind,
+                        device,
+                        process: process.clone(),
+                    });
+                }
+            }
+        }
+        uses
+    }
+
+    /// Whether `path` is a camera (`/dev/video0`) or an ALSA capture device
+    /// (`/dev/snd/pcmC0D0c`; playback devices end in `p`).
+    pub fn classify(path: &Path) -> Option<DeviceKind> {
+        let name = path.file_name()?.to_str()?;
+        let parent = path.parent()?;
+        if parent == Path::new("/dev") && name.starts_with("video") {
+            Some(DeviceKind::Camera)
+        } else if parent == Path::new("/dev/snd") && name.starts_with("pcmC") && name.ends_with('c')
+        {
+            Some(DeviceKind::Microphone)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::devices_in_use;
+#[cfg(target_os = "macos")]
+pub use macos::devices_in_use;
+
+/// Device detection is unsupported on this platform.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn devices_in_use() -> Vec<DeviceUse> {
+    Vec::new()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::linux::{classify, devices_in_use_under};
+    use super::*;
+    use crate::fake_proc::FakeProc;
+    use std::path::Path;
+
+    fn device_use(kind: DeviceKind, device: &str, process: &str) -> DeviceUse {
+        DeviceUse {
+            kind,
+            device: device.into(),
+            process: Some(process.into()),
+        }
+    }
+
+    #[test]
+    fn classifies_cameras_and_capture_devices_only() {
+        let kind = |path: &str| classify(Path::new(path));
+        assert_eq!(kind("/dev/video0"), Some(DeviceKind::Camera));
+        assert_eq!(kind("/dev/snd/pcmC0D0c"), Some(DeviceKind::Microphone));
+        assert_eq!(kind("/dev/snd/pcmC1D3p"), None);
+        assert_eq!(kind("/dev/snd/controlC0"), None);
+        assert_eq!(kind("/dev/null"), None);
+        assert_eq!(kind("/tmp/video0"), None);
+        assert_eq!(kind("socket:[12345]"), None);
+    }
+
+    #[test]
+    fn finds_devices_held_open_once_per_process() {
+        let proc = FakeProc::new("av-in-use");
+        proc.process_with_fds(
+            "100",
+            "zoom",
+            &[
+                "/dev/null",
+                "/dev/video0",
+                "/dev/video0",
+                "/dev/snd/pcmC0D0c",
+            ],
+        )
+        .process_with_fds("200", "firefox", &["/dev/snd/pcmC0D0p", "socket:[1]"])
+        .process_with_fds("self", "zoom", &["/dev/video1"]);
+        let mut uses = devices_in_use_under(proc.path());
+        uses.sort_by(|a, b| a.device.cmp(&b.device));
+        assert_eq!(
+            uses,
+            vec![
+                device_use(DeviceKind::Microphone, "/dev/snd/pcmC0D0c", "zoom"),
+                device_use(DeviceKind::Camera, "/dev/video0", "zoom"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sound_servers_holding_capture_devices_are_not_flagged() {
+        let proc = FakeProc::new("av-sound-servers");
+        proc.process_with_fds(
+            "300",
+            "pipewire",
+            &["/dev/snd/pcmC0D0c", "/dev/snd/pcmC0D0p"],
+        )
+        .process_with_fds("301", "wireplumber", &["/dev/snd/pcmC0D0c"])
+        .process_with_fds("302", "pulseaudio", &["/dev/snd/pcmC1D0c"]);
+        assert!(devices_in_use_under(proc.path()).is_empty());
+
+        // A camera opened through PipeWire is still in use.
+        proc.process_with_fds("303", "pipewire", &["/dev/video0"]);
+        assert_eq!(
+            devices_in_use_under(proc.path()),
+            vec![device_use(DeviceKind::Camera, "/dev/video0", "pipewire")]
+        );
+    }
+
+    #[test]
+    fn missing_proc_tree_means_nothing_in_use() {
+        assert!(devices_in_use_under(Path::new("/nonexistent/proc")).is_empty());
+    }
+}
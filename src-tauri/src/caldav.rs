@@ -0,0 +1,443 @@
+//! CalDAV calendars, read-only.
+//!
+//! Each `[[calendar.caldav]]` calendar is asked for its free/busy over the next two days with a
+//! `free-busy-query` REPORT (RFC 4791 §7.10), at most every `refresh_minutes`. Requests go
+//! through `curl`, so TLS, proxies and `~/.netrc` behave as they do elsewhere on the system;
+//! credentials are passed on its standard input, never on the command line.
+//!
+//! Results are cached on disk, so busy periods keep applying while the server is unreachable
+//! and right after a restart. Free/busy carries no event titles: busy periods are named after
+//! their calendar.
+
+use crate::calendar::{self, CalendarEvent, Period};
+use crate::config::CalDavCalendar;
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// How far ahead free/busy is fetched; more than the lookahead, so the cache lasts offline.
+const FETCH_HOURS: i64 = 48;
+
+/// Upper bound on one request, connection included.
+const REQUEST_TIMEOUT_SECONDS: u32 = 20;
+
+/// Free/busy of one calendar, as last fetched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Fetched {
+    /// Last attempt, successful or not; the next one waits `refresh_minutes` from here.
+    checked_at: DateTime<Utc>,
+    /// Last successful fetch, if any.
+    fetched_at: Option<DateTime<Utc>>,
+    busy: Vec<Period>,
+}
+
+/// Free/busy per calendar URL, mirrored to [`cache_path`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cache {
+    calendars: HashMap<String, Fetched>,
+}
+
+impl Cache {
+    fn load() -> Self {
+        let path = cache_path();
+        match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("Ignoring CalDAV cache {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) {
+        let path = cache_path();
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                log::warn!("Failed to create cache directory {}: {e}", parent.display());
+                return;
+            }
+        }
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                let tmp = path.with_extension("json.tmp");
+                if let Err(e) =
+                    std::fs::write(&tmp, json).and_then(|()| std::fs::rename(&tmp, &path))
+                {
+                    log::warn!("Failed to write CalDAV cache to {}: {e}", path.display());
+                }
+            }
+            Err(e) => log::warn!("Failed to serialise CalDAV cache: {e}"),
+        }
+    }
+}
+
+fn cache_path() -> PathBuf {
+    let mut p = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    p.push("twenty20");
+    p.push("caldav.json");
+    p
+}
+
+/// Busy periods of `calendars` that have not ended by `now`, refreshing those last checked
+/// more than `refresh_minutes` ago. A calendar that cannot be fetched keeps its cached periods.
+///
+/// Blocking; see [`calendar::lookahead`].
+pub fn busy(
+    calendars: &[CalDavCalendar],
+    refresh_minutes: u32,
+    now: DateTime<Local>,
+) -> Vec<CalendarEvent> {
+    static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+    if calendars.is_empty() {
+        return Vec::new();
+    }
+    let now = now.with_timezone(&Utc);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = cache.get_or_insert_with(Cache::load);
+    if cache.refresh(calendars, refresh_minutes, now) {
+        cache.save();
+    }
+    cache.busy(calendars, now)
+}
+
+impl Cache {
+    /// Fetches the calendars that are due, forgets those no longer configured, and returns
+    /// whether anything changed.
+    fn refresh(
+        &mut self,
+        calendars: &[CalDavCalendar],
+        refresh_minutes: u32,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let mut changed = false;
+        for calendar in calendars {
+            let due = self.calendars.get(&calendar.url).map_or(true, |f| {
+                now - f.checked_at >= Duration::minutes(refresh_minutes.into())
+            });
+            if !due {
+                continue;
+            }
+            let previous = self.calendars.remove(&calendar.url);
+            let fetched = match fetch(calendar, now) {
+                Ok(busy) => {
+                    log::info!(
+                        "Fetched {} busy periods from {}",
+                        busy.len(),
+                        label(calendar)
+                    );
+                    Fetched {
+                        checked_at: now,
+                        fetched_at: Some(now),
+                        busy,
+                    }
+                }
+                Err(e) => {
+                    let since = previous
+                        .as_ref()
+                        .and_then(|f| f.fetched_at)
+                        .map_or("never".to_string(), |t| {
+                            t.with_timezone(&Local).format("%a %H:%M").to_string()
+                        });
+                    log::warn!(
+                        "Could not fetch {}: {e} — using the cached free/busy (last fetched: {since})",
+                        label(calendar)
+                    );
+                    Fetched {
+                        checked_at: now,
+                        fetched_at: previous.as_ref().and_then(|f| f.fetched_at),
+                        busy: previous.map(|f| f.busy).unwrap_or_default(),
+                    }
+                }
+            };
+            self.calendars.insert(calendar.url.clone(), fetched);
+            changed = true;
+        }
+        // Forget calendars that are no longer configured.
+        let before = self.calendars.len();
+        self.calendars
+            .retain(|url, _| calendars.iter().any(|c| c.url == *url));
+        changed || self.calendars.len() != before
+    }
+
+    /// The cached busy periods of `calendars` that have not ended by `now`.
+    fn busy(&self, calendars: &[CalDavCalendar], now: DateTime<Utc>) -> Vec<CalendarEvent> {
+        calendars
+            .iter()
+            .filter_map(|calendar| Some((calendar, self.calendars.get(&calendar.url)?)))
+            .flat_map(|(calendar, fetched)| {
+                fetched
+                    .busy
+                    .iter()
+                    .filter(|(_, end)| *end > now)
+                    .map(|(start, end)| CalendarEvent {
+                        summary: label(calendar).to_string(),
+                        start: start.with_timezone(&Local),
+                        end: end.with_timezone(&Local),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// The calendar's name, or "Busy" when it has none.
+fn label(calendar: &CalDavCalendar) -> &str {
+    match calendar.name.trim() {
+        "" => "Busy",
+        name => name,
+    }
+}
+
+/// Runs a `free-busy-query` REPORT against `calendar` for the next [`FETCH_HOURS`].
+fn fetch(calendar: &CalDavCalendar, now: DateTime<Utc>) -> Result<Vec<Period>, String> {
+    let format = "%Y%m%dT%H%M%SZ";
+    let body = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:free-busy-query xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <C:time-range start="{}" end="{}"/>
+</C:free-busy-query>
+"#,
+        (now - Duration::hours(1)).format(format),
+        (now + Duration::hours(FETCH_HOURS)).format(format),
+    );
+
+    // Everything, credentials included, goes through a curl config file on stdin.
+    let mut config = vec![
+        ("url", calendar.url.trim().to_string()),
+        ("request", "REPORT".to_string()),
+        ("header", "Depth: 1".to_string()),
+        (
+            "header",
+            "Content-Type: application/xml; charset=utf-8".to_string(),
+        ),
+        ("data-binary", body),
+    ];
+    // A user name without a password would make curl prompt for one.
+    if calendar.password.is_empty() {
+        config.push(("netrc-optional", String::new()));
+    } else {
+        config.push((
+            "user",
+            format!("{}:{}", calendar.username, calendar.password),
+        ));
+    }
+    let config: String = config
+        .into_iter()
+        .map(|(key, value)| {
+            if value.is_empty() {
+                format!("{key}\n")
+            } else {
+                format!("{key} = \"{}\"\n", quote(&value))
+            }
+        })
+        .collect();
+
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--max-time",
+            &REQUEST_TIMEOUT_SECONDS.to_string(),
+            "--config",
+            "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run curl: {e}"))?;
+    // Dropping stdin closes it, so curl sees the end of its config even if writing failed;
+    // either way it is waited for rather than left behind.
+    let written = child
+        .stdin
+        .take()
+        .map_or(Ok(()), |mut stdin| stdin.write_all(config.as_bytes()));
+    let out = child
+        .wait_with_output()
+        .map_err(|e| format!("curl failed: {e}"))?;
+    written.map_err(|e| format!("could not write to curl: {e}"))?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        return Err(stderr.trim().trim_start_matches("curl: ").to_string());
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    if !text.contains("BEGIN:VFREEBUSY") {
+        return Err("the server did not answer with free/busy".to_string());
+    }
+    Ok(calendar::parse_free_busy(&text))
+}
+
+/// Escapes a value for a double-quoted curl config string.
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    const FREE_BUSY: &str = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VFREEBUSY\r\n\
+        FREEBUSY;FBTYPE=BUSY:20261017T080000Z/PT1H,20261017T120000Z/20261017T1\r\n \
+        23000Z\r\n\
+        FREEBUSY;FBTYPE=FREE:20261017T140000Z/PT1H\r\n\
+        END:VFREEBUSY\r\n\
+        END:VCALENDAR\r\n";
+
+    /// A local HTTP server answering one request per canned `(status, body)` response.
+    struct Server {
+        calendar: CalDavCalendar,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Server {
+        fn start(responses: Vec<(u16, &'static str)>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!(
+                "http://{}/calendars/alice/work/",
+                listener.local_addr().unwrap()
+            );
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let log = Arc::clone(&requests);
+            thread::spawn(move || {
+                for (status, body) in responses {
+                    let Ok((stream, _)) = listener.accept() else {
+                        return;
+                    };
+                    let mut reader = BufReader::new(stream);
+                    let mut request = String::new();
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                        request.push_str(&line);
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                    }
+                    let mut content = vec![0; length];
+                    reader.read_exact(&mut content).unwrap();
+                    request.push_str(&String::from_utf8_lossy(&content));
+                    log.lock().unwrap().push(request);
+                    let _ = write!(
+                        reader.get_mut(),
+                        "HTTP/1.1 {status} Canned\r\nContent-Type: text/calendar\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                }
+            });
+            Self {
+                calendar: CalDavCalendar {
+                    name: "Work".into(),
+                    url,
+                    username: "alice".into(),
+                    password: "p\"w\\x".into(),
+                },
+                requests,
+            }
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+    }
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 17, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn fetch_sends_a_free_busy_query_and_parses_busy_periods() {
+        let server = Server::start(vec![(200, FREE_BUSY)]);
+        let busy = fetch(&server.calendar, at(7, 0)).unwrap();
+        assert_eq!(busy, vec![(at(8, 0), at(9, 0)), (at(12, 0), at(12, 30))]);
+
+        let request = server.requests.lock().unwrap()[0].clone();
+        assert!(request.starts_with("REPORT /calendars/alice/work/ HTTP/1.1\r\n"));
+        assert!(request.contains("Depth: 1\r\n"));
+        // alice:p"w\x
+        assert!(request.contains("Authorization: Basic YWxpY2U6cCJ3XHg=\r\n"));
+        assert!(
+            request.contains(r#"<C:time-range start="20261017T060000Z" end="20261019T070000Z"/>"#)
+        );
+    }
+
+    #[test]
+    fn fetch_fails_on_an_error_status_or_a_non_calendar_answer() {
+        let server = Server::start(vec![(500, "oops"), (200, "<html></html>")]);
+        let e = fetch(&server.calendar, at(7, 0)).unwrap_err();
+        assert!(e.contains("500"), "{e}");
+        let e = fetch(&server.calendar, at(7, 0)).unwrap_err();
+        assert_eq!(e, "the server did not answer with free/busy");
+    }
+
+    #[test]
+    fn failed_refresh_keeps_the_cached_periods() {
+        let server = Server::start(vec![(200, FREE_BUSY), (503, "")]);
+        let calendars = [server.calendar.clone()];
+        let mut cache = Cache::default();
+        assert!(cache.refresh(&calendars, 15, at(7, 0)));
+        assert!(cache.refresh(&calendars, 15, at(7, 15)));
+        assert_eq!(server.requests(), 2);
+
+        let fetched = &cache.calendars[&server.calendar.url];
+        assert_eq!(fetched.checked_at, at(7, 15));
+        assert_eq!(fetched.fetched_at, Some(at(7, 0)));
+        let busy = cache.busy(&calendars, at(8, 30));
+        assert_eq!(busy.len(), 2);
+        assert_eq!(busy[0].summary, "Work");
+        assert_eq!(busy[0].end, at(9, 0).with_timezone(&Local));
+    }
+
+    #[test]
+    fn refresh_waits_refresh_minutes_after_each_attempt() {
+        let server = Server::start(vec![(503, ""), (200, FREE_BUSY)]);
+        let calendars = [server.calendar.clone()];
+        let mut cache = Cache::default();
+        assert!(cache.refresh(&calendars, 15, at(7, 0)));
+        assert!(cache.busy(&calendars, at(7, 0)).is_empty());
+        // A failed attempt counts too, so an unreachable server is not retried every poll.
+        assert!(!cache.refresh(&calendars, 15, at(7, 14)));
+        assert_eq!(server.requests(), 1);
+        assert!(cache.refresh(&calendars, 15, at(7, 15)));
+        assert_eq!(server.requests(), 2);
+        assert_eq!(cache.busy(&calendars, at(7, 15)).len(), 2);
+    }
+
+    #[test]
+    fn refresh_forgets_calendars_no_longer_configured() {
+        let server = Server::start(vec![(200, FREE_BUSY)]);
+        let mut cache = Cache::default();
+        cache.refresh(std::slice::from_ref(&server.calendar), 15, at(7, 0));
+        assert!(cache.refresh(&[], 15, at(7, 1)));
+        assert!(cache.calendars.is_empty());
+    }
+}
@@ -0,0 +1,930 @@
+//! Local calendar awareness.
+//!
+//! Reads the iCalendar files listed in `[calendar] files` (exported or synced by other tools),
+//! expands recurring events, and reports the busy event in progress and the next one to the
+//! scheduler ([`crate::timer::Input::Calendar`]) on the timer loop's 30-second poll. The
+//! scheduler pauses during events and moves a break forward so it ends as an event starts.
+//! Files are parsed again only when they change on disk. Busy periods from CalDAV calendars
+//! ([`crate::caldav`]) are taken into account alongside the files.
+//!
+//! Supported: `VEVENT`s with `DTSTART` and `DTEND` or `DURATION`; `RRULE` with `FREQ` daily,
+//! weekly, monthly or yearly, `INTERVAL`, `COUNT`, `UNTIL`, `BYDAY` and `BYMONTHDAY`; `EXDATE`;
+//! and moved occurrences (`RECURRENCE-ID`). UTC times and times with an IANA `TZID` (such as
+//! `Europe/Berlin`, possibly behind a vendor prefix) are converted to local time, so recurring
+//! events keep their wall-clock time in their own zone across daylight-saving changes.
+//! `VTIMEZONE` definitions are not read: a `TZID` that is not an IANA name, such as Outlook's
+//! `W. Europe Standard Time`, is read as local time, with a warning. All-day, free
+//! (`TRANSP:TRANSPARENT`) and cancelled events do not count as busy.
+
+use crate::config::CalendarConfig;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// How far ahead the next event is looked for.
+const LOOKAHEAD_HOURS: i64 = 24;
+
+/// Upper bound on recurrence periods stepped through per event, against runaway rules.
+const MAX_PERIODS: u32 = 50_000;
+
+/// One occurrence of a busy event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+/// The busy event in progress at `now` (the one ending last, when they overlap) and the next
+/// one to start within a day, across all files and CalDAV calendars in `config`.
+///
+/// Blocking: it may fetch from CalDAV servers. The timer loop runs it on a blocking thread.
+pub fn lookahead(
+    config: &CalendarConfig,
+    now: DateTime<Local>,
+) -> (Option<CalendarEvent>, Option<CalendarEvent>) {
+    let window_end = now + Duration::hours(LOOKAHEAD_HOURS);
+    let from_files = config
+        .files
+        .iter()
+        .flat_map(|file| events(&expand_home(file)))
+        .flat_map(|event| event.occurrences(now - Duration::days(1), window_end));
+    let from_caldav = crate::caldav::busy(&config.caldav, config.refresh_minutes, now);
+    let mut current: Option<CalendarEvent> = None;
+    let mut next: Option<CalendarEvent> = None;
+    for occurrence in from_files.chain(from_caldav) {
+        if occurrence.start <= now && now < occurrence.end {
+            if current.as_ref().map_or(true, |c| occurrence.end > c.end) {
+                current = Some(occurrence);
+            }
+        } else if occurrence.start > now
+            && occurrence.start < window_end
+            && next.as_ref().map_or(true, |n| occurrence.start < n.start)
+        {
+            next = Some(occurrence);
+        }
+    }
+    (current, next)
+}
+
+/// A busy period from a free/busy response.
+pub type Period = (DateTime<Utc>, DateTime<Utc>);
+
+/// Busy periods in a free/busy response: the `FREEBUSY` properties of its `VFREEBUSY`, as
+/// `start/end` or `start/duration` lists. Periods marked `FBTYPE=FREE` are left out.
+pub fn parse_free_busy(text: &str) -> Vec<Period> {
+    let mut periods = Vec::new();
+    for line in unfold(text) {
+        let Some(prop) = Property::parse(&line) else {
+            continue;
+        };
+        if prop.name != "FREEBUSY"
+            || prop
+                .param("FBTYPE")
+                .is_some_and(|t| t.eq_ignore_ascii_case("FREE"))
+        {
+            continue;
+        }
+        for period in prop.value.split(',') {
+            let Some((start, end)) = period.trim().split_once('/') else {
+                continue;
+            };
+            let Some(start) = parse_date_time(start).and_then(When::to_local) else {
+                continue;
+            };
+            let end = match parse_date_time(end) {
+                Some(end) => end.to_local(),
+                None => parse_duration(end).map(|d| start + d),
+            };
+            if let Some(end) = end.filter(|end| *end > start) {
+                periods.push((start.with_timezone(&Utc), end.with_timezone(&Utc)));
+            }
+        }
+    }
+    periods
+}
+
+fn expand_home(file: &str) -> PathBuf {
+    match file.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(file),
+    }
+}
+
+/// Modification time and length of a calendar file.
+type Stamp = (SystemTime, u64);
+
+/// Parsed calendar files and the stamp they were parsed at.
+type Cache = Mutex<HashMap<PathBuf, (Stamp, Vec<Event>)>>;
+
+/// The busy events in `path`, parsed again only when the file has changed.
+fn events(path: &Path) -> Vec<Event> {
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    let stamp = std::fs::metadata(path)
+        .ok()
+        .and_then(|m| Some((m.modified().ok()?, m.len())));
+    let Some(stamp) = stamp else {
+        log::warn!("Calendar file {} not found", path.display());
+        return Vec::new();
+    };
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((seen, events)) = cache.get(path) {
+        if *seen == stamp {
+            return events.clone();
+        }
+    }
+    let events = match std::fs::read(path) {
+        Ok(bytes) => parse(&String::from_utf8_lossy(&bytes)),
+        Err(e) => {
+            log::warn!("Could not read calendar file {}: {e}", path.display());
+            Vec::new()
+        }
+    };
+    log::info!("Loaded {} events from {}", events.len(), path.display());
+    cache.insert(path.to_path_buf(), (stamp, events.clone()));
+    events
+}
+
+/// The time zone a [`When`] is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Zone {
+    /// `…Z`.
+    Utc,
+    /// Floating, or with a `TZID` that could not be resolved.
+    Local,
+    /// With a `TZID`.
+    Tz(Tz),
+}
+
+/// A point in time as written in the file, in its zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct When {
+    time: NaiveDateTime,
+    zone: Zone,
+}
+
+impl When {
+    /// `None` for a time skipped by a daylight-saving change.
+    fn to_local(self) -> Option<DateTime<Local>> {
+        match self.zone {
+            Zone::Utc => Some(Utc.from_utc_datetime(&self.time).with_timezone(&Local)),
+            Zone::Local => Local.from_local_datetime(&self.time).earliest(),
+            Zone::Tz(tz) => tz
+                .from_local_datetime(&self.time)
+                .earliest()
+                .map(|t| t.with_timezone(&Local)),
+        }
+    }
+}
+
+/// A busy `VEVENT`, before recurrence expansion.
+#[derive(Debug, Clone)]
+struct Event {
+    uid: String,
+    summary: String,
+    start: When,
+    duration: Duration,
+    rule: Option<Rule>,
+    exdates: Vec<When>,
+    /// The occurrence of a recurring event this one replaces.
+    recurrence_id: Option<When>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A parsed `RRULE`.
+#[derive(Debug, Clone)]
+struct Rule {
+    freq: Freq,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<When>,
+    /// Weekdays, with an optional ordinal for monthly rules (`1MO`, `-1FR`).
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+}
+
+impl Event {
+    /// Occurrences that overlap `from..to`, in order.
+    fn occurrences(&self, from: DateTime<Local>, to: DateTime<Local>) -> Vec<CalendarEvent> {
+        let mut out = Vec::new();
+        let mut push = |start: NaiveDateTime| {
+            let Some(start) = (When {
+                time: start,
+                zone: self.start.zone,
+            })
+            .to_local() else {
+                return;
+            };
+            let end = start + self.duration;
+            if end > from && start < to {
+                out.push(CalendarEvent {
+                    summary: self.summary.clone(),
+                    start,
+                    end,
+                });
+            }
+        };
+        let Some(rule) = &self.rule else {
+            push(self.start.time);
+            return out;
+        };
+        let until = rule.until.and_then(When::to_local);
+        // Compared as instants: an `EXDATE` may be written in another zone than `DTSTART`.
+        let excluded: Vec<DateTime<Local>> =
+            self.exdates.iter().filter_map(|w| w.to_local()).collect();
+        let mut count = 0;
+        for period in 0..MAX_PERIODS {
+            let candidates = rule.period(self.start.time, period);
+            let Some(first) = candidates.first() else {
+                continue;
+            };
+            let first_local = When {
+                time: *first,
+                zone: self.start.zone,
+            }
+            .to_local();
+            if first_local.is_some_and(|t| t >= to) {
+                break;
+            }
+            for start in candidates {
+                if start < self.start.time {
+                    continue;
+                }
+                let local = When {
+                    time: start,
+                    zone: self.start.zone,
+                }
+                .to_local();
+                if until.is_some_and(|u| local.is_some_and(|t| t > u)) {
+                    return out;
+                }
+                count += 1;
+                if rule.count.is_some_and(|c| count > c) {
+                    return out;
+                }
+                if !local.is_some_and(|t| excluded.contains(&t)) {
+                    push(start);
+                }
+            }
+        }
+        out
+    }
+}
+
+impl Rule {
+    /// Candidate starts in the `index`th period (day, week, month or year) after `dtstart`'s,
+    /// in order. Candidates before `dtstart` are filtered out by the caller.
+    fn period(&self, dtstart: NaiveDateTime, index: u32) -> Vec<NaiveDateTime> {
+        let step = i64::from(index) * i64::from(self.interval.max(1));
+        let time = dtstart.time();
+        let date = dtstart.date();
+        let mut days: Vec<NaiveDate> = match self.freq {
+            Freq::Daily => {
+                let day = date + Duration::days(step);
+                let weekday_ok =
+                    self.by_day.is_empty() || self.by_day.iter().any(|(_, w)| *w == day.weekday());
+                let month_day_ok = self.by_month_day.is_empty()
+                    || self
+                        .by_month_day
+                        .iter()
+                        .any(|&d| month_day(day, d) == Some(day));
+                if weekday_ok && month_day_ok {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Freq::Weekly => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday().into())
+                    + Duration::weeks(step);
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![date.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, w)| *w).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .map(|w| monday + Duration::days(w.num_days_from_monday().into()))
+                    .collect()
+            }
+            Freq::Monthly => {
+                let months = i64::from(date.month0()) + step;
+                let year = date.year() + i32::try_from(months / 12).unwrap_or(0);
+                let Some(first) = NaiveDate::from_ymd_opt(year, (months % 12) as u32 + 1, 1) else {
+                    return Vec::new();
+                };
+                if !self.by_day.is_empty() {
+                    self.by_day
+                        .iter()
+                        .flat_map(|&(nth, weekday)| nth_weekdays(first, weekday, nth))
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    self.by_month_day
+                        .iter()
+                        .filter_map(|&d| month_day(first, d))
+                        .collect()
+                } else {
+                    month_day(first, date.day() as i32).into_iter().collect()
+                }
+            }
+            Freq::Yearly => {
+                let year = date.year() + i32::try_from(step).unwrap_or(0);
+                NaiveDate::from_ymd_opt(year, date.month(), date.day())
+                    .into_iter()
+                    .collect()
+            }
+        };
+        days.sort();
+        days.dedup();
+        days.into_iter().map(|d| d.and_time(time)).collect()
+    }
+}
+
+/// Day `day` of the month containing `date`; negative counts from the end (`-1` = last).
+fn month_day(date: NaiveDate, day: i32) -> Option<NaiveDate> {
+    let first = date.with_day(1)?;
+    let last = (first + Duration::days(31)).with_day(1)? - Duration::days(1);
+    let day = if day < 0 {
+        last.day() as i32 + 1 + day
+    } else {
+        day
+    };
+    first.with_day(u32::try_from(day).ok()?)
+}
+
+/// The `weekday`s of the month starting at `first`: all of them, or only the `nth` (negative
+/// counts from the end).
+fn nth_weekdays(first: NaiveDate, weekday: Weekday, nth: Option<i32>) -> Vec<NaiveDate> {
+    let all: Vec<NaiveDate> = first
+        .iter_days()
+        .take_while(|d| d.month() == first.month())
+        .filter(|d| d.weekday() == weekday)
+        .collect();
+    match nth {
+        None => all,
+        Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => all
+            .len()
+            .checked_sub(n.unsigned_abs() as usize)
+            .and_then(|i| all.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// A content line: `NAME;PARAM=value:VALUE`.
+struct Property<'a> {
+    name: String,
+    params: Vec<(String, &'a str)>,
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        // The value starts at the first colon outside a quoted parameter value.
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(i),
+            _ => None,
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+        let mut parts = head.split(';');
+        let name = parts.next()?.trim().to_ascii_uppercase();
+        let params = parts
+            .filter_map(|p| {
+                let (k, v) = p.split_once('=')?;
+                Some((k.trim().to_ascii_uppercase(), v.trim_matches('"')))
+            })
+            .collect();
+        Some(Self {
+            name,
+            params,
+            value,
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == name).map(|(_, v)| *v)
+    }
+}
+
+/// Joins folded lines (continuations start with a space or tab).
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// The busy events in an iCalendar document, with moved occurrences taken out of the events
+/// they replace.
+fn parse(text: &str) -> Vec<Event> {
+    // Every event, with whether it is busy: a cancelled or free override still replaces its
+    // occurrence.
+    let mut events = Vec::new();
+    let mut unknown_zones = Vec::new();
+    let mut current: Option<(Event, Option<When>, bool)> = None;
+    for line in unfold(text) {
+        let Some(prop) = Property::parse(&line) else {
+            continue;
+        };
+        match (prop.name.as_str(), prop.value.trim()) {
+            ("BEGIN", "VEVENT") => {
+                current = Some((
+                    Event {
+                        uid: String::new(),
+                        summary: String::new(),
+                        start: When {
+                            time: NaiveDateTime::MIN,
+                            zone: Zone::Local,
+                        },
+                        duration: Duration::zero(),
+                        rule: None,
+                        exdates: Vec::new(),
+                        recurrence_id: None,
+                    },
+                    None,
+                    true,
+                ));
+            }
+            ("END", "VEVENT") => {
+                if let Some((mut event, end, busy)) = current.take() {
+                    if event.summary.is_empty() {
+                        event.summary = "Busy".to_string();
+                    }
+                    if let Some(end) = end {
+                        // `DTEND` may be written in another zone than `DTSTART`.
+                        event.duration = match (end.to_local(), event.start.to_local()) {
+                            (Some(end), Some(start)) => end - start,
+                            _ => end.time - event.start.time,
+                        };
+                    }
+                    let busy = busy && event.start.time != NaiveDateTime::MIN;
+                    events.push((event, busy));
+                }
+            }
+            _ => {
+                let Some((event, end, busy)) = current.as_mut() else {
+                    continue;
+                };
+                match prop.name.as_str() {
+                    "UID" => event.uid = prop.value.to_string(),
+                    "SUMMARY" => event.summary = unescape(prop.value),
+                    "DTSTART" => match parse_when(&prop, prop.value, &mut unknown_zones) {
+                        Some(when) => event.start = when,
+                        // All-day events do not block time.
+                        None => *busy = false,
+                    },
+                    "DTEND" => *end = parse_when(&prop, prop.value, &mut unknown_zones),
+                    "DURATION" => {
+                        if let Some(d) = parse_duration(prop.value) {
+                            event.duration = d;
+                        }
+                    }
+                    "RRULE" => event.rule = parse_rule(prop.value),
+                    "EXDATE" => event.exdates.extend(
+                        prop.value
+                            .split(',')
+                            .filter_map(|v| parse_when(&prop, v, &mut unknown_zones)),
+                    ),
+                    "RECURRENCE-ID" => {
+                        event.recurrence_id = parse_when(&prop, prop.value, &mut unknown_zones)
+                    }
+                    "TRANSP" if prop.value.trim() == "TRANSPARENT" => *busy = false,
+                    "STATUS" if prop.value.trim() == "CANCELLED" => *busy = false,
+                    _ => {}
+                }
+            }
+        }
+    }
+    // A moved occurrence replaces the original one in its recurring event.
+    let moved: Vec<(String, When)> = events
+        .iter()
+        .filter_map(|(e, _)| Some((e.uid.clone(), e.recurrence_id?)))
+        .collect();
+    let mut events: Vec<Event> = events
+        .into_iter()
+        .filter_map(|(event, busy)| busy.then_some(event))
+        .collect();
+    for event in events.iter_mut().filter(|e| e.rule.is_some()) {
+        event.exdates.extend(
+            moved
+                .iter()
+                .filter(|(uid, _)| *uid == event.uid)
+                .map(|(_, time)| *time),
+        );
+    }
+    events.retain(|e| e.duration > Duration::zero());
+    events
+}
+
+/// A `DATE-TIME` `value` of `prop` in the zone named by its `TZID`; `None` for all-day `DATE`
+/// values and malformed input. `TZID`s that cannot be resolved are warned about once per
+/// document, in `unknown_zones`.
+fn parse_when(prop: &Property, value: &str, unknown_zones: &mut Vec<String>) -> Option<When> {
+    if prop.param("VALUE") == Some("DATE") {
+        return None;
+    }
+    let mut when = parse_date_time(value.trim())?;
+    if let (Zone::Local, Some(tzid)) = (when.zone, prop.param("TZID")) {
+        match time_zone(tzid) {
+            Some(tz) => when.zone = Zone::Tz(tz),
+            None if !unknown_zones.iter().any(|z| z == tzid) => {
+                log::warn!("Unknown calendar time zone {tzid:?}; its times are read as local time");
+                unknown_zones.push(tzid.to_string());
+            }
+            None => {}
+        }
+    }
+    Some(when)
+}
+
+/// The IANA time zone `tzid` names, also behind a vendor prefix such as
+/// `/mozilla.org/20070129_1/Europe/Berlin`.
+fn time_zone(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim();
+    std::iter::once(tzid)
+        .chain(tzid.match_indices('/').map(|(i, _)| &tzid[i + 1..]))
+        .find_map(|name| name.parse().ok())
+}
+
+fn parse_date_time(value: &str) -> Option<When> {
+    let (value, zone) = match value.strip_suffix('Z') {
+        Some(v) => (v, Zone::Utc),
+        None => (value, Zone::Local),
+    };
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some(When { time, zone })
+}
+
+/// `P1W`, `PT1H30M`, `P1DT2H`…
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim().trim_start_matches('+');
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(v) => (true, v),
+        None => (false, value),
+    };
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match unit {
+                    'W' => Duration::weeks(n),
+                    'D' => Duration::days(n),
+                    'H' => Duration::hours(n),
+                    'M' => Duration::minutes(n),
+                    'S' => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20261231T000000Z`; `None` for unsupported
+/// frequencies.
+fn parse_rule(value: &str) -> Option<Rule> {
+    let mut rule = Rule {
+        freq: Freq::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+    };
+    let mut freq = None;
+    for part in value.trim().split(';') {
+        let Some((key, val)) = part.split_once('=') else {
+            continue;
+        };
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                freq = match val {
+                    "DAILY" => Some(Freq::Daily),
+                    "WEEKLY" => Some(Freq::Weekly),
+                    "MONTHLY" => Some(Freq::Monthly),
+                    "YEARLY" => Some(Freq::Yearly),
+                    _ => None,
+                }
+            }
+            "INTERVAL" => rule.interval = val.parse().unwrap_or(1),
+            "COUNT" => rule.count = val.parse().ok(),
+            "UNTIL" => {
+                rule.until = parse_date_time(val).or_else(|| {
+                    let date = NaiveDate::parse_from_str(val, "%Y%m%d").ok()?;
+                    Some(When {
+                        time: date.and_hms_opt(23, 59, 59)?,
+                        zone: Zone::Local,
+                    })
+                })
+            }
+            "BYDAY" => {
+                rule.by_day = val
+                    .split(',')
+                    .filter_map(|d| {
+                        let d = d.trim();
+                        let (nth, day) = d.split_at(d.len().checked_sub(2)?);
+                        let weekday = match day {
+                            "MO" => Weekday::Mon,
+                            "TU" => Weekday::Tue,
+                            "WE" => Weekday::Wed,
+                            "TH" => Weekday::Thu,
+                            "FR" => Weekday::Fri,
+                            "SA" => Weekday::Sat,
+                            "SU" => Weekday::Sun,
+                            _ => return None,
+                        };
+                        Some((nth.parse().ok(), weekday))
+                    })
+                    .collect()
+            }
+            "BYMONTHDAY" => {
+                rule.by_month_day = val.split(',').filter_map(|d| d.parse().ok()).collect()
+            }
+            _ => {}
+        }
+    }
+    rule.freq = freq?;
+    Some(rule)
+}
+
+/// Undoes iCalendar text escaping (`\,`, `\;`, `\n`, `\\`).
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n' | 'N')) => {
+                out.push(' ');
+                chars.next();
+            }
+            ('\\', Some(next)) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    /// Local (floating) wall-clock time, in UTC.
+    fn floating(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Local
+            .with_ymd_and_hms(y, mo, d, h, mi, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    /// Start times of the occurrences of the events in `body` that overlap `from..to`.
+    fn starts(body: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let ics = format!("BEGIN:VCALENDAR\r\n{body}END:VCALENDAR\r\n");
+        let mut starts: Vec<DateTime<Utc>> = parse(&ics)
+            .iter()
+            .flat_map(|e| e.occurrences(from.with_timezone(&Local), to.with_timezone(&Local)))
+            .map(|o| o.start.with_timezone(&Utc))
+            .collect();
+        starts.sort();
+        starts
+    }
+
+    #[test]
+    fn weekly_rule_with_days_and_count() {
+        let body = "BEGIN:VEVENT\r\n\
+            UID:standup\r\n\
+            DTSTART:20261005T093000\r\n\
+            DURATION:PT15M\r\n\
+            RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR;COUNT=5\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)),
+            [
+                floating(2026, 10, 5, 9, 30),
+                floating(2026, 10, 7, 9, 30),
+                floating(2026, 10, 9, 9, 30),
+                floating(2026, 10, 12, 9, 30),
+                floating(2026, 10, 14, 9, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_rule_with_interval_until_and_exdate() {
+        let body = "BEGIN:VEVENT\r\n\
+            UID:focus\r\n\
+            DTSTART:20261001T140000Z\r\n\
+            DTEND:20261001T150000Z\r\n\
+            RRULE:FREQ=DAILY;INTERVAL=2;UNTIL=20261009T140000Z\r\n\
+            EXDATE:20261005T140000Z\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 9, 1, 0, 0), utc(2026, 12, 1, 0, 0)),
+            [
+                utc(2026, 10, 1, 14, 0),
+                utc(2026, 10, 3, 14, 0),
+                utc(2026, 10, 7, 14, 0),
+                utc(2026, 10, 9, 14, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_rule_on_the_last_friday() {
+        let body = "BEGIN:VEVENT\r\n\
+            UID:review\r\n\
+            DTSTART:20260925T160000Z\r\n\
+            DURATION:PT1H\r\n\
+            RRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 9, 1, 0, 0), utc(2027, 3, 1, 0, 0)),
+            [
+                utc(2026, 9, 25, 16, 0),
+                utc(2026, 10, 30, 16, 0),
+                utc(2026, 11, 27, 16, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn moved_occurrence_replaces_the_original() {
+        let body = "BEGIN:VEVENT\r\n\
+            UID:one-on-one\r\n\
+            DTSTART:20261006T100000Z\r\n\
+            DURATION:PT30M\r\n\
+            RRULE:FREQ=WEEKLY;COUNT=3\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:one-on-one\r\n\
+            RECURRENCE-ID:20261013T100000Z\r\n\
+            DTSTART:20261014T150000Z\r\n\
+            DURATION:PT30M\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)),
+            [
+                utc(2026, 10, 6, 10, 0),
+                utc(2026, 10, 14, 15, 0),
+                utc(2026, 10, 20, 10, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn cancelled_or_free_override_removes_the_occurrence() {
+        let body = "BEGIN:VEVENT\r\n\
+            UID:one-on-one\r\n\
+            DTSTART:20261006T100000Z\r\n\
+            DURATION:PT30M\r\n\
+            RRULE:FREQ=WEEKLY;COUNT=3\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:one-on-one\r\n\
+            RECURRENCE-ID:20261013T100000Z\r\n\
+            DTSTART:20261013T100000Z\r\n\
+            DURATION:PT30M\r\n\
+            STATUS:CANCELLED\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:one-on-one\r\n\
+            RECURRENCE-ID:20261020T100000Z\r\n\
+            DTSTART:20261020T100000Z\r\n\
+            DURATION:PT30M\r\n\
+            TRANSP:TRANSPARENT\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)),
+            [utc(2026, 10, 6, 10, 0)]
+        );
+    }
+
+    #[test]
+    fn exdate_and_recurrence_id_match_as_instants_in_any_zone() {
+        // 10:00 in Berlin is 08:00 UTC in summer time.
+        let body = "BEGIN:VEVENT\r\n\
+            UID:sync\r\n\
+            DTSTART;TZID=Europe/Berlin:20261005T100000\r\n\
+            DURATION:PT30M\r\n\
+            RRULE:FREQ=DAILY;COUNT=3\r\n\
+            EXDATE:20261006T080000Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:sync\r\n\
+            RECURRENCE-ID;TZID=America/New_York:20261007T040000\r\n\
+            DTSTART:20261007T120000Z\r\n\
+            DURATION:PT30M\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)),
+            [utc(2026, 10, 5, 8, 0), utc(2026, 10, 7, 12, 0)]
+        );
+    }
+
+    #[test]
+    fn dtend_in_another_zone_gives_the_real_duration() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;TZID=Europe/Berlin:20261005T100000\r\n\
+            DTEND:20261005T090000Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let events = parse(ics);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].duration, Duration::hours(1));
+    }
+
+    #[test]
+    fn tzid_times_keep_their_wall_clock_time_across_daylight_saving() {
+        // Central European Summer Time ends on 25 October 2026.
+        let body = "BEGIN:VEVENT\r\n\
+            UID:planning\r\n\
+            DTSTART;TZID=Europe/Berlin:20261019T090000\r\n\
+            DTEND;TZID=Europe/Berlin:20261019T100000\r\n\
+            RRULE:FREQ=WEEKLY;COUNT=2\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)),
+            [utc(2026, 10, 19, 7, 0), utc(2026, 10, 26, 8, 0)]
+        );
+    }
+
+    #[test]
+    fn tzid_behind_a_vendor_prefix_is_resolved() {
+        let body = "BEGIN:VEVENT\r\n\
+            DTSTART;TZID=\"/mozilla.org/20070129_1/America/New_York\":20261016T090000\r\n\
+            DURATION:PT1H\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)),
+            [utc(2026, 10, 16, 13, 0)]
+        );
+    }
+
+    #[test]
+    fn unknown_tzid_is_read_as_local_time() {
+        let body = "BEGIN:VEVENT\r\n\
+            DTSTART;TZID=W. Europe Standard Time:20261016T090000\r\n\
+            DURATION:PT1H\r\n\
+            END:VEVENT\r\n";
+        assert_eq!(
+            starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)),
+            [floating(2026, 10, 16, 9, 0)]
+        );
+    }
+
+    #[test]
+    fn all_day_free_and_cancelled_events_are_not_busy() {
+        let body = "BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20261016\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20261016T090000Z\r\n\
+            DURATION:PT1H\r\n\
+            TRANSP:TRANSPARENT\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20261016T110000Z\r\n\
+            DURATION:PT1H\r\n\
+            STATUS:CANCELLED\r\n\
+            END:VEVENT\r\n";
+        assert!(starts(body, utc(2026, 10, 1, 0, 0), utc(2026, 11, 1, 0, 0)).is_empty());
+    }
+}
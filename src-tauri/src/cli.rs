@@ -0,0 +1,156 @@
+//! Command-line flags and subcommands, handled before the app starts.
+//!
+//! - `--set KEY=VALUE` (repeatable) — overrides a setting for this run without touching
+//!   `config.toml`, e.g. `--set work_interval_minutes=1 --set schedule.enabled=false`; see
+//!   [`crate::config_layers`].
+//! - `twenty20 config check [PATH]` — validates `config.toml` (or `PATH`) without changing it
+//!   and prints every setting that would be adjusted. Exits with 0 when the file is clean, 1
+//!   when settings would be adjusted and 2 when it cannot be read or parsed.
+
+use crate::config::AppConfig;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Records the `--set` flags in `args` (without the program name) and runs the subcommand
+/// that follows them, returning its exit code, or `None` if there is none and the app should
+/// start.
+pub fn run(args: &[String]) -> Option<i32> {
+    let mut overrides = toml::Table::new();
+    let mut rest = args;
+    loop {
+        let assignment = match rest {
+            [flag, value, tail @ ..] if flag == "--set" => {
+                rest = tail;
+                value.as_str()
+            }
+            [flag, tail @ ..] if flag.starts_with("--set=") => {
+                rest = tail;
+                &flag["--set=".len()..]
+            }
+            _ => break,
+        };
+        let Some((key, value)) = assignment.split_once('=') else {
+            return Some(usage());
+        };
+        crate::config_layers::insert_dotted(
+            &mut overrides,
+            key.trim(),
+            crate::config_layers::parse_value(value.trim()),
+        );
+    }
+    crate::config_layers::set_command_line(overrides);
+
+    match rest {
+        [group, cmd, rest @ ..] if group == "config" && cmd == "check" => {
+            let path = match rest {
+                [] => AppConfig::config_path(),
+                [path] => PathBuf::from(path),
+                _ => return Some(usage()),
+            };
+            Some(config_check(&path, &mut io::stdout(), &mut io::stderr()))
+        }
+        [group, ..] if group == "config" => Some(usage()),
+        _ => None,
+    }
+}
+
+fn usage() -> i32 {
+    eprintln!("usage: twenty20 [--set KEY=VALUE]... [config check [PATH]]");
+    2
+}
+
+/// Runs `config check` on `path`, printing the report to `out` and errors to `err`.
+fn config_check(path: &Path, out: &mut impl Write, err: &mut impl Write) -> i32 {
+    match AppConfig::check(path) {
+        Err(e) => {
+            let _ = writeln!(err, "{e}");
+            2
+        }
+        Ok(diagnostics) if diagnostics.is_empty() => {
+            let _ = writeln!(out, "{}: OK", path.display());
+            0
+        }
+        Ok(diagnostics) => {
+            for d in &diagnostics {
+                let _ = writeln!(out, "{}: {d}", path.display());
+            }
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    /// Runs `config check` on a file with `contents` (none if `None`), returning the exit
+    /// code, stdout and stderr.
+    fn check(test: &str, contents: Option<&str>) -> (i32, String, String) {
+        let dir = std::env::temp_dir().join(format!("twenty20-cli-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        if let Some(contents) = contents {
+            std::fs::write(&path, contents).unwrap();
+        }
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let code = config_check(&path, &mut out, &mut err);
+        let out = String::from_utf8(out).unwrap();
+        let err = String::from_utf8(err).unwrap();
+        (code, out.replace(&path.display().to_string(), "PATH"), err)
+    }
+
+    #[test]
+    fn set_without_a_value_is_rejected() {
+        for bad in [
+            &["--set", "work_interval_minutes"][..],
+            &["--set=work_interval_minutes"],
+            &["--set", "a=1", "--set", "strict_mode"],
+        ] {
+            assert_eq!(run(&args(bad)), Some(2), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn config_check_passes_a_clean_file() {
+        let (code, out, err) = check("clean", Some("work_interval_minutes = 20\n"));
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "PATH: OK\n", ""));
+    }
+
+    #[test]
+    fn config_check_lists_adjusted_settings() {
+        let (code, out, err) = check(
+            "adjusted",
+            Some("work_interval_minutes = 0\nsound = \"gong\"\n"),
+        );
+        assert_eq!(code, 1);
+        assert_eq!(err, "");
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2, "{out}");
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("PATH: sound: \"gong\" → \"off\" (")));
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("PATH: work_interval_minutes: 0 → 1 (")));
+    }
+
+    #[test]
+    fn config_check_fails_on_a_file_it_cannot_read() {
+        let (code, out, err) = check("missing", None);
+        assert_eq!((code, out.as_str()), (2, ""));
+        assert!(err.starts_with("Could not read "), "{err}");
+
+        let (code, out, err) = check("not-toml", Some("work_interval_minutes = \n"));
+        assert_eq!((code, out.as_str()), (2, ""));
+        assert!(err.contains("is not valid TOML"), "{err}");
+
+        let (code, _, err) = check("wrong-type", Some("strict_mode = \"yes\"\n"));
+        assert_eq!(code, 2);
+        assert!(err.starts_with("Invalid setting in "), "{err}");
+    }
+}
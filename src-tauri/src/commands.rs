@@ -1,15 +1,24 @@
-use crate::config::AppConfig;
+use crate::config::{AppConfig, Diagnostic};
+use crate::overlay::OverlayConfig;
 use crate::strict_mode;
-use crate::timer::SharedTimerState;
+use crate::themes::ThemePack;
+use crate::timer::{BreakInfo, BreakKind, Input, SharedTimerState};
 use serde_json::Value;
 use tauri::menu::Menu;
 use tauri::Wry;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, State};
 
 /// Tauri state container.
 pub struct AppState {
     pub timer: SharedTimerState,
+    /// Effective settings: the base config with the profile in effect applied.
     pub config: std::sync::Mutex<AppConfig>,
+    /// Settings as written in `config.toml`.
+    pub base_config: std::sync::Mutex<AppConfig>,
+    /// Name of the profile in effect, if any.
+    pub active_profile: std::sync::Mutex<Option<String>>,
+    /// Settings in `config.toml` that had to be adjusted when it was last loaded.
+    pub config_diagnostics: std::sync::Mutex<Vec<Diagnostic>>,
     pub tray_menu: std::sync::Mutex<Option<Menu<Wry>>>,
 }
 
@@ -21,15 +30,47 @@ macro_rules! lock {
 }
 
 /// Returns overlay configuration required by the frontend overlay initializer.
+///
+/// Describes the break in progress, or the next one when no break is active.
 #[tauri::command]
 pub fn get_overlay_config(label: Option<String>, state: State<AppState>) -> Value {
     let config = lock!(state.config);
+    let info = {
+        let ts = lock!(state.timer);
+        ts.current_break().cloned().unwrap_or_else(|| {
+            let kind = ts.state.next_break_kind(config.long_break_every);
+            BreakInfo {
+                kind,
+                duration: match kind {
+                    BreakKind::Long => config.long_break_duration_seconds,
+                    _ => config.break_duration_seconds,
+                },
+                reminders: Vec::new(),
+                snooze_seconds: ts.snooze_seconds(),
+            }
+        })
+    };
+    let overlay = OverlayConfig::new(&info, config.strict_mode, &config.overlay_theme);
     // The primary overlay window is always labelled "overlay_0".
     let is_primary = label.as_deref() == Some("overlay_0");
     serde_json::json!({
-        "break_duration": config.break_duration_seconds,
+        "break_duration": overlay.break_duration,
         "is_primary": is_primary,
-        "is_strict_mode": config.strict_mode,
+        "is_strict_mode": overlay.is_strict_mode,
+        "break_kind": overlay.break_kind,
+        "headline": overlay.headline,
+        "message": overlay.message,
+        "snooze_seconds": overlay.snooze_seconds,
+        "theme": overlay.theme,
+    })
+}
+
+/// Returns the theme pack called `name` for the overlay: its CSS variables and background image.
+#[tauri::command]
+pub fn get_theme_pack(name: String) -> Result<ThemePack, String> {
+    crate::themes::load(&crate::themes::themes_dir(), &name).map_err(|e| {
+        log::warn!("Cannot load theme pack {name:?}: {e}");
+        e
     })
 }
 
@@ -37,16 +78,18 @@ pub fn get_overlay_config(label: Option<String>, state: State<AppState>) -> Valu
 #[tauri::command]
 pub fn force_skip_break(app: AppHandle, state: State<AppState>) -> Result<(), String> {
     strict_mode::log_force_skip();
-    strict_mode::disable_strict_input_suppression();
-    crate::overlay::close_overlays(&app);
-    // Reset timer to full interval after force-skip.
-    {
-        let mut ts = lock!(state.timer);
-        ts.seconds_remaining = ts.work_interval_seconds;
-        ts.is_paused = false;
-        ts.pause_reason = None;
-    }
-    let _ = app.emit("break:end", serde_json::json!({ "force_skipped": true }));
+    // The scheduler closes the overlays, lifts input suppression and resets the timer to a
+    // full interval.
+    let effects = lock!(state.timer).handle(Input::ForceSkip);
+    crate::apply_effects(&app, effects);
+    Ok(())
+}
+
+/// Postpones the break in progress from the overlay's snooze button.
+#[tauri::command]
+pub fn snooze_break(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    let effects = lock!(state.timer).snooze()?;
+    crate::apply_effects(&app, effects);
     Ok(())
 }
 
@@ -1,59 +1,688 @@
+use crate::config_layers::{Layers, Source};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Current config schema version, written as `config_version`. Files without the key are
+/// version 1; each of the [`MIGRATIONS`] adds one.
+pub const CONFIG_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// Upgrade steps applied in order by [`AppConfig::load`]; entry `n - 1` upgrades a version `n`
+/// table to version `n + 1`. Empty so far: settings have only been added, and a missing
+/// setting takes its default.
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[];
+
+/// Upgrades `table` to [`CONFIG_VERSION`] and returns the version it was written with.
+///
+/// Tables from a newer release are left untouched; unknown keys are ignored when parsing, and
+/// [`AppConfig::save`] refuses to write them back.
+fn migrate(table: &mut toml::Table) -> u32 {
+    upgrade(table, MIGRATIONS)
+}
+
+/// [`migrate`] through `steps`, the last of which upgrades to version `steps.len() + 1`.
+fn upgrade(table: &mut toml::Table, steps: &[fn(&mut toml::Table)]) -> u32 {
+    let current = steps.len() as u32 + 1;
+    let from = table
+        .get("config_version")
+        .and_then(toml::Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(1)
+        .max(1);
+    if from > current {
+        log::warn!(
+            "config.toml is from a newer release (version {from}); unknown settings are ignored \
+             and changes are not saved"
+        );
+        return from;
+    }
+    for step in &steps[(from - 1) as usize..] {
+        step(table);
+    }
+    table.insert(
+        "config_version".into(),
+        toml::Value::Integer(current.into()),
+    );
+    from
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppConfig {
+    /// Schema version of the file; see [`CONFIG_VERSION`].
+    pub config_version: u32,
     /// Work interval in minutes (1–60). Default: 20.
     pub work_interval_minutes: u32,
     /// Break duration in seconds (5–60). Default: 20.
     pub break_duration_seconds: u32,
+    /// Take a long break instead of every Nth short break (0 = off, otherwise 2–12). Default: 0.
+    pub long_break_every: u32,
+    /// Long break duration in seconds (60–1800). Default: 300.
+    pub long_break_duration_seconds: u32,
     /// Strict mode — disables skip/pause controls.
     pub strict_mode: bool,
-    /// Overlay theme: "dark" | "light" | "nature".
-    pub overlay_theme: String,
-    /// Sound: "off" | "chime" | "whitenoise".
-    pub sound: String,
+    /// Overlay theme: a built-in [`OverlayTheme`] or an installed theme pack (see
+    /// [`crate::themes`]), by name. Default: `"dark"`.
+    pub overlay_theme: ThemeChoice,
+    /// Sound played when a break starts. Default: `"off"`.
+    pub sound: SoundChoice,
     /// Launch at login.
     pub launch_at_login: bool,
-    /// Pre-break warning lead time in seconds. 0 = off.
-    pub pre_warning_seconds: u32,
+    /// Pre-break warning lead time, written in seconds (0 = off, otherwise 30–120). Default: 60.
+    #[serde(rename = "pre_warning_seconds")]
+    pub pre_warning: PreWarning,
     /// Meeting detection auto-pause.
     pub meeting_detection: bool,
+    /// Conferencing apps whose running signals a meeting.
+    pub meeting_apps: Vec<MeetingApp>,
+    /// Browser window titles that signal a call.
+    pub meeting_titles: Vec<MeetingTitle>,
+    /// Consecutive 30-second polls that must see a meeting before the timer pauses (1–10).
+    /// Default: 2.
+    pub meeting_start_polls: u32,
+    /// Consecutive polls that must see no meeting before the timer resumes (1–10). Default: 2.
+    pub meeting_end_polls: u32,
+    /// No break starts sooner than this many seconds after a meeting ends (max 600).
+    /// Default: 60.
+    pub meeting_grace_seconds: u32,
+    /// After a meeting at least this many minutes long, a catch-up break starts as soon as the
+    /// grace period is over (0 = off, otherwise 5–240). Default: 30.
+    pub meeting_catch_up_minutes: u32,
+    /// Hold a break that falls due while presenting or sharing the screen.
+    pub presentation_detection: bool,
+    /// Minutes a break may be held for a presentation before a notification suggests one
+    /// instead (1–120). Default: 15.
+    pub presentation_max_deferral_minutes: u32,
+    /// Pause the work timer after this many seconds without input (0 = off, otherwise
+    /// 30–1800). Absences longer than the break count as a break. Default: 120.
+    pub idle_pause_seconds: u32,
+    /// Snooze length in minutes (1–15). Default: 5.
+    pub snooze_minutes: u32,
+    /// Snoozes allowed per work cycle (0 = snoozing off, max 5). Default: 2.
+    pub snooze_limit: u32,
+    /// Each snooze in a cycle halves the next one (down to one minute). Default: false.
+    pub snooze_shrinks: bool,
+    /// Tray skips and pauses allowed per day, reset at local midnight (0 = unlimited, max 20).
+    /// Default: 0.
+    pub daily_skip_budget: u32,
+    /// What happens to the cycle after system sleep or a screen lock. Default: `reset_if_rested`.
+    pub sleep_policy: SleepPolicy,
+    /// Working hours; breaks only run inside them when enabled.
+    pub schedule: WorkSchedule,
+    /// Local iCalendar files whose events pause the timer and move breaks.
+    pub calendar: CalendarConfig,
+    /// Profile picked from the tray; `None` selects automatically (see [`ProfileConfig`]).
+    pub active_profile: Option<String>,
+    /// Named profiles overlaying these settings.
+    pub profiles: Vec<ProfileConfig>,
+    /// Additional reminder schedules (stretch, hydration, posture…) run alongside the eye break.
+    pub reminders: Vec<ReminderConfig>,
+    /// Per-application rules, checked against the focused application every 30 seconds.
+    pub app_rules: Vec<AppRule>,
+    /// Values replaced while parsing, reported by the next [`AppConfig::validate`].
+    #[serde(skip)]
+    rejected: Vec<Diagnostic>,
+    /// Policy, environment and command-line layers applied on top of `config.toml`.
+    #[serde(skip)]
+    layers: Arc<Layers>,
+}
+
+/// A setting chosen from a fixed set of names.
+pub trait Choice: Copy + Default + 'static {
+    /// Every choice, in the order offered in the settings window.
+    const ALL: &'static [Self];
+
+    /// The name written to `config.toml`.
+    fn name(self) -> &'static str;
+
+    /// The choice called `name`, ignoring case and surrounding whitespace.
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Built-in colour scheme of the break overlay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayTheme {
+    #[default]
+    Dark,
+    Light,
+    Nature,
+}
+
+impl Choice for OverlayTheme {
+    const ALL: &'static [Self] = &[Self::Dark, Self::Light, Self::Nature];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+            Self::Nature => "nature",
+        }
+    }
+}
+
+/// The `overlay_theme` setting: a built-in theme, or the name of a theme pack (see
+/// [`crate::themes`]). Built-in names win, since no pack may take one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ThemeChoice {
+    Builtin(OverlayTheme),
+    Pack(String),
+}
+
+impl ThemeChoice {
+    /// The name written to `config.toml`.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Builtin(theme) => theme.name(),
+            Self::Pack(name) => name,
+        }
+    }
+}
+
+impl Default for ThemeChoice {
+    fn default() -> Self {
+        Self::Builtin(OverlayTheme::default())
+    }
+}
+
+impl From<String> for ThemeChoice {
+    fn from(name: String) -> Self {
+        match OverlayTheme::from_name(&name) {
+            Some(theme) => Self::Builtin(theme),
+            None => Self::Pack(name.trim().to_string()),
+        }
+    }
+}
+
+impl From<ThemeChoice> for String {
+    fn from(choice: ThemeChoice) -> Self {
+        choice.name().to_string()
+    }
+}
+
+/// Sound played when a break starts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundChoice {
+    #[default]
+    Off,
+    Chime,
+    WhiteNoise,
+}
+
+impl Choice for SoundChoice {
+    const ALL: &'static [Self] = &[Self::Off, Self::Chime, Self::WhiteNoise];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Chime => "chime",
+            Self::WhiteNoise => "whitenoise",
+        }
+    }
+}
+
+/// Lead time of the notification shown before a break, written as `pre_warning_seconds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum PreWarning {
+    Off,
+    Seconds(u32),
+}
+
+impl PreWarning {
+    /// Lead times offered in the settings window.
+    pub const CHOICES: [Self; 5] = [
+        Self::Off,
+        Self::Seconds(30),
+        Self::Seconds(60),
+        Self::Seconds(90),
+        Self::Seconds(120),
+    ];
+
+    /// Lead time in seconds; 0 when off.
+    pub fn seconds(self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::Seconds(s) => s,
+        }
+    }
+
+    /// Label shown in the settings window, e.g. `"60s"`.
+    pub fn label(self) -> String {
+        match self {
+            Self::Off => "Off".into(),
+            Self::Seconds(s) => format!("{s}s"),
+        }
+    }
+}
+
+impl Default for PreWarning {
+    fn default() -> Self {
+        Self::Seconds(60)
+    }
+}
+
+impl From<u32> for PreWarning {
+    fn from(seconds: u32) -> Self {
+        match seconds {
+            0 => Self::Off,
+            s => Self::Seconds(s),
+        }
+    }
+}
+
+impl From<PreWarning> for u32 {
+    fn from(warning: PreWarning) -> Self {
+        warning.seconds()
+    }
+}
+
+/// Weekly working-hours schedule, configured as a `[schedule]` table.
+///
+/// Each day lists local-time ranges such as `"09:00-12:30"`; a day left out or given an empty
+/// list is a day off, so listing every day as `[]` means no working days. A table that lists
+/// no days at all keeps the default Monday–Friday week.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkSchedule {
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub days: WeekDays,
+}
+
+impl Default for WorkSchedule {
+    /// Disabled; Monday–Friday 09:00–18:00 once switched on.
+    fn default() -> Self {
+        let weekday = || vec!["09:00-18:00".to_string()];
+        Self {
+            enabled: false,
+            days: WeekDays {
+                monday: weekday(),
+                tuesday: weekday(),
+                wednesday: weekday(),
+                thursday: weekday(),
+                friday: weekday(),
+                saturday: Vec::new(),
+                sunday: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Calendar awareness, configured as the `[calendar]` table (see [`crate::calendar`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// iCalendar (`.ics`) files exported or synced by other tools; `~` is the home directory.
+    pub files: Vec<String>,
+    /// Pause the work timer while a busy event is in progress.
+    pub pause_during_events: bool,
+    /// Move a break that would run into an event up to this many minutes earlier, so it ends
+    /// as the event starts (0 = off, max 30).
+    pub pull_forward_minutes: u32,
+    /// CalDAV calendars whose free/busy is fetched, as `[[calendar.caldav]]` tables.
+    pub caldav: Vec<CalDavCalendar>,
+    /// Minutes between CalDAV fetches (5–240).
+    pub refresh_minutes: u32,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            pause_during_events: true,
+            pull_forward_minutes: 10,
+            caldav: Vec::new(),
+            refresh_minutes: 15,
+        }
+    }
+}
+
+/// A CalDAV calendar collection, read through its free/busy (see [`crate::caldav`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalDavCalendar {
+    /// Shown in the tray during its busy periods, e.g. "Work" for "Paused — Work until 10:00";
+    /// free/busy carries no event titles.
+    pub name: String,
+    /// URL of the calendar collection, e.g.
+    /// `"https://dav.example.com/calendars/alice/work/"`.
+    pub url: String,
+    pub username: String,
+    /// Leave empty to take the user name and password from `~/.netrc` instead.
+    pub password: String,
+}
+
+/// Local-time ranges (`"HH:MM-HH:MM"`) per weekday; days left out have none.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeekDays {
+    pub monday: Vec<String>,
+    pub tuesday: Vec<String>,
+    pub wednesday: Vec<String>,
+    pub thursday: Vec<String>,
+    pub friday: Vec<String>,
+    pub saturday: Vec<String>,
+    pub sunday: Vec<String>,
+}
+
+impl WeekDays {
+    /// The days in order, Monday first.
+    pub fn all(&self) -> [&Vec<String>; 7] {
+        [
+            &self.monday,
+            &self.tuesday,
+            &self.wednesday,
+            &self.thursday,
+            &self.friday,
+            &self.saturday,
+            &self.sunday,
+        ]
+    }
+
+    /// Drops ranges that are not `"HH:MM-HH:MM"`, reporting them as `{field}.{day}`.
+    fn retain_valid(&mut self, field: &str, diagnostics: &mut Vec<Diagnostic>) {
+        for (name, day) in [
+            ("monday", &mut self.monday),
+            ("tuesday", &mut self.tuesday),
+            ("wednesday", &mut self.wednesday),
+            ("thursday", &mut self.thursday),
+            ("friday", &mut self.friday),
+            ("saturday", &mut self.saturday),
+            ("sunday", &mut self.sunday),
+        ] {
+            day.retain(|r| {
+                let valid = crate::schedule::TimeRange::parse(r).is_some();
+                if !valid {
+                    diagnostics.push(Diagnostic::new(
+                        format!("{field}.{name}"),
+                        format!("{r:?}"),
+                        "removed",
+                        "expected \"HH:MM-HH:MM\" with the start before the end",
+                    ));
+                }
+                valid
+            });
+        }
+    }
+}
+
+/// A named set of overrides, configured as a `[[profiles]]` table.
+///
+/// Any top-level setting may be overridden; everything else comes from the base config.
+/// A profile with `networks` or `hours` is activated automatically while they match, unless
+/// another profile is picked from the tray.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileConfig {
+    pub name: String,
+    /// Wi-Fi SSIDs / network connection names that activate this profile.
+    pub networks: Vec<String>,
+    /// Local-time ranges during which this profile is active.
+    pub hours: Option<WeekDays>,
+    /// The overridden settings.
+    #[serde(flatten)]
+    pub overrides: toml::Table,
+}
+
+/// What happens to the work cycle when the system wakes or the screen is unlocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SleepPolicy {
+    /// Always start a fresh cycle.
+    Reset,
+    /// Carry on where the countdown (or break) left off.
+    Resume,
+    /// Start a fresh cycle only if the time away covered the next break; otherwise resume.
+    ResetIfRested,
+}
+
+/// How a reminder is delivered when it falls due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderDelivery {
+    /// Full-screen overlay, like the eye break.
+    Overlay,
+    /// System notification only.
+    Notification,
+}
+
+/// A conferencing app that signals a meeting while it is running (and not hidden, on macOS),
+/// configured as a `[[meeting_apps]]` table in `config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingApp {
+    /// Shown in the tray, e.g. "Zoom" for "Paused — Zoom meeting".
+    pub name: String,
+    /// Application identifier: the bundle ID on macOS, e.g. `"us.zoom.xos"`; the process name
+    /// on Linux, e.g. `"zoom"`.
+    pub id: String,
+}
+
+impl MeetingApp {
+    fn defaults() -> Vec<Self> {
+        [
+            ("Zoom", "us.zoom.xos"),
+            ("Microsoft Teams", "com.microsoft.teams2"),
+            ("Webex", "Cisco-Systems.Spark"),
+            ("FaceTime", "com.apple.FaceTime"),
+            ("Discord", "com.hnc.Discord"),
+            ("Zoom", "zoom"),
+            ("Microsoft Teams", "teams-for-linux"),
+            ("Skype", "skypeforlinux"),
+        ]
+        .into_iter()
+        .map(|(name, id)| Self {
+            name: name.to_string(),
+            id: id.to_string(),
+        })
+        .collect()
+    }
+}
+
+/// A browser window title that signals a call, configured as a `[[meeting_titles]]` table in
+/// `config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingTitle {
+    /// Shown in the tray, e.g. "Google Meet" for "Paused — Google Meet meeting".
+    pub name: String,
+    /// Regular expression searched in the front window title of each browser.
+    pub pattern: String,
+}
+
+impl MeetingTitle {
+    fn defaults() -> Vec<Self> {
+        [
+            ("Google Meet", "Meet \u{2013}"),
+            ("Google Meet", "Meet - "),
+            ("Google Meet", "Google Meet"),
+            ("Zoom", "Zoom Meeting"),
+            ("Microsoft Teams", "Microsoft Teams"),
+            ("Browser", "On a call"),
+        ]
+        .into_iter()
+        .map(|(name, pattern)| Self {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+        })
+        .collect()
+    }
+}
+
+/// What happens while an application matching an [`AppRule`] is focused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppAction {
+    /// Pause the work timer.
+    #[default]
+    Pause,
+    /// Hold a break that falls due until the application loses focus.
+    Defer,
+    /// Let breaks run as usual, even during a detected meeting.
+    Allow,
+}
+
+impl std::fmt::Display for AppAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Pause => "pause",
+            Self::Defer => "defer",
+            Self::Allow => "allow",
+        })
+    }
+}
+
+/// A per-application rule, configured as an `[[app_rules]]` table in `config.toml`.
+///
+/// Matches the focused application by identifier (the bundle ID on macOS; the window class or
+/// process name on Linux; case-insensitive) and/or a regular expression on its window title.
+/// The first matching rule applies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppRule {
+    /// Application identifier, e.g. `"com.obsproject.obs-studio"` or `"steam_app_570"`.
+    pub app: String,
+    /// Regular expression matched against the focused window's title, e.g. `"(?i)recording"`.
+    pub title: String,
+    pub action: AppAction,
+}
+
+impl AppRule {
+    /// What the rule matches, for logs and the tray.
+    pub fn label(&self) -> &str {
+        if self.app.is_empty() {
+            &self.title
+        } else {
+            &self.app
+        }
+    }
+}
+
+/// One named reminder schedule, configured as a `[[reminders]]` table in `config.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderConfig {
+    /// Unique name shown in the tray, e.g. "Stretch".
+    pub name: String,
+    pub enabled: bool,
+    /// Minutes between reminders (1–480).
+    pub interval_minutes: u32,
+    /// Overlay duration in seconds (5–600); ignored for notifications.
+    pub duration_seconds: u32,
+    pub delivery: ReminderDelivery,
+    /// Text shown in the overlay or notification. Empty = "Time to <name>".
+    pub message: String,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            name: "Reminder".into(),
+            enabled: true,
+            interval_minutes: 60,
+            duration_seconds: 60,
+            delivery: ReminderDelivery::Notification,
+            message: String::new(),
+        }
+    }
+}
+
+impl ReminderConfig {
+    /// Text to show when the reminder fires.
+    pub fn display_message(&self) -> String {
+        if self.message.is_empty() {
+            format!("Time to {}", self.name.to_lowercase())
+        } else {
+            self.message.clone()
+        }
+    }
 }
 
 impl Default for AppConfig {
     /// Create an `AppConfig` populated with the application's default settings.
     ///
     /// The defaults are:
+    /// - `config_version` = [`CONFIG_VERSION`]
     /// - `work_interval_minutes = 20`
     /// - `break_duration_seconds = 20`
+    /// - `long_break_every = 0` (long breaks off)
+    /// - `long_break_duration_seconds = 300`
     /// - `strict_mode = false`
     /// - `overlay_theme = "dark"`
     /// - `sound = "off"`
     /// - `launch_at_login = true`
     /// - `pre_warning_seconds = 60`
-    /// - `meeting_detection = true`
+    /// - `meeting_detection = true`, with Zoom, Teams, Webex, FaceTime, Discord and Skype in
+    ///   `meeting_apps` and Google Meet, Zoom and Teams web calls in `meeting_titles`
+    /// - `meeting_start_polls = 2`, `meeting_end_polls = 2`, `meeting_grace_seconds = 60`,
+    ///   `meeting_catch_up_minutes = 30`
+    /// - `presentation_detection = true`, `presentation_max_deferral_minutes = 15`
+    /// - `idle_pause_seconds = 120`
+    /// - `snooze_minutes = 5`, `snooze_limit = 2`, `snooze_shrinks = false`
+    /// - `daily_skip_budget = 0`
+    /// - `sleep_policy = "reset_if_rested"`
+    /// - `schedule` disabled (Monday–Friday 09:00–18:00 once enabled)
+    /// - no calendar files or CalDAV calendars; once some are listed, events pause the timer
+    ///   and breaks move up to 10 minutes earlier to end before them; CalDAV refreshes every
+    ///   15 minutes
+    /// - no profiles, `active_profile` unset (automatic)
+    /// - `reminders = []`
+    /// - `app_rules = []`
     ///
     /// # Examples
     ///
     /// ```
     /// let cfg = AppConfig::default();
     /// assert_eq!(cfg.work_interval_minutes, 20);
-    /// assert_eq!(cfg.overlay_theme, "dark");
+    /// assert_eq!(cfg.overlay_theme, ThemeChoice::Builtin(OverlayTheme::Dark));
     /// assert!(cfg.launch_at_login);
     /// ```
     fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             work_interval_minutes: 20,
             break_duration_seconds: 20,
+            long_break_every: 0,
+            long_break_duration_seconds: 300,
             strict_mode: false,
-            overlay_theme: "dark".into(),
-            sound: "off".into(),
+            overlay_theme: ThemeChoice::Builtin(OverlayTheme::Dark),
+            sound: SoundChoice::Off,
             launch_at_login: true,
-            pre_warning_seconds: 60,
+            pre_warning: PreWarning::Seconds(60),
             meeting_detection: true,
+            meeting_apps: MeetingApp::defaults(),
+            meeting_titles: MeetingTitle::defaults(),
+            meeting_start_polls: 2,
+            meeting_end_polls: 2,
+            meeting_grace_seconds: 60,
+            meeting_catch_up_minutes: 30,
+            presentation_detection: true,
+            presentation_max_deferral_minutes: 15,
+            idle_pause_seconds: 120,
+            snooze_minutes: 5,
+            snooze_limit: 2,
+            snooze_shrinks: false,
+            daily_skip_budget: 0,
+            sleep_policy: SleepPolicy::ResetIfRested,
+            schedule: WorkSchedule::default(),
+            calendar: CalendarConfig::default(),
+            active_profile: None,
+            profiles: Vec::new(),
+            reminders: Vec::new(),
+            app_rules: Vec::new(),
+            rejected: Vec::new(),
+            layers: Arc::default(),
         }
     }
 }
@@ -79,43 +708,156 @@ impl AppConfig {
         path
     }
 
-    /// Loads the application configuration from the platform-specific config file, falling back to the default configuration if the file is missing or invalid.
+    /// Loads the application configuration from the platform-specific config file.
     ///
-    /// If the file is missing or cannot be parsed as TOML, the default `AppConfig` is saved to the config path before being returned.
+    /// If the file is missing, the default `AppConfig` is saved to the config path and returned.
+    /// Files written by an older release are upgraded through [`MIGRATIONS`] and rewritten
+    /// (the original is kept as `config.v<N>.toml.bak`).
     ///
     /// # Returns
     ///
-    /// The loaded `AppConfig`, or the default `AppConfig` when loading/parsing fails.
+    /// The loaded `AppConfig`, or `Err(String)` describing why the file could not be read or
+    /// parsed. The file is never modified in that case, so a typo in a hand-edited config can
+    /// be fixed without losing the rest of it.
     ///
     /// # Examples
     ///
     /// ```
-    /// let cfg = AppConfig::load();
+    /// let cfg = AppConfig::load().unwrap_or_default();
     /// // use cfg, e.g. ensure values are within expected ranges
     /// let cfg = cfg.validated();
     /// ```
-    pub fn load() -> Self {
+    pub fn load() -> Result<Self, String> {
         let path = Self::config_path();
-        if let Ok(contents) = fs::read_to_string(&path) {
-            if let Ok(config) = toml::from_str::<AppConfig>(&contents) {
-                return config;
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let default_config = Self::parse("", &path)?.0;
+                // Save defaults on first run
+                let _ = default_config.save();
+                return Ok(default_config);
+            }
+            Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+        };
+        let (config, from) = Self::parse(&contents, &path)?;
+        if from < CONFIG_VERSION {
+            log::info!("Upgrading config.toml from version {from} to {CONFIG_VERSION}");
+            backup(&path, &format!("config.v{from}.toml.bak"));
+            if let Err(e) = config.save() {
+                log::warn!("Failed to write upgraded config: {e}");
+            }
+        }
+        Ok(config)
+    }
+
+    /// The defaults with the policy, environment and command-line layers applied; what the app
+    /// runs on when `config.toml` cannot be used.
+    pub fn layered_default() -> Self {
+        let path = Self::config_path();
+        Self::parse("", &path)
+            .map(|(config, _)| config)
+            .unwrap_or_else(|e| {
+                log::error!("{e}");
+                Self::default()
+            })
+    }
+
+    /// Parses the contents of the config file at `path`, upgrading it in memory and applying
+    /// the layers around it (see [`crate::config_layers`]). Returns the config and the version
+    /// the file was written with.
+    fn parse(contents: &str, path: &Path) -> Result<(Self, u32), String> {
+        let mut table: toml::Table = contents
+            .parse()
+            .map_err(|e| format!("{} is not valid TOML: {e}", path.display()))?;
+        let from = migrate(&mut table);
+        let layers = Layers::load(&Self::setting_names());
+        layers.apply_defaults(&mut table);
+        let mut rejected = Vec::new();
+        layers.apply_overrides(&mut table, &mut rejected);
+        rejected.extend(normalize_choices(&mut table));
+        rejected.extend(normalize_schedule(&mut table));
+        let mut config: AppConfig = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| format!("Invalid setting in {}: {e}", path.display()))?;
+        config.rejected = rejected;
+        config.layers = Arc::new(layers);
+        Ok((config, from))
+    }
+
+    /// Names of the top-level settings.
+    fn setting_names() -> Vec<String> {
+        let mut names: Vec<String> = match toml::Value::try_from(Self::default()) {
+            Ok(toml::Value::Table(table)) => table.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+        // Unset options are not serialized.
+        names.push("active_profile".into());
+        names
+    }
+
+    /// What keeps the setting at `path` (e.g. `"strict_mode"`) from being changed in the
+    /// settings window, if anything.
+    pub fn pinned_by(&self, path: &str) -> Option<Source> {
+        self.layers.pinned_by(path)
+    }
+
+    /// The managed policy's minimum for the setting at `path`, if it sets one.
+    pub fn policy_minimum(&self, path: &str) -> Option<i64> {
+        self.layers.minimum(path)
+    }
+
+    /// Reads the config file at `path` without changing it and reports everything
+    /// [`AppConfig::validate`] would change, including in each profile's effective settings.
+    ///
+    /// Returns `Err` if the file cannot be read or parsed.
+    pub fn check(path: &Path) -> Result<Vec<Diagnostic>, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+        let (mut config, _) = Self::parse(&contents, path)?;
+        let mut diagnostics = config.validate();
+        if let Some(e) = &config.layers.policy_error {
+            diagnostics.insert(
+                0,
+                Diagnostic::new(
+                    crate::config_layers::policy_path().display().to_string(),
+                    "policy",
+                    "ignored",
+                    e.as_str(),
+                ),
+            );
+        }
+        for profile in &config.profiles {
+            let field = format!("profiles.{}", profile.name);
+            match config.try_with_profile(profile) {
+                Ok(mut effective) => {
+                    diagnostics.extend(effective.validate().into_iter().map(|mut d| {
+                        d.field = format!("{field}.{}", d.field);
+                        d
+                    }))
+                }
+                Err(e) => {
+                    // toml errors span several lines.
+                    let reason = e.split_whitespace().collect::<Vec<_>>().join(" ");
+                    diagnostics.push(Diagnostic::new(field, "overrides", "ignored", reason));
+                }
             }
         }
-        let default_config = Self::default();
-        // Save defaults on first run
-        let _ = default_config.save();
-        default_config
+        Ok(diagnostics)
     }
 
     /// Persists the configuration to the platform-specific config file.
     ///
     /// Attempts to create the parent directory if necessary, serializes `self` to
     /// pretty TOML, and writes it to the path returned by `AppConfig::config_path()`.
+    /// An existing file is first copied to `config.toml.bak`.
     ///
     /// # Returns
     ///
     /// `Ok(())` if the file was written successfully, `Err(String)` with an error
-    /// message if directory creation, serialization, or file write fails.
+    /// message if directory creation, serialization, or file write fails. A config loaded from
+    /// a newer release is never written: it would lose the settings this release does not
+    /// know. Neither is a file that cannot be parsed: the app runs on defaults until the user
+    /// fixes it, and saving would throw away everything else in it.
     ///
     /// # Examples
     ///
@@ -125,19 +867,60 @@ impl AppConfig {
     /// assert!(cfg.save().is_ok());
     /// ```
     pub fn save(&self) -> Result<(), String> {
-        let path = Self::config_path();
+        self.save_to(&Self::config_path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        if self.config_version > CONFIG_VERSION {
+            return Err(format!(
+                "{} is from a newer release (version {}); not overwriting it",
+                path.display(),
+                self.config_version
+            ));
+        }
+        if let Ok(contents) = fs::read_to_string(path) {
+            if let Err(e) = Self::parse(&contents, path) {
+                return Err(format!(
+                    "Not overwriting {}, which has errors: {e}",
+                    path.display()
+                ));
+            }
+        }
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
-        let contents = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
-        fs::write(&path, contents).map_err(|e| e.to_string())?;
+        let contents = self.user_toml(path)?;
+        backup(path, "config.toml.bak");
+        fs::write(path, contents).map_err(|e| e.to_string())?;
         Ok(())
     }
 
-    /// Return a copy of the config with numeric fields clamped to their valid ranges.
+    /// This config as TOML for the file at `path`: settings pinned by the environment, `--set`
+    /// flags or the managed policy are put back to what the file says, and unchanged policy
+    /// defaults are left out.
+    fn user_toml(&self, path: &Path) -> Result<String, String> {
+        let toml::Value::Table(mut table) =
+            toml::Value::try_from(self).map_err(|e| e.to_string())?
+        else {
+            return toml::to_string_pretty(self).map_err(|e| e.to_string());
+        };
+        let user: toml::Table = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| contents.parse().ok())
+            .unwrap_or_default();
+        if self.layers.restore_user_values(&mut table, &user) {
+            // Settings left out must stay out; the table loses the field order.
+            return toml::to_string_pretty(&table).map_err(|e| e.to_string());
+        }
+        let config: AppConfig = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+        toml::to_string_pretty(&config).map_err(|e| e.to_string())
+    }
+
+    /// Return a copy of the config with every setting in its valid range.
     ///
-    /// Ensures `work_interval_minutes` is between 1 and 60 (inclusive) and
-    /// `break_duration_seconds` is between 5 and 60 (inclusive).
+    /// Same as [`AppConfig::validate`], discarding the diagnostics.
     ///
     /// # Examples
     ///
@@ -148,19 +931,875 @@ impl AppConfig {
     /// assert_eq!(valid.break_duration_seconds, 60);
     /// ```
     pub fn validated(mut self) -> Self {
-        self.work_interval_minutes = self.work_interval_minutes.clamp(1, 60);
-        self.break_duration_seconds = self.break_duration_seconds.clamp(5, 60);
-        // pre_warning_seconds: 0 (off) or 30–120.
-        if self.pre_warning_seconds != 0 {
-            self.pre_warning_seconds = self.pre_warning_seconds.clamp(30, 120);
+        self.validate();
+        self
+    }
+
+    /// Brings every setting into its valid range and reports each value that was changed.
+    ///
+    /// Ensures `work_interval_minutes` is between 1 and 60 (inclusive),
+    /// `break_duration_seconds` is between 5 and 60 (inclusive), `long_break_every` is 0 or
+    /// between 2 and 12, and `long_break_duration_seconds` is between 60 and 1800. Unknown
+    /// themes and sounds were replaced when parsing, malformed schedule ranges are dropped,
+    /// and unnamed or duplicate reminders and profiles are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut cfg = AppConfig { work_interval_minutes: 90, ..Default::default() };
+    /// let diagnostics = cfg.validate();
+    /// assert_eq!(cfg.work_interval_minutes, 60);
+    /// assert_eq!(diagnostics[0].field, "work_interval_minutes");
+    /// assert_eq!(diagnostics[0].applied, "60");
+    /// ```
+    pub fn validate(&mut self) -> Vec<Diagnostic> {
+        let mut out = std::mem::take(&mut self.rejected);
+        let d = &mut out;
+        let layers = Arc::clone(&self.layers);
+        clamp(
+            d,
+            &layers,
+            "work_interval_minutes",
+            &mut self.work_interval_minutes,
+            1,
+            60,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "break_duration_seconds",
+            &mut self.break_duration_seconds,
+            5,
+            60,
+            false,
+        );
+        // long_break_every: 0 (off) or 2–12; every single break being long is just a longer break.
+        clamp(
+            d,
+            &layers,
+            "long_break_every",
+            &mut self.long_break_every,
+            2,
+            12,
+            true,
+        );
+        clamp(
+            d,
+            &layers,
+            "long_break_duration_seconds",
+            &mut self.long_break_duration_seconds,
+            60,
+            1800,
+            false,
+        );
+        let mut pre_warning = self.pre_warning.seconds();
+        clamp(
+            d,
+            &layers,
+            "pre_warning_seconds",
+            &mut pre_warning,
+            30,
+            120,
+            true,
+        );
+        self.pre_warning = pre_warning.into();
+        clamp(
+            d,
+            &layers,
+            "meeting_start_polls",
+            &mut self.meeting_start_polls,
+            1,
+            10,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "meeting_end_polls",
+            &mut self.meeting_end_polls,
+            1,
+            10,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "meeting_grace_seconds",
+            &mut self.meeting_grace_seconds,
+            0,
+            600,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "meeting_catch_up_minutes",
+            &mut self.meeting_catch_up_minutes,
+            5,
+            240,
+            true,
+        );
+        clamp(
+            d,
+            &layers,
+            "presentation_max_deferral_minutes",
+            &mut self.presentation_max_deferral_minutes,
+            1,
+            120,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "idle_pause_seconds",
+            &mut self.idle_pause_seconds,
+            30,
+            1800,
+            true,
+        );
+        clamp(
+            d,
+            &layers,
+            "snooze_minutes",
+            &mut self.snooze_minutes,
+            1,
+            15,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "snooze_limit",
+            &mut self.snooze_limit,
+            0,
+            5,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "daily_skip_budget",
+            &mut self.daily_skip_budget,
+            0,
+            20,
+            false,
+        );
+        // Schedule: drop ranges that are not "HH:MM-HH:MM". Days that end up empty are days
+        // off; a table listing no days at all was given the default week when parsed.
+        self.schedule.days.retain_valid("schedule", d);
+        clamp(
+            d,
+            &layers,
+            "calendar.pull_forward_minutes",
+            &mut self.calendar.pull_forward_minutes,
+            0,
+            30,
+            false,
+        );
+        clamp(
+            d,
+            &layers,
+            "calendar.refresh_minutes",
+            &mut self.calendar.refresh_minutes,
+            5,
+            240,
+            false,
+        );
+        let mut index = 0;
+        self.calendar.caldav.retain(|calendar| {
+            index += 1;
+            let url = calendar.url.trim();
+            if url.starts_with("https://") || url.starts_with("http://") {
+                return true;
+            }
+            d.push(Diagnostic::new(
+                format!("calendar.caldav[{}].url", index - 1),
+                format!("{url:?}"),
+                "calendar removed",
+                "must be an http:// or https:// URL",
+            ));
+            false
+        });
+        // Reminders: clamp ranges, drop unnamed entries and duplicate names (first wins).
+        let mut seen = std::collections::HashSet::new();
+        let mut index = 0;
+        self.reminders.retain(|r| {
+            index += 1;
+            keep_named(d, "reminders", index - 1, &r.name, &mut seen)
+        });
+        for r in &mut self.reminders {
+            let field = format!("reminders.{}", r.name);
+            clamp(
+                d,
+                &layers,
+                &format!("{field}.interval_minutes"),
+                &mut r.interval_minutes,
+                1,
+                480,
+                false,
+            );
+            clamp(
+                d,
+                &layers,
+                &format!("{field}.duration_seconds"),
+                &mut r.duration_seconds,
+                5,
+                600,
+                false,
+            );
         }
-        // Normalise string enums to known values; fall back to default.
-        if !["dark", "light", "nature"].contains(&self.overlay_theme.as_str()) {
-            self.overlay_theme = "dark".into();
+        // App rules: drop rules that match nothing or whose title pattern does not compile.
+        let mut index = 0;
+        self.app_rules.retain(|rule| {
+            index += 1;
+            let field = format!("app_rules[{}]", index - 1);
+            if rule.app.trim().is_empty() && rule.title.is_empty() {
+                d.push(Diagnostic::new(
+                    field,
+                    "no app or title",
+                    "rule removed",
+                    "a rule needs an app, a title pattern or both",
+                ));
+                return false;
+            }
+            keep_pattern(d, &format!("{field}.title"), &rule.title, "rule removed")
+        });
+        // Meeting signatures: drop entries that match nothing or do not compile.
+        let mut index = 0;
+        self.meeting_apps.retain(|app| {
+            index += 1;
+            if app.id.trim().is_empty() {
+                d.push(Diagnostic::new(
+                    format!("meeting_apps[{}].id", index - 1),
+                    format!("{:?}", app.id),
+                    "entry removed",
+                    "an application identifier is required",
+                ));
+                return false;
+            }
+            true
+        });
+        let mut index = 0;
+        self.meeting_titles.retain(|title| {
+            index += 1;
+            let field = format!("meeting_titles[{}].pattern", index - 1);
+            if title.pattern.is_empty() {
+                d.push(Diagnostic::new(
+                    field,
+                    "\"\"",
+                    "entry removed",
+                    "an empty pattern would match every window",
+                ));
+                return false;
+            }
+            keep_pattern(d, &field, &title.pattern, "entry removed")
+        });
+        // Profiles: drop unnamed entries and duplicate names; forget a pinned profile that
+        // no longer exists.
+        let mut seen = std::collections::HashSet::new();
+        let mut index = 0;
+        self.profiles.retain(|p| {
+            index += 1;
+            keep_named(d, "profiles", index - 1, &p.name, &mut seen)
+        });
+        for p in &mut self.profiles {
+            if let Some(hours) = &mut p.hours {
+                hours.retain_valid(&format!("profiles.{}.hours", p.name), d);
+            }
         }
-        if !["off", "chime", "whitenoise"].contains(&self.sound.as_str()) {
-            self.sound = "off".into();
+        if let Some(name) = &self.active_profile {
+            if self.profile(name).is_none() {
+                d.push(Diagnostic::new(
+                    "active_profile",
+                    format!("{name:?}"),
+                    "automatic",
+                    "no profile with that name",
+                ));
+                self.active_profile = None;
+            }
         }
-        self
+        out
+    }
+
+    /// The profile called `name`, if configured.
+    pub fn profile(&self, name: &str) -> Option<&ProfileConfig> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// This config with `profile`'s overrides applied on top, and the environment, `--set`
+    /// flags and managed policy above them.
+    ///
+    /// Overrides that do not fit a setting's type are reported and the profile is ignored.
+    pub fn with_profile(&self, profile: Option<&ProfileConfig>) -> AppConfig {
+        let overrides = profile.map(|p| &p.overrides);
+        self.layered(overrides).unwrap_or_else(|e| {
+            if let Some(profile) = profile {
+                log::warn!("Ignoring profile {:?}: {e}", profile.name);
+            }
+            self.layered(None).unwrap_or_else(|_| self.clone())
+        })
+    }
+
+    /// This config with `profile`'s overrides applied on top, or why they do not fit.
+    pub fn try_with_profile(&self, profile: &ProfileConfig) -> Result<AppConfig, String> {
+        self.layered(Some(&profile.overrides))
+    }
+
+    fn layered(&self, overrides: Option<&toml::Table>) -> Result<AppConfig, String> {
+        let toml::Value::Table(mut table) =
+            toml::Value::try_from(self).map_err(|e| e.to_string())?
+        else {
+            return Ok(self.clone());
+        };
+        for (key, value) in overrides.into_iter().flatten() {
+            // Profiles cannot reconfigure profiles.
+            if !matches!(
+                key.as_str(),
+                "profiles" | "active_profile" | "config_version"
+            ) {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+        // Profiles sit below the environment, command line and policy.
+        let mut rejected = Vec::new();
+        self.layers.apply_overrides(&mut table, &mut rejected);
+        rejected.extend(normalize_choices(&mut table));
+        rejected.extend(normalize_schedule(&mut table));
+        let mut config: AppConfig = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+        config.rejected = rejected;
+        config.layers = Arc::clone(&self.layers);
+        Ok(config)
+    }
+}
+
+/// Copies `path`, if it exists, to `name` in the same directory before it is rewritten.
+fn backup(path: &Path, name: &str) {
+    if !path.exists() {
+        return;
+    }
+    let target = path.with_file_name(name);
+    if let Err(e) = fs::copy(path, &target) {
+        log::warn!(
+            "Failed to back up {} to {}: {e}",
+            path.display(),
+            target.display()
+        );
+    }
+}
+
+/// A setting that [`AppConfig::validate`] had to change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Dotted path of the setting, e.g. `snooze_minutes` or `reminders.Stretch.interval_minutes`.
+    pub field: String,
+    /// The value as written.
+    pub value: String,
+    /// The value used instead.
+    pub applied: String,
+    /// Why `value` was rejected.
+    pub reason: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(
+        field: impl Into<String>,
+        value: impl Into<String>,
+        applied: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            value: value.into(),
+            applied: applied.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} → {} ({})",
+            self.field, self.value, self.applied, self.reason
+        )
+    }
+}
+
+/// Clamps `value` to `min..=max` (leaving 0 alone if `zero_is_off`), reporting a change.
+///
+/// A managed policy minimum above `max` raises the maximum: `layers` has already raised the
+/// value to it, and the policy must hold.
+fn clamp(
+    diagnostics: &mut Vec<Diagnostic>,
+    layers: &Layers,
+    field: &str,
+    value: &mut u32,
+    min: u32,
+    max: u32,
+    zero_is_off: bool,
+) {
+    let max = layers
+        .minimum(field)
+        .and_then(|m| u32::try_from(m).ok())
+        .map_or(max, |m| m.max(max));
+    if (zero_is_off && *value == 0) || (min..=max).contains(value) {
+        return;
+    }
+    let reason = if zero_is_off {
+        format!("must be 0 (off) or between {min} and {max}")
+    } else if min == 0 {
+        format!("must be at most {max}")
+    } else {
+        format!("must be between {min} and {max}")
+    };
+    let applied = (*value).clamp(min, max);
+    diagnostics.push(Diagnostic::new(
+        field,
+        value.to_string(),
+        applied.to_string(),
+        reason,
+    ));
+    *value = applied;
+}
+
+/// Replaces unknown theme and sound names in a parsed `table` with the defaults, so a typo
+/// does not reject the whole file, and reports them.
+fn normalize_choices(table: &mut toml::Table) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    normalize_theme(table, &mut diagnostics);
+    normalize_choice::<SoundChoice>(table, "sound", &mut diagnostics);
+    diagnostics
+}
+
+/// Gives a `[schedule]` table that lists no days at all the default working week, reporting it
+/// when the schedule is enabled. Days listed as `[]` stay days off.
+fn normalize_schedule(table: &mut toml::Table) -> Vec<Diagnostic> {
+    let Some(toml::Value::Table(schedule)) = table.get_mut("schedule") else {
+        return Vec::new();
+    };
+    let Ok(toml::Value::Table(days)) = toml::Value::try_from(WorkSchedule::default().days) else {
+        return Vec::new();
+    };
+    if days.keys().any(|day| schedule.contains_key(day)) {
+        return Vec::new();
+    }
+    let enabled = schedule.get("enabled").and_then(toml::Value::as_bool) == Some(true);
+    schedule.extend(days);
+    if !enabled {
+        return Vec::new();
+    }
+    vec![Diagnostic::new(
+        "schedule",
+        "no days listed",
+        "Monday–Friday 09:00-18:00",
+        "list the working days, with [] for days off",
+    )]
+}
+
+/// Like [`normalize_choice`], also accepting the name of an installed, valid theme pack.
+fn normalize_theme(table: &mut toml::Table, diagnostics: &mut Vec<Diagnostic>) {
+    let key = "overlay_theme";
+    let Some(value) = table.get(key) else {
+        return;
+    };
+    let names: Vec<&str> = OverlayTheme::ALL.iter().map(|c| c.name()).collect();
+    let mut reason = format!(
+        "must be one of {} or an installed theme pack",
+        names.join(", ")
+    );
+    if let Some(name) = value.as_str().map(str::trim) {
+        if let Some(theme) = OverlayTheme::from_name(name) {
+            table.insert(key.into(), theme.name().into());
+            return;
+        }
+        let themes = crate::themes::themes_dir();
+        match crate::themes::text(&themes, name) {
+            Ok(_) => {
+                table.insert(key.into(), name.into());
+                return;
+            }
+            Err(e) if themes.join(name).is_dir() => {
+                reason = format!("the theme pack is invalid: {e}");
+            }
+            Err(_) => {}
+        }
+    }
+    diagnostics.push(Diagnostic::new(
+        key,
+        value.to_string(),
+        format!("{:?}", OverlayTheme::default().name()),
+        reason,
+    ));
+    table.remove(key);
+}
+
+fn normalize_choice<T: Choice>(
+    table: &mut toml::Table,
+    key: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let Some(value) = table.get(key) else {
+        return;
+    };
+    if let Some(choice) = value.as_str().and_then(T::from_name) {
+        table.insert(key.into(), choice.name().into());
+        return;
+    }
+    let names: Vec<&str> = T::ALL.iter().map(|c| c.name()).collect();
+    diagnostics.push(Diagnostic::new(
+        key,
+        value.to_string(),
+        format!("{:?}", T::default().name()),
+        format!("must be one of {}", names.join(", ")),
+    ));
+    table.remove(key);
+}
+
+/// Whether `pattern` is a valid regular expression; reports it as `applied` otherwise.
+fn keep_pattern(
+    diagnostics: &mut Vec<Diagnostic>,
+    field: &str,
+    pattern: &str,
+    applied: &str,
+) -> bool {
+    match regex::Regex::new(pattern) {
+        Ok(_) => true,
+        Err(e) => {
+            diagnostics.push(Diagnostic::new(
+                field,
+                format!("{pattern:?}"),
+                applied,
+                e.to_string().lines().last().unwrap_or_default().trim(),
+            ));
+            false
+        }
+    }
+}
+
+/// Whether entry `index` of the `field` list, called `name`, is kept: unnamed entries and
+/// repeated names (compared case-insensitively; the first wins) are reported and dropped.
+fn keep_named(
+    diagnostics: &mut Vec<Diagnostic>,
+    field: &str,
+    index: usize,
+    name: &str,
+    seen: &mut std::collections::HashSet<String>,
+) -> bool {
+    let key = name.trim().to_lowercase();
+    if key.is_empty() {
+        diagnostics.push(Diagnostic::new(
+            format!("{field}[{index}].name"),
+            format!("{name:?}"),
+            "entry removed",
+            "a name is required",
+        ));
+        false
+    } else if !seen.insert(key) {
+        diagnostics.push(Diagnostic::new(
+            format!("{field}[{index}].name"),
+            format!("{name:?}"),
+            "entry removed",
+            "the name is already used by an earlier entry",
+        ));
+        false
+    } else {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `contents` as a config file and validates it.
+    fn parse(contents: &str) -> (AppConfig, Vec<Diagnostic>) {
+        let (mut config, _) = AppConfig::parse(contents, Path::new("config.toml")).unwrap();
+        let diagnostics = config.validate();
+        (config, diagnostics)
+    }
+
+    /// A config file written before `config_version` existed.
+    const V1_CONFIG: &str = r#"
+work_interval_minutes = 25
+break_duration_seconds = 30
+strict_mode = true
+overlay_theme = "nature"
+sound = "chime"
+launch_at_login = false
+pre_warning_seconds = 90
+meeting_detection = false
+"#;
+
+    #[test]
+    fn version_1_file_reads_with_its_settings_kept() {
+        let (config, from) = AppConfig::parse(V1_CONFIG, Path::new("config.toml")).unwrap();
+        assert_eq!(from, 1);
+        assert_eq!(config.config_version, CONFIG_VERSION);
+        assert_eq!(config.work_interval_minutes, 25);
+        assert_eq!(config.break_duration_seconds, 30);
+        assert!(config.strict_mode);
+        assert_eq!(
+            config.overlay_theme,
+            ThemeChoice::Builtin(OverlayTheme::Nature)
+        );
+        assert_eq!(config.sound, SoundChoice::Chime);
+        assert!(!config.launch_at_login);
+        assert_eq!(config.pre_warning, PreWarning::Seconds(90));
+        assert!(!config.meeting_detection);
+        // Settings added since version 1 take their defaults.
+        assert_eq!(config.long_break_every, 0);
+        assert!(!config.schedule.enabled);
+
+        // The written file reads back the same.
+        let written = config
+            .user_toml(Path::new("/nonexistent/config.toml"))
+            .unwrap();
+        let (reread, from) = AppConfig::parse(&written, Path::new("config.toml")).unwrap();
+        assert_eq!(from, CONFIG_VERSION);
+        assert_eq!(
+            toml::to_string(&reread).unwrap(),
+            toml::to_string(&config).unwrap()
+        );
+    }
+
+    #[test]
+    fn upgrade_runs_the_steps_after_the_file_version() {
+        fn rename_sound(table: &mut toml::Table) {
+            if let Some(sound) = table.remove("sound") {
+                table.insert("break_sound".into(), sound);
+            }
+        }
+        fn default_volume(table: &mut toml::Table) {
+            table.entry("volume").or_insert(toml::Value::Integer(50));
+        }
+        let steps: &[fn(&mut toml::Table)] = &[rename_sound, default_volume];
+
+        let mut table: toml::Table = V1_CONFIG.parse().unwrap();
+        assert_eq!(upgrade(&mut table, steps), 1);
+        assert_eq!(table["config_version"].as_integer(), Some(3));
+        assert_eq!(table["break_sound"].as_str(), Some("chime"));
+        assert!(!table.contains_key("sound"));
+        assert_eq!(table["volume"].as_integer(), Some(50));
+
+        let mut table: toml::Table = "config_version = 2\nsound = \"off\"\nvolume = 80\n"
+            .parse()
+            .unwrap();
+        assert_eq!(upgrade(&mut table, steps), 2);
+        assert_eq!(table["sound"].as_str(), Some("off"));
+        assert_eq!(table["volume"].as_integer(), Some(80));
+
+        let mut table: toml::Table = "config_version = 4\nsound = \"off\"\n".parse().unwrap();
+        assert_eq!(upgrade(&mut table, steps), 4);
+        assert_eq!(table["config_version"].as_integer(), Some(4));
+        assert_eq!(table["sound"].as_str(), Some("off"));
+    }
+
+    #[test]
+    fn a_file_with_errors_is_never_overwritten() {
+        let dir = std::env::temp_dir().join(format!("twenty20-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let broken = "work_interval_minutes = 25\nstrict_mode = yes\n";
+        fs::write(&path, broken).unwrap();
+
+        for _ in 0..2 {
+            let err = AppConfig::default().save_to(&path).unwrap_err();
+            assert!(err.contains("not valid TOML"), "{err}");
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), broken);
+        assert!(!dir.join("config.toml.bak").exists());
+
+        // Once fixed, saving works again and keeps the previous file.
+        fs::write(&path, "work_interval_minutes = 25\n").unwrap();
+        AppConfig::default().save_to(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("config.toml.bak")).unwrap(),
+            "work_interval_minutes = 25\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_from_a_newer_release_is_read_but_not_overwritten() {
+        let (config, from) = AppConfig::parse(
+            "config_version = 99\nwork_interval_minutes = 30\nfuture_setting = true\n",
+            Path::new("config.toml"),
+        )
+        .unwrap();
+        assert_eq!(from, 99);
+        assert_eq!(config.config_version, 99);
+        assert_eq!(config.work_interval_minutes, 30);
+        assert!(config
+            .save_to(Path::new("/nonexistent/config.toml"))
+            .unwrap_err()
+            .contains("newer release"));
+    }
+
+    #[test]
+    fn validation_never_goes_below_a_policy_minimum() {
+        let mut config = AppConfig {
+            layers: Arc::new(Layers::from_policy(
+                "[minimum]\nbreak_duration_seconds = 90\nsnooze_minutes = 5\n"
+                    .parse()
+                    .unwrap(),
+            )),
+            break_duration_seconds: 90,
+            snooze_minutes: 5,
+            ..AppConfig::default()
+        };
+        assert_eq!(config.validate(), []);
+        assert_eq!(config.break_duration_seconds, 90);
+
+        // The minimum raises the maximum only as far as itself.
+        config.break_duration_seconds = 200;
+        config.snooze_minutes = 40;
+        let diagnostics = config.validate();
+        assert_eq!(config.break_duration_seconds, 90);
+        assert_eq!(config.snooze_minutes, 15);
+        assert_eq!(diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn overlay_theme_is_read_and_written_by_name() {
+        let (config, diagnostics) = parse("overlay_theme = \" Light \"\n");
+        assert_eq!(
+            config.overlay_theme,
+            ThemeChoice::Builtin(OverlayTheme::Light)
+        );
+        assert_eq!(diagnostics, []);
+        assert!(toml::to_string(&config)
+            .unwrap()
+            .contains("overlay_theme = \"light\"\n"));
+
+        let pack = ThemeChoice::from("ocean".to_string());
+        assert_eq!(pack, ThemeChoice::Pack("ocean".into()));
+        assert_eq!(String::from(pack), "ocean");
+    }
+
+    #[test]
+    fn overlay_theme_naming_no_installed_pack_falls_back_to_the_default() {
+        let (config, diagnostics) = parse("overlay_theme = \"no-such-pack\"\n");
+        assert_eq!(config.overlay_theme, ThemeChoice::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].field, "overlay_theme");
+    }
+
+    #[test]
+    fn schedule_listing_every_day_as_empty_has_no_working_days() {
+        let (config, diagnostics) = parse(
+            r#"
+[schedule]
+enabled = true
+monday = []
+tuesday = []
+wednesday = []
+thursday = []
+friday = []
+saturday = []
+sunday = []
+"#,
+        );
+        assert!(config.schedule.enabled);
+        assert!(config.schedule.days.all().iter().all(|day| day.is_empty()));
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn schedule_listing_some_days_leaves_the_others_off() {
+        let (config, diagnostics) = parse(
+            r#"
+[schedule]
+enabled = true
+saturday = ["10:00-14:00"]
+"#,
+        );
+        assert_eq!(config.schedule.days.saturday, ["10:00-14:00"]);
+        assert!(config.schedule.days.monday.is_empty());
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn schedule_without_days_uses_the_default_week_and_says_so() {
+        let (config, diagnostics) = parse("[schedule]\nenabled = true\n");
+        assert_eq!(config.schedule.days.monday, ["09:00-18:00"]);
+        assert!(config.schedule.days.sunday.is_empty());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].field, "schedule");
+
+        let (config, diagnostics) = parse("[schedule]\nenabled = false\n");
+        assert_eq!(config.schedule.days.friday, ["09:00-18:00"]);
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn schedule_with_only_invalid_ranges_reports_each() {
+        let (config, diagnostics) = parse(
+            r#"
+[schedule]
+enabled = true
+monday = ["9-5"]
+"#,
+        );
+        assert!(config.schedule.days.all().iter().all(|day| day.is_empty()));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].field, "schedule.monday");
+    }
+
+    #[test]
+    fn validation_clamps_numbers_to_their_ranges() {
+        let (config, diagnostics) = parse(
+            r#"
+work_interval_minutes = 0
+break_duration_seconds = 600
+long_break_every = 1
+long_break_duration_seconds = 300
+idle_pause_seconds = 0
+"#,
+        );
+        assert_eq!(config.work_interval_minutes, 1);
+        assert_eq!(config.break_duration_seconds, 60);
+        assert_eq!(config.long_break_every, 2);
+        assert_eq!(config.long_break_duration_seconds, 300);
+        // 0 turns the idle pause off rather than being raised to the minimum.
+        assert_eq!(config.idle_pause_seconds, 0);
+        let changed: Vec<(&str, &str, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.field.as_str(), d.value.as_str(), d.applied.as_str()))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                ("work_interval_minutes", "0", "1"),
+                ("break_duration_seconds", "600", "60"),
+                ("long_break_every", "1", "2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_choice_falls_back_to_the_default_and_says_so() {
+        let (config, diagnostics) = parse("sound = \"gong\"\nstrict_mode = true\n");
+        assert_eq!(config.sound, SoundChoice::default());
+        assert!(config.strict_mode, "the rest of the file is kept");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].field, "sound");
+        assert_eq!(diagnostics[0].value, "\"gong\"");
+        assert_eq!(diagnostics[0].applied, "\"off\"");
+        assert_eq!(
+            diagnostics[0].reason,
+            "must be one of off, chime, whitenoise"
+        );
+
+        let (config, diagnostics) = parse("sound = 3\n");
+        assert_eq!(config.sound, SoundChoice::default());
+        assert_eq!(diagnostics[0].value, "3");
+
+        let (config, diagnostics) = parse("sound = \"Chime\"\n");
+        assert_eq!(config.sound, SoundChoice::Chime);
+        assert_eq!(diagnostics, []);
     }
 }
@@ -0,0 +1,572 @@
+//! Layered configuration.
+//!
+//! Settings are resolved from these layers, lowest first:
+//!
+//! 1. built-in defaults;
+//! 2. the managed policy's `[defaults]` table;
+//! 3. the user's `config.toml` (the only layer the settings window and tray write to);
+//! 4. the profile in effect (see [`crate::profiles`]);
+//! 5. `TWENTY20_*` environment variables, e.g. `TWENTY20_WORK_INTERVAL_MINUTES=1` or
+//!    `TWENTY20_SCHEDULE__ENABLED=false` (`__` separates nested keys);
+//! 6. `--set key=value` command-line flags;
+//! 7. the managed policy's `[locked]` values and `[minimum]` bounds, which nothing overrides.
+//!
+//! The managed policy is a TOML file installed by an administrator at [`policy_path`]:
+//!
+//! ```toml
+//! [locked]
+//! strict_mode = true
+//!
+//! [minimum]
+//! break_duration_seconds = 30
+//! ```
+//!
+//! Layers 5–7 are applied on top of every config built from `config.toml`, and are taken out
+//! again when it is saved, so they never end up in the user's file. A policy file that stops
+//! parsing does not lift its locks: the last copy that could be read stays in force.
+
+use crate::config::Diagnostic;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Prefix of environment variables that override settings.
+const ENV_PREFIX: &str = "TWENTY20_";
+
+/// Overrides from `--set` flags, recorded by [`crate::cli`] before the app starts.
+static COMMAND_LINE: OnceLock<toml::Table> = OnceLock::new();
+
+/// Records the `--set` overrides for every config loaded from now on. Only the first call
+/// has an effect.
+pub fn set_command_line(overrides: toml::Table) {
+    let _ = COMMAND_LINE.set(overrides);
+}
+
+/// Location of the managed policy: `/Library/Application Support/Twenty20/policy.toml` on
+/// macOS and `/etc/twenty20/policy.toml` elsewhere.
+///
+/// Debug builds honour `TWENTY20_POLICY` so the policy can be tried out without root.
+pub fn policy_path() -> PathBuf {
+    if cfg!(debug_assertions) {
+        if let Some(path) = std::env::var_os("TWENTY20_POLICY") {
+            return PathBuf::from(path);
+        }
+    }
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/Twenty20/policy.toml")
+    } else {
+        PathBuf::from("/etc/twenty20/policy.toml")
+    }
+}
+
+/// Where a setting that cannot be changed from the settings window comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Policy,
+    Environment(String),
+    CommandLine,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Policy => write!(f, "the managed policy"),
+            Self::Environment(var) => write!(f, "${var}"),
+            Self::CommandLine => write!(f, "a --set flag"),
+        }
+    }
+}
+
+/// The layers around the user's `config.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct Layers {
+    policy_defaults: toml::Table,
+    environment: toml::Table,
+    command_line: toml::Table,
+    locked: toml::Table,
+    minimum: toml::Table,
+    /// Why the policy file could not be used, if it exists but is broken. The last good policy
+    /// is applied in its place, if there was one.
+    pub policy_error: Option<String>,
+}
+
+impl Layers {
+    /// Reads the managed policy, the environment and the recorded `--set` flags.
+    ///
+    /// `known` lists the top-level setting names; environment variables naming anything else
+    /// are ignored with a warning.
+    pub fn load(known: &[String]) -> Self {
+        let (policy, policy_error) = read_policy(&policy_path(), &last_good_policy_path());
+        Self {
+            environment: from_environment(known, std::env::vars()),
+            command_line: COMMAND_LINE.get().cloned().unwrap_or_default(),
+            policy_error,
+            ..Self::from_policy(policy)
+        }
+    }
+
+    /// The layers of a parsed policy file, without environment or command-line overrides.
+    pub fn from_policy(mut policy: toml::Table) -> Self {
+        let mut take = |name: &str| match policy.remove(name) {
+            Some(toml::Value::Table(t)) => t,
+            _ => toml::Table::new(),
+        };
+        Self {
+            policy_defaults: take("defaults"),
+            locked: take("locked"),
+            minimum: take("minimum"),
+            ..Self::default()
+        }
+    }
+
+    /// Fills in the policy's `[defaults]` for settings the user's file leaves out.
+    pub fn apply_defaults(&self, table: &mut toml::Table) {
+        merge_missing(table, &self.policy_defaults);
+    }
+
+    /// Applies the environment, `--set` flags and the policy's locked values and minimums,
+    /// reporting every user value the policy overrides.
+    pub fn apply_overrides(&self, table: &mut toml::Table, diagnostics: &mut Vec<Diagnostic>) {
+        merge(table, &self.environment);
+        merge(table, &self.command_line);
+        for (path, value) in flatten(&self.locked) {
+            if let Some(current) = get(table, &path).filter(|v| **v != value) {
+                diagnostics.push(Diagnostic::new(
+                    path.join("."),
+                    current.to_string(),
+                    value.to_string(),
+                    "locked by the managed policy",
+                ));
+            }
+            set(table, &path, value);
+        }
+        for (path, min) in flatten(&self.minimum) {
+            let below = match (get(table, &path), &min) {
+                (Some(toml::Value::Integer(v)), toml::Value::Integer(m)) => v < m,
+                (Some(toml::Value::Float(v)), toml::Value::Float(m)) => v < m,
+                _ => false,
+            };
+            if below {
+                let current = get(table, &path)
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                diagnostics.push(Diagnostic::new(
+                    path.join("."),
+                    current,
+                    min.to_string(),
+                    format!("the managed policy requires at least {min}"),
+                ));
+                set(table, &path, min);
+            }
+        }
+    }
+
+    /// Replaces settings set by the environment, `--set` flags or locked by the policy with
+    /// what `user` (the file on disk) says, so saving does not write them to `config.toml`.
+    /// Policy defaults the user has not changed are left out as well, so later changes to the
+    /// policy still reach them.
+    ///
+    /// Returns whether any setting was left out.
+    pub fn restore_user_values(&self, table: &mut toml::Table, user: &toml::Table) -> bool {
+        let mut removed = false;
+        for layer in [&self.environment, &self.command_line, &self.locked] {
+            for (path, _) in flatten(layer) {
+                match get(user, &path) {
+                    Some(value) => set(table, &path, value.clone()),
+                    None => removed |= remove(table, &path),
+                }
+            }
+        }
+        for (path, value) in flatten(&self.policy_defaults) {
+            if get(user, &path).is_none() && get(table, &path) == Some(&value) {
+                removed |= remove(table, &path);
+            }
+        }
+        removed
+    }
+
+    /// What pins the setting at `path` (e.g. `"strict_mode"`), if anything; the highest
+    /// layer wins.
+    pub fn pinned_by(&self, path: &str) -> Option<Source> {
+        let path: Vec<String> = path.split('.').map(str::to_string).collect();
+        if get(&self.locked, &path).is_some() {
+            Some(Source::Policy)
+        } else if get(&self.command_line, &path).is_some() {
+            Some(Source::CommandLine)
+        } else if get(&self.environment, &path).is_some() {
+            Some(Source::Environment(format!(
+                "{ENV_PREFIX}{}",
+                path.join("__").to_uppercase()
+            )))
+        } else {
+            None
+        }
+    }
+
+    /// The policy's minimum for the setting at `path`, if it sets one.
+    pub fn minimum(&self, path: &str) -> Option<i64> {
+        let path: Vec<String> = path.split('.').map(str::to_string).collect();
+        get(&self.minimum, &path).and_then(toml::Value::as_integer)
+    }
+}
+
+/// Where a copy of the last managed policy that could be read is kept.
+fn last_good_policy_path() -> PathBuf {
+    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("twenty20");
+    path.push("policy.last-good.toml");
+    path
+}
+
+/// The policy at `path`, and why it could not be used if it exists but is broken.
+///
+/// A broken policy does not fail open: the copy of the last good one kept at `last_good` is
+/// applied instead, so a botched edit by an administrator does not lift the locks. The copy is
+/// refreshed whenever the policy reads, and removed with the policy.
+fn read_policy(path: &Path, last_good: &Path) -> (toml::Table, Option<String>) {
+    let error = match std::fs::read_to_string(path) {
+        Ok(contents) => match contents.parse::<toml::Table>() {
+            Ok(policy) => {
+                if std::fs::read_to_string(last_good).ok().as_ref() != Some(&contents) {
+                    let written = last_good
+                        .parent()
+                        .map_or(Ok(()), std::fs::create_dir_all)
+                        .and_then(|()| std::fs::write(last_good, &contents));
+                    if let Err(e) = written {
+                        log::warn!("Could not keep a copy of the managed policy: {e}");
+                    }
+                }
+                return (policy, None);
+            }
+            Err(e) => format!("{} is not valid TOML: {e}", path.display()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let _ = std::fs::remove_file(last_good);
+            return (toml::Table::new(), None);
+        }
+        Err(e) => format!("Could not read {}: {e}", path.display()),
+    };
+    let fallback = std::fs::read_to_string(last_good)
+        .ok()
+        .and_then(|contents| contents.parse::<toml::Table>().ok());
+    match fallback {
+        Some(policy) => {
+            log::error!("{error}; keeping the last managed policy that could be read");
+            (policy, Some(error))
+        }
+        None => {
+            log::error!("{error}; there is no earlier policy to keep, so none is applied");
+            (toml::Table::new(), Some(error))
+        }
+    }
+}
+
+/// Parses a `--set` or environment value as TOML (`5`, `true`, `"dark"`), falling back to a
+/// plain string so `light` works without quotes.
+pub fn parse_value(raw: &str) -> toml::Value {
+    format!("v = {raw}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Sets the dotted `key` (e.g. `schedule.enabled`) in `table`.
+pub fn insert_dotted(table: &mut toml::Table, key: &str, value: toml::Value) {
+    let path: Vec<String> = key.split('.').map(|s| s.trim().to_string()).collect();
+    set(table, &path, value);
+}
+
+/// The settings set by the `TWENTY20_*` variables among `vars`.
+fn from_environment(
+    known: &[String],
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> toml::Table {
+    let mut table = toml::Table::new();
+    for (var, raw) in vars {
+        let Some(key) = var.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if key == "POLICY" {
+            continue;
+        }
+        let key = key.to_lowercase().replace("__", ".");
+        let top = key.split('.').next().unwrap_or_default();
+        if !known.iter().any(|k| k == top) {
+            log::warn!("Ignoring {var}: there is no setting called {top:?}");
+            continue;
+        }
+        log::info!("{key} set by {var}");
+        insert_dotted(&mut table, &key, parse_value(&raw));
+    }
+    table
+}
+
+/// Overwrites `table` with every value in `top`, descending into tables.
+fn merge(table: &mut toml::Table, top: &toml::Table) {
+    for (path, value) in flatten(top) {
+        set(table, &path, value);
+    }
+}
+
+/// Adds the values in `bottom` that `table` does not set, descending into tables.
+fn merge_missing(table: &mut toml::Table, bottom: &toml::Table) {
+    for (path, value) in flatten(bottom) {
+        if get(table, &path).is_none() {
+            set(table, &path, value);
+        }
+    }
+}
+
+/// The leaf values of `table` with their key paths. Arrays (reminders, profiles, day ranges)
+/// are leaves.
+fn flatten(table: &toml::Table) -> Vec<(Vec<String>, toml::Value)> {
+    let mut out = Vec::new();
+    for (key, value) in table {
+        match value {
+            toml::Value::Table(inner) => {
+                for (mut path, value) in flatten(inner) {
+                    path.insert(0, key.clone());
+                    out.push((path, value));
+                }
+            }
+            _ => out.push((vec![key.clone()], value.clone())),
+        }
+    }
+    out
+}
+
+fn get<'a>(table: &'a toml::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (last, parents) = path.split_last()?;
+    let mut table = table;
+    for key in parents {
+        table = table.get(key)?.as_table()?;
+    }
+    table.get(last)
+}
+
+fn set(table: &mut toml::Table, path: &[String], value: toml::Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for key in parents {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        let toml::Value::Table(inner) = entry else {
+            return;
+        };
+        table = inner;
+    }
+    table.insert(last.clone(), value);
+}
+
+fn remove(table: &mut toml::Table, path: &[String]) -> bool {
+    let Some((last, parents)) = path.split_last() else {
+        return false;
+    };
+    let mut table = table;
+    for key in parents {
+        match table.get_mut(key).and_then(toml::Value::as_table_mut) {
+            Some(inner) => table = inner,
+            None => return false,
+        }
+    }
+    table.remove(last).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> toml::Table {
+        toml.parse().unwrap()
+    }
+
+    fn known() -> Vec<String> {
+        ["work_interval_minutes", "schedule", "overlay_theme"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    /// Layers with the given policy, environment and `--set` tables.
+    fn layers(policy: &str, environment: &str, command_line: &str) -> Layers {
+        Layers {
+            environment: table(environment),
+            command_line: table(command_line),
+            ..Layers::from_policy(table(policy))
+        }
+    }
+
+    #[test]
+    fn environment_variables_name_settings_and_nested_keys() {
+        let vars = [
+            ("TWENTY20_WORK_INTERVAL_MINUTES", "1"),
+            ("TWENTY20_SCHEDULE__ENABLED", "false"),
+            ("TWENTY20_OVERLAY_THEME", "light"),
+            ("TWENTY20_POLICY", "/tmp/policy.toml"),
+            ("TWENTY20_NO_SUCH_SETTING", "1"),
+            ("WORK_INTERVAL_MINUTES", "2"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        assert_eq!(
+            from_environment(&known(), vars),
+            table("work_interval_minutes = 1\noverlay_theme = \"light\"\n[schedule]\nenabled = false\n")
+        );
+    }
+
+    #[test]
+    fn values_parse_as_toml_or_fall_back_to_a_string() {
+        let cases = [
+            ("5", toml::Value::Integer(5)),
+            ("true", toml::Value::Boolean(true)),
+            ("\"dark\"", toml::Value::String("dark".into())),
+            ("dark", toml::Value::String("dark".into())),
+            ("09:00-17:00", toml::Value::String("09:00-17:00".into())),
+            (
+                "[\"a\", \"b\"]",
+                toml::Value::Array(vec!["a".into(), "b".into()]),
+            ),
+        ];
+        for (raw, want) in cases {
+            assert_eq!(parse_value(raw), want, "{raw}");
+        }
+    }
+
+    #[test]
+    fn each_layer_overrides_the_ones_below_it() {
+        let layers = layers(
+            "[defaults]\nwork_interval_minutes = 30\nsnooze_minutes = 10\nsound = \"chime\"\n\
+             [locked]\nstrict_mode = true\n",
+            "snooze_minutes = 7\nsound = \"off\"\n",
+            "sound = \"whitenoise\"\n",
+        );
+        let mut config = table("snooze_minutes = 3\nsound = \"chime\"\nstrict_mode = false\n");
+        let mut diagnostics = Vec::new();
+        layers.apply_defaults(&mut config);
+        layers.apply_overrides(&mut config, &mut diagnostics);
+        assert_eq!(
+            config,
+            table(
+                // Policy default (unset by the user), environment over the user, `--set` over
+                // the environment, and the lock over everything.
+                "work_interval_minutes = 30\nsnooze_minutes = 7\nsound = \"whitenoise\"\n\
+                 strict_mode = true\n"
+            )
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].field, "strict_mode");
+        assert_eq!(diagnostics[0].applied, "true");
+
+        // A user value wins over a policy default.
+        let mut config = table("work_interval_minutes = 20\n");
+        layers.apply_defaults(&mut config);
+        assert_eq!(config["work_interval_minutes"].as_integer(), Some(20));
+    }
+
+    #[test]
+    fn locks_and_minimums_win_over_the_command_line() {
+        let layers = layers(
+            "[locked.schedule]\nenabled = true\n[minimum]\nbreak_duration_seconds = 30\n\
+             long_break_duration_seconds = 120\n",
+            "",
+            "break_duration_seconds = 10\n[schedule]\nenabled = false\n",
+        );
+        let mut config = table("long_break_duration_seconds = 600\n");
+        let mut diagnostics = Vec::new();
+        layers.apply_overrides(&mut config, &mut diagnostics);
+        assert_eq!(config["break_duration_seconds"].as_integer(), Some(30));
+        assert_eq!(config["schedule"]["enabled"].as_bool(), Some(true));
+        // A value above the minimum is left alone.
+        assert_eq!(
+            config["long_break_duration_seconds"].as_integer(),
+            Some(600)
+        );
+        let fields: Vec<&str> = diagnostics.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, ["schedule.enabled", "break_duration_seconds"]);
+    }
+
+    #[test]
+    fn pinned_by_names_the_highest_layer() {
+        let layers = layers(
+            "[locked]\nstrict_mode = true\n[minimum]\nbreak_duration_seconds = 30\n",
+            "strict_mode = false\nsnooze_minutes = 7\n[schedule]\nenabled = true\n",
+            "snooze_minutes = 9\n",
+        );
+        assert_eq!(layers.pinned_by("strict_mode"), Some(Source::Policy));
+        assert_eq!(
+            layers.pinned_by("snooze_minutes"),
+            Some(Source::CommandLine)
+        );
+        assert_eq!(
+            layers.pinned_by("schedule.enabled"),
+            Some(Source::Environment("TWENTY20_SCHEDULE__ENABLED".into()))
+        );
+        assert_eq!(layers.pinned_by("break_duration_seconds"), None);
+        assert_eq!(layers.minimum("break_duration_seconds"), Some(30));
+        assert_eq!(layers.minimum("snooze_minutes"), None);
+    }
+
+    #[test]
+    fn saving_restores_what_the_user_wrote() {
+        let layers = layers(
+            "[defaults]\nsound = \"chime\"\nsnooze_minutes = 10\n[locked]\nstrict_mode = true\n",
+            "work_interval_minutes = 1\n",
+            "[schedule]\nenabled = false\n",
+        );
+        let user = table("work_interval_minutes = 25\nsnooze_minutes = 10\n");
+        // The effective config, after the user changed the sound in the settings window.
+        let mut config = table(
+            "work_interval_minutes = 1\nstrict_mode = true\nsound = \"off\"\n\
+             snooze_minutes = 10\n[schedule]\nenabled = false\nmonday = []\n",
+        );
+        assert!(layers.restore_user_values(&mut config, &user));
+        assert_eq!(
+            config,
+            table(
+                "work_interval_minutes = 25\nsound = \"off\"\nsnooze_minutes = 10\n\
+                 [schedule]\nmonday = []\n"
+            )
+        );
+
+        // Nothing to leave out.
+        let mut config = user.clone();
+        assert!(!Layers::default().restore_user_values(&mut config, &user));
+        assert_eq!(config, user);
+    }
+
+    #[test]
+    fn broken_policy_keeps_the_last_good_one() {
+        let dir = std::env::temp_dir().join(format!("twenty20-policy-{}", std::process::id()));
+        let path = dir.join("policy.toml");
+        let last_good = dir.join("state").join("policy.last-good.toml");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // No policy, and none before it.
+        assert_eq!(read_policy(&path, &last_good), (toml::Table::new(), None));
+
+        // A broken policy with nothing to fall back on applies nothing, and says so.
+        std::fs::write(&path, "[locked\nstrict_mode = true\n").unwrap();
+        let (policy, error) = read_policy(&path, &last_good);
+        assert!(policy.is_empty());
+        assert!(error.unwrap().contains("not valid TOML"));
+
+        let good = "[locked]\nstrict_mode = true\n";
+        std::fs::write(&path, good).unwrap();
+        assert_eq!(read_policy(&path, &last_good), (table(good), None));
+
+        std::fs::write(&path, "[locked]\nstrict_mode = \n").unwrap();
+        let (policy, error) = read_policy(&path, &last_good);
+        assert_eq!(policy, table(good));
+        assert!(error.is_some());
+
+        // Removing the policy lifts it, and forgets the copy.
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read_policy(&path, &last_good), (toml::Table::new(), None));
+        assert!(!last_good.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
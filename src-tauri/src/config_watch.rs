@@ -0,0 +1,100 @@
+//! Hot reload of `config.toml` and the managed policy.
+//!
+//! A poll rather than a file-system watcher: every two seconds each file's modification time
+//! and size (its [`Stamp`]) is compared with the last poll's. That needs no extra dependencies
+//! and copes with editors that save by replacing the file; the price is up to two seconds of
+//! delay, and an edit that keeps both the size and the modification time goes unnoticed until
+//! the next one. A changed stamp reloads `config.toml` with the policy laid over it, and
+//! applies the settings to the running app the same way the settings window's save action
+//! does, with the profile in effect laid on top (see [`crate::profiles`]).
+
+use crate::commands::AppState;
+use crate::config::AppConfig;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// Lock a Mutex, recovering from a poisoned state gracefully.
+macro_rules! lock {
+    ($m:expr) => {
+        $m.lock().unwrap_or_else(|e| e.into_inner())
+    };
+}
+
+/// How often the files' stamps are compared.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Modification time and length of a file, or `None` if it does not exist.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Spawns the watcher task for the lifetime of the application.
+///
+/// A file that fails to parse is reported once per change and otherwise ignored; the running
+/// settings stay in effect until it is fixed.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let path = AppConfig::config_path();
+        let policy = crate::config_layers::policy_path();
+        let mut seen = (stamp(&path), stamp(&policy));
+        let mut deferred = false;
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let current = (stamp(&path), stamp(&policy));
+            if current.0.is_none() || current == seen {
+                continue;
+            }
+            match AppConfig::load() {
+                Err(e) => {
+                    log::error!("{e}");
+                    crate::send_config_error_notification(&app, &e);
+                    seen = current;
+                }
+                Ok(mut config) => {
+                    let diagnostics = config.validate();
+                    if apply(&app, config, current.1 != seen.1) {
+                        for d in &diagnostics {
+                            log::warn!("config.toml: {d}");
+                        }
+                        *lock!(app.state::<AppState>().config_diagnostics) = diagnostics;
+                        seen = current;
+                        deferred = false;
+                    } else if !deferred {
+                        log::warn!("config.toml turns strict mode off — applying after the break");
+                        deferred = true;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Replaces the base config and applies the result via [`crate::profiles::apply_base`].
+///
+/// `policy_changed` forces the effective config to be recomputed even when `config.toml`
+/// reads the same: the policy also applies on top of the profile in effect.
+///
+/// Returns `false`, keeping the previous base config, if the change would turn strict mode off
+/// while a break is on screen; the file stays marked as unseen, so the watcher offers it again
+/// on every poll until the break is over.
+fn apply(app: &AppHandle, new: AppConfig, policy_changed: bool) -> bool {
+    {
+        let state = app.state::<AppState>();
+        let mut base = lock!(state.base_config);
+        // Our own saves (settings window, tray, migrations) also touch the file; there is
+        // nothing to reapply for them.
+        if !policy_changed && toml::to_string(&new).ok() == toml::to_string(&*base).ok() {
+            *base = new;
+            return true;
+        }
+    }
+    let applied = crate::profiles::apply_base(app, new);
+    if applied {
+        log::info!("config.toml changed on disk — reloaded");
+    }
+    applied
+}
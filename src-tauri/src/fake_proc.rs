@@ -0,0 +1,54 @@
+//! A fake `/proc` tree for the tests of the Linux detectors that read it (meeting apps,
+//! cameras and microphones in use).
+
+use std::path::{Path, PathBuf};
+
+/// A fake proc tree under the temp directory, removed when dropped.
+pub struct FakeProc(PathBuf);
+
+impl FakeProc {
+    /// An empty tree; `name` must be unique among the tests.
+    pub fn new(name: &str) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("twenty20-proc-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Adds process `pid` with the given `comm` and NUL-separated `argv`.
+    pub fn process(&self, pid: &str, comm: Option<&str>, argv: &[&str]) -> &Self {
+        let dir = self.0.join(pid);
+        std::fs::create_dir_all(&dir).unwrap();
+        if let Some(comm) = comm {
+            std::fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+        }
+        let mut cmdline = argv.join("\0");
+        if !argv.is_empty() {
+            cmdline.push('\0');
+        }
+        std::fs::write(dir.join("cmdline"), cmdline).unwrap();
+        self
+    }
+
+    /// Adds process `pid` called `comm` with file descriptors linked to `targets`.
+    pub fn process_with_fds(&self, pid: &str, comm: &str, targets: &[&str]) -> &Self {
+        self.process(pid, Some(comm), &[]);
+        let fds = self.0.join(pid).join("fd");
+        std::fs::create_dir_all(&fds).unwrap();
+        for (fd, target) in targets.iter().enumerate() {
+            std::os::unix::fs::symlink(target, fds.join(fd.to_string())).unwrap();
+        }
+        self
+    }
+}
+
+impl Drop for FakeProc {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
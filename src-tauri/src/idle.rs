@@ -0,0 +1,194 @@
+//! User idle-time detection.
+//!
+//! Reports how long it has been since the last keyboard or pointer input so the timer loop
+//! can pause the work countdown while the user is away and credit long absences as breaks.
+//! Every backend is best-effort: `None` means "unknown", never "active".
+//!
+//! - macOS: `CGEventSourceSecondsSinceLastEventType` (CoreGraphics, raw FFI).
+//! - Linux: `org.freedesktop.ScreenSaver.GetSessionIdleTime` (KDE and most other desktops) or
+//!   GNOME's `org.gnome.Mutter.IdleMonitor.GetIdletime` over the session bus (via `gdbus`),
+//!   falling back to the XScreenSaver extension (via `xprintidle`, X11 only).
+
+/// A source of user idle time.
+pub trait IdleSource: Send + Sync {
+    /// Seconds since the last user input, or `None` if it cannot be determined.
+    fn idle_seconds(&self) -> Option<u32>;
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::IdleSource;
+
+    // kCGEventSourceStateCombinedSessionState = 0
+    const KCG_EVENT_SOURCE_STATE_COMBINED_SESSION: i32 = 0;
+    // kCGAnyInputEventType = ~0
+    const KCG_ANY_INPUT_EVENT_TYPE: u32 = !0u32;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state_id: i32, event_type: u32) -> f64;
+    }
+
+    /// Idle time from the combined HID session state. Needs no permissions.
+    pub struct CoreGraphicsIdle;
+
+    impl IdleSource for CoreGraphicsIdle {
+        fn idle_seconds(&self) -> Option<u32> {
+            let secs = unsafe {
+                CGEventSourceSecondsSinceLastEventType(
+                    KCG_EVENT_SOURCE_STATE_COMBINED_SESSION,
+                    KCG_ANY_INPUT_EVENT_TYPE,
+                )
+            };
+            (secs.is_finite() && secs >= 0.0).then_some(secs as u32)
+        }
+    }
+
+    pub fn default_source() -> Box<dyn IdleSource> {
+        Box::new(CoreGraphicsIdle)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::IdleSource;
+    use crate::subprocess::output;
+
+    /// Tries each source in order and returns the first reading.
+    pub struct FirstAvailable(pub Vec<Box<dyn IdleSource>>);
+
+    impl IdleSource for FirstAvailable {
+        fn idle_seconds(&self) -> Option<u32> {
+            self.0.iter().find_map(|s| s.idle_seconds())
+        }
+    }
+
+    /// `org.freedesktop.ScreenSaver.GetSessionIdleTime`, supported by KDE and most other
+    /// desktops, but not GNOME: its screen saver service does not implement the method.
+    pub struct ScreenSaverDbus;
+
+    impl IdleSource for ScreenSaverDbus {
+        fn idle_seconds(&self) -> Option<u32> {
+            let out = output(
+                "gdbus",
+                &[
+                    "call",
+                    "--session",
+                    "--dest",
+                    "org.freedesktop.ScreenSaver",
+                    "--object-path",
+                    "/org/freedesktop/ScreenSaver",
+                    "--method",
+                    "org.freedesktop.ScreenSaver.GetSessionIdleTime",
+                ],
+            )?;
+            idle_seconds_from_gdbus(&out)
+        }
+    }
+
+    /// GNOME's idle monitor, on Wayland and X11 alike.
+    pub struct MutterIdleMonitor;
+
+    impl IdleSource for MutterIdleMonitor {
+        fn idle_seconds(&self) -> Option<u32> {
+            let out = output(
+                "gdbus",
+                &[
+                    "call",
+                    "--session",
+                    "--dest",
+                    "org.gnome.Mutter.IdleMonitor",
+                    "--object-path",
+                    "/org/gnome/Mutter/IdleMonitor/Core",
+                    "--method",
+                    "org.gnome.Mutter.IdleMonitor.GetIdletime",
+                ],
+            )?;
+            idle_seconds_from_gdbus(&out)
+        }
+    }
+
+    fn idle_seconds_from_gdbus(output: &str) -> Option<u32> {
+        u32::try_from(parse_gdbus_idle_ms(output)? / 1000).ok()
+    }
+
+    /// XScreenSaver extension idle counter, via the `xprintidle` helper (X11 sessions only).
+    pub struct XScreenSaver;
+
+    impl IdleSource for XScreenSaver {
+        fn idle_seconds(&self) -> Option<u32> {
+            let ms: u64 = output("xprintidle", &[])?.trim().parse().ok()?;
+            u32::try_from(ms / 1000).ok()
+        }
+    }
+
+    /// Parses `gdbus` output of the form `(uint32 12345,)` (ScreenSaver) or `(uint64 12345,)`
+    /// (Mutter) into milliseconds.
+    pub fn parse_gdbus_idle_ms(output: &str) -> Option<u64> {
+        let digits: String = output
+            .trim()
+            .trim_start_matches('(')
+            .trim_start_matches("uint32")
+            .trim_start_matches("uint64")
+            .trim()
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    }
+
+    pub fn default_source() -> Box<dyn IdleSource> {
+        Box::new(FirstAvailable(vec![
+            Box::new(ScreenSaverDbus),
+            Box::new(MutterIdleMonitor),
+            Box::new(XScreenSaver),
+        ]))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+mod other {
+    use super::IdleSource;
+
+    /// Idle detection is unsupported on this platform.
+    pub struct NoIdle;
+
+    impl IdleSource for NoIdle {
+        fn idle_seconds(&self) -> Option<u32> {
+            None
+        }
+    }
+
+    pub fn default_source() -> Box<dyn IdleSource> {
+        Box::new(NoIdle)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::default_source;
+#[cfg(target_os = "macos")]
+pub use macos::default_source;
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub use other::default_source;
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::linux::parse_gdbus_idle_ms;
+
+    #[test]
+    fn gdbus_replies_parse_to_milliseconds() {
+        let cases = [
+            ("(uint32 12345,)\n", Some(12345)),
+            ("(uint64 5000000000,)\n", Some(5_000_000_000)),
+            ("(0,)", Some(0)),
+            ("", None),
+            (
+                "Error: GDBus.Error:org.freedesktop.DBus.Error.NotSupported",
+                None,
+            ),
+        ];
+        for (output, want) in cases {
+            assert_eq!(parse_gdbus_idle_ms(output), want, "{output:?}");
+        }
+    }
+}
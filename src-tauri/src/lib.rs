@@ -1,21 +1,54 @@
+// Without the app much of the core is only reached from tests.
+#![cfg_attr(not(feature = "app"), allow(dead_code, unused_imports))]
+
+mod app_rules;
+#[cfg(feature = "app")]
 mod audio;
+mod av_devices;
+mod caldav;
+mod calendar;
+mod cli;
+#[cfg(feature = "app")]
 mod commands;
 mod config;
+mod config_layers;
+#[cfg(feature = "app")]
+mod config_watch;
+#[cfg(all(test, target_os = "linux"))]
+mod fake_proc;
+mod idle;
 mod meeting;
+mod network;
+#[cfg(feature = "app")]
 mod overlay;
+mod presentation;
+mod profiles;
+mod schedule;
+#[cfg(feature = "app")]
 mod settings_window;
 mod sleep_watch;
+#[cfg(feature = "app")]
 mod strict_mode;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod subprocess;
+mod themes;
 mod timer;
+#[cfg(feature = "app")]
 mod tray;
 
+#[cfg(feature = "app")]
 use commands::AppState;
+#[cfg(feature = "app")]
 use config::AppConfig;
+#[cfg(feature = "app")]
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "app")]
 use tauri::{Emitter, Manager};
+#[cfg(feature = "app")]
 use timer::SharedTimerState;
 
 /// Lock a Mutex, recovering from a poisoned state gracefully.
+#[cfg(feature = "app")]
 macro_rules! lock {
     ($m:expr) => {
         $m.lock().unwrap_or_else(|e| e.into_inner())
@@ -35,12 +68,37 @@ macro_rules! lock {
 /// // Starts the Twenty20 Tauri application; this call does not return until the app exits.
 /// twenty20_lib::run();
 /// ```
+#[cfg(feature = "app")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
-    let config = AppConfig::load();
-    let timer_state = Arc::new(Mutex::new(timer::restore_or_create(&config)));
+    // A broken config file is left untouched; run on defaults (still subject to the managed
+    // policy) and tell the user.
+    let (mut config, config_error) = match AppConfig::load() {
+        Ok(config) => (config, None),
+        Err(e) => {
+            log::error!("{e}");
+            (AppConfig::layered_default(), Some(e))
+        }
+    };
+    let diagnostics = config.validate();
+    for d in &diagnostics {
+        log::warn!("config.toml: {d}");
+    }
+    // Network-based profiles are picked up by the first `profiles` poll.
+    let (effective, active_profile) = profiles::effective(&config, &[]);
+    let restored = timer::restore_or_create(&timer::state_path(), &effective);
+    let timer_state = Arc::new(Mutex::new(timer::BreakScheduler::new(
+        restored.state,
+        &effective,
+    )));
+    let resumed_break = restored.active_break;
 
     tauri::Builder::default()
         .plugin(tauri_plugin_autostart::init(
@@ -50,13 +108,18 @@ pub fn run() {
         .plugin(tauri_plugin_notification::init())
         .manage(AppState {
             timer: Arc::clone(&timer_state),
-            config: Mutex::new(config),
+            config: Mutex::new(effective),
+            base_config: Mutex::new(config),
+            active_profile: Mutex::new(active_profile),
+            config_diagnostics: Mutex::new(diagnostics),
             tray_menu: Mutex::new(None),
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_overlay_config,
             commands::force_skip_break,
+            commands::snooze_break,
             commands::test_sound,
+            commands::get_theme_pack,
         ])
         .setup(move |app| {
             #[cfg(target_os = "macos")]
@@ -65,6 +128,20 @@ pub fn run() {
             // Build the system tray.
             tray::setup_tray(app)?;
 
+            if let Some(error) = &config_error {
+                send_config_error_notification(app.handle(), error);
+            }
+
+            // Pick up hand edits to config.toml while running.
+            config_watch::spawn(app.handle().clone());
+            profiles::spawn(app.handle().clone());
+
+            // Reopen a strict break that was on screen when the app last quit.
+            if let Some(saved) = resumed_break {
+                let effects = lock!(timer_state).resume_break(saved);
+                apply_effects(app.handle(), effects);
+            }
+
             // Wire sleep/wake awareness: bridge IOKit notifications into the timer loop.
             let (sleep_tx, sleep_rx) = tokio::sync::watch::channel::<bool>(false);
             sleep_watch::setup(sleep_tx);
@@ -89,278 +166,252 @@ pub fn run() {
         });
 }
 
-/// Runs the main timer loop that drives work/break countdowns, emits UI events, manages overlays and strict input suppression, and polls for meetings.
+/// Runs the main timer loop: a thin adapter between the Tauri runtime and the
+/// [`timer::BreakScheduler`].
+///
+/// The loop feeds the scheduler sleep/wake transitions, idle-time readings (polled every
+/// 5 seconds), meeting detection, per-application rule and calendar results (polled every 30
+/// seconds) and [`timer::Input::Tick`]s, then applies the returned [`timer::Effect`]s. All
+/// break logic lives in the scheduler.
 ///
-/// The loop ticks once per second and:
-/// - Decrements the work timer and emits `timer:tick` events for UI updates.
-/// - Sends a pre-break notification when configured lead time is reached.
-/// - Transitions to a break phase when the work timer reaches zero, opens overlays, enables strict mode if configured, emits `break:start`, counts down the break, then emits `break:end` and resets the work timer.
-/// - Detects meetings periodically and pauses/resumes the timer with a `Meeting` pause reason; if a meeting starts during a break, it will close overlays and reset the break state.
-/// - Handles manual pauses with an optional auto-resume timeout.
-/// - Persists timer state after updates.
+/// Timing never depends on how often the loop runs: the scheduler measures elapsed time on the
+/// monotonic clock, so a late or blocked tick is caught up rather than lost. Ticks fire once a
+/// second for the UI and additionally at [`timer::BreakScheduler::next_deadline`], so a break
+/// starts and ends on time. Detectors run on blocking threads and report back over a channel,
+/// so a slow poll (osascript can take seconds) never holds up a tick.
 ///
-/// Note: This function runs indefinitely until the application exits. It uses the managed `AppState` config and the shared `SharedTimerState` to drive behavior and emits events on the provided `app` handle.
+/// Note: This function runs indefinitely until the application exits.
 ///
 /// # Examples
 ///
 /// ```no_run
 /// # use tokio::spawn;
 /// # // `app` and `timer` are provided by the application environment.
-/// // spawn(async move { run_timer_loop(app, timer).await });
+/// // spawn(async move { run_timer_loop(app, timer, sleep_rx).await });
 /// ```
+#[cfg(feature = "app")]
 async fn run_timer_loop(
     app: tauri::AppHandle,
     timer: SharedTimerState,
-    sleep_rx: tokio::sync::watch::Receiver<bool>,
+    mut sleep_rx: tokio::sync::watch::Receiver<bool>,
 ) {
+    use std::sync::atomic::AtomicBool;
     use std::time::Duration;
-    use tokio::time::sleep;
-
-    let mut meeting_poll_counter = 0u32;
-    // Track the break phase locally (not in shared state to avoid extra locking).
-    let mut break_active = false;
-    let mut break_seconds_left: u32 = 0;
-    let mut notified_pre_warning = false;
-    // Throttle disk persistence: only write every 30 ticks (≈ 30 s).
-    let mut persist_counter: u32 = 0;
-    // Track sleep state to detect transitions.
-    let mut was_sleeping = false;
-    tray::update_icon(&app, tray::TrayIconState::Open);
+    use timer::Input;
+    use tokio::time::{interval_at, sleep_until, Instant, MissedTickBehavior};
+
+    let every = |secs: u64| {
+        let period = Duration::from_secs(secs);
+        let mut interval = interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    };
+    let mut ui_tick = every(1);
+    let mut idle_poll = every(5);
+    let mut meeting_poll = every(30);
+
+    let idle_source: Arc<dyn idle::IdleSource> = Arc::from(idle::default_source());
+    let idle_busy = Arc::new(AtomicBool::new(false));
+    let meeting_busy = Arc::new(AtomicBool::new(false));
+    let app_rules_busy = Arc::new(AtomicBool::new(false));
+    let calendar_busy = Arc::new(AtomicBool::new(false));
+    let presentation_busy = Arc::new(AtomicBool::new(false));
+    let (poll_tx, mut poll_rx) = tokio::sync::mpsc::unbounded_channel::<Input>();
+
+    let mut is_sleeping = false;
+    let mut sleep_watch_alive = true;
+    tray::update_icon(&app, timer::TrayIconState::Open);
 
     loop {
-        sleep(Duration::from_secs(1)).await;
-
-        // --- Sleep/wake awareness (checked at the top of every tick) ---
-        let is_sleeping = *sleep_rx.borrow();
-
-        if !was_sleeping && is_sleeping {
-            // Transition: awake → sleeping.
-            overlay::close_overlays(&app);
-            strict_mode::disable_strict_input_suppression();
-            break_active = false;
-            notified_pre_warning = false;
-            log::info!("System sleeping — timer loop suspended");
-            was_sleeping = true;
-            continue;
-        }
-
-        if was_sleeping && !is_sleeping {
-            // Transition: sleeping → awake — reset work timer to a fresh cycle.
-            {
-                let mut ts = lock!(timer);
-                ts.seconds_remaining = ts.work_interval_seconds;
-                ts.is_paused = false;
-                ts.pause_reason = None;
-                ts.manual_pause_seconds_remaining = None;
-                timer::persist_state(&ts);
-            }
-            tray::update_icon(&app, tray::TrayIconState::Open);
-            {
-                let ts = lock!(timer);
-                let _ = app.emit(
-                    "timer:tick",
-                    serde_json::json!({
-                        "seconds_remaining": ts.seconds_remaining,
-                        "is_paused": false,
-                        "pause_reason": null,
-                    }),
-                );
-            }
-            meeting_poll_counter = 0;
-            log::info!("System woke — timer reset to full cycle");
-            was_sleeping = false;
-            continue;
-        }
-
-        if is_sleeping {
-            // Mid-sleep tick (was_sleeping == true, is_sleeping == true).
-            continue;
-        }
-
-        let (config_interval, config_break_dur, is_strict, meeting_detection, pre_warning_secs) = {
-            let app_state = app.state::<AppState>();
-            let cfg = lock!(app_state.config);
-            (
-                cfg.work_interval_minutes * 60,
-                cfg.break_duration_seconds,
-                cfg.strict_mode,
-                cfg.meeting_detection,
-                cfg.pre_warning_seconds,
-            )
-        };
-
-        // --- Meeting detection (every 30 seconds, offloaded to a blocking thread) ---
-        meeting_poll_counter += 1;
-        if meeting_detection && meeting_poll_counter >= 30 {
-            meeting_poll_counter = 0;
-
-            let meeting_now = tokio::task::spawn_blocking(meeting::is_meeting_active)
-                .await
-                .unwrap_or(false);
-
-            let currently_meeting_paused = {
-                let ts = lock!(timer);
-                matches!(ts.pause_reason, Some(timer::PauseReason::Meeting))
-            };
-
-            if meeting_now && !currently_meeting_paused {
-                log::info!("Meeting detected — pausing timer");
-                if break_active {
-                    overlay::close_overlays(&app);
-                    strict_mode::disable_strict_input_suppression();
-                    break_active = false;
-                    let mut ts = lock!(timer);
-                    ts.seconds_remaining = config_interval;
-                    ts.is_paused = true;
-                    ts.pause_reason = Some(timer::PauseReason::Meeting);
+        let deadline = lock!(timer).next_deadline();
+        let input = tokio::select! {
+            _ = ui_tick.tick() => Input::Tick,
+            _ = sleep_until(deadline.map_or_else(Instant::now, Instant::from_std)),
+                if deadline.is_some() => Input::Tick,
+
+            // --- Sleep/wake awareness ---
+            changed = sleep_rx.changed(), if sleep_watch_alive => {
+                if changed.is_err() {
+                    sleep_watch_alive = false;
+                    continue;
+                }
+                let sleeping = *sleep_rx.borrow_and_update();
+                if sleeping == is_sleeping {
+                    continue;
+                }
+                is_sleeping = sleeping;
+                meeting_poll.reset();
+                if sleeping {
+                    Input::Sleep
                 } else {
-                    let mut ts = lock!(timer);
-                    ts.is_paused = true;
-                    ts.pause_reason = Some(timer::PauseReason::Meeting);
+                    Input::Wake
                 }
-            } else if !meeting_now && currently_meeting_paused {
-                log::info!("Meeting ended — resuming timer");
-                let mut ts = lock!(timer);
-                ts.is_paused = false;
-                ts.pause_reason = None;
             }
-        }
 
-        // --- Break countdown phase ---
-        if break_active {
-            // Decrement first, then check for completion (fixes off-by-one so the
-            // break lasts exactly config_break_dur seconds).
-            break_seconds_left = break_seconds_left.saturating_sub(1);
-            if break_seconds_left == 0 {
-                break_active = false;
-                notified_pre_warning = false;
-                overlay::close_overlays(&app);
-                strict_mode::disable_strict_input_suppression();
-                let _ = app.emit("break:end", serde_json::json!({ "force_skipped": false }));
-                let mut ts = lock!(timer);
-                ts.seconds_remaining = config_interval;
-                ts.is_paused = false;
-                ts.pause_reason = None;
-                timer::persist_state(&ts);
-                log::info!("Break complete — restarting work timer");
-                tray::update_icon(&app, tray::TrayIconState::Open);
-            } else {
-                overlay::emit_break_tick(&app, break_seconds_left);
+            // Results of polls started before a sleep are stale.
+            Some(input) = poll_rx.recv() => {
+                if is_sleeping {
+                    continue;
+                }
+                input
             }
-            continue;
-        }
 
-        // --- Work timer countdown ---
-        let paused = lock!(timer).is_paused;
-
-        if paused {
-            // Handle manual pause auto-resume.
-            {
-                let mut ts = lock!(timer);
-                match ts.manual_pause_seconds_remaining {
-                    Some(0) => {
-                        ts.manual_pause_seconds_remaining = None;
-                        if matches!(ts.pause_reason, Some(timer::PauseReason::Manual)) {
-                            ts.is_paused = false;
-                            ts.pause_reason = None;
-                        }
-                    }
-                    Some(ref mut r) => {
-                        *r -= 1;
-                    }
-                    None => {}
+            // --- Idle detection (Linux backends spawn a helper process) ---
+            _ = idle_poll.tick() => {
+                let enabled = lock!(app.state::<AppState>().config).idle_pause_seconds > 0;
+                if enabled && !is_sleeping {
+                    let source = Arc::clone(&idle_source);
+                    spawn_poll(&idle_busy, &poll_tx, move || {
+                        source.idle_seconds().map(|seconds| Input::Idle { seconds })
+                    });
                 }
+                continue;
             }
 
-            // Emit tick so the tray popover keeps updating while paused.
-            let ts = lock!(timer);
-            let _ = app.emit(
-                "timer:tick",
-                serde_json::json!({
-                    "seconds_remaining": ts.seconds_remaining,
-                    "is_paused": ts.is_paused,
-                    "pause_reason": ts.pause_reason,
-                }),
-            );
-
-            // Update native tray menu
-            let (is_strict, _) = {
-                let app_state = app.state::<AppState>();
-                let cfg = lock!(app_state.config);
-                (cfg.strict_mode, cfg.break_duration_seconds)
-            };
-            update_tray_menu(&app, ts.seconds_remaining, true, is_strict);
-
-            maybe_persist(&timer, &mut persist_counter);
-            continue;
-        }
-
-        // Tick the work timer.
-        let seconds_remaining = {
-            let mut ts = lock!(timer);
-            if ts.seconds_remaining > 0 {
-                ts.seconds_remaining -= 1;
+            // --- Meeting detection, per-application rules and calendars ---
+            _ = meeting_poll.tick() => {
+                let (detectors, rules, calendar, presentations) = {
+                    let state = app.state::<AppState>();
+                    let config = lock!(state.config);
+                    let detectors = config
+                        .meeting_detection
+                        .then(|| meeting::Registry::from_config(&config));
+                    (
+                        detectors,
+                        config.app_rules.clone(),
+                        config.calendar.clone(),
+                        config.presentation_detection,
+                    )
+                };
+                if is_sleeping {
+                    continue;
+                }
+                if rules.is_empty() {
+                    let _ = poll_tx.send(Input::AppRule(None));
+                } else {
+                    spawn_poll(&app_rules_busy, &poll_tx, move || {
+                        Some(Input::AppRule(app_rules::evaluate(&rules)))
+                    });
+                }
+                if presentations {
+                    spawn_poll(&presentation_busy, &poll_tx, || {
+                        Some(Input::Presentation(presentation::detect()))
+                    });
+                } else {
+                    let _ = poll_tx.send(Input::Presentation(None));
+                }
+                if calendar.files.is_empty() && calendar.caldav.is_empty() {
+                    let _ = poll_tx.send(Input::Calendar {
+                        current: None,
+                        next: None,
+                    });
+                } else {
+                    spawn_poll(&calendar_busy, &poll_tx, move || {
+                        let (current, next) = calendar::lookahead(&calendar, chrono::Local::now());
+                        Some(Input::Calendar { current, next })
+                    });
+                }
+                if let Some(detectors) = detectors {
+                    spawn_poll(&meeting_busy, &poll_tx, move || {
+                        Some(match detectors.detect() {
+                            Some(signal) => Input::MeetingStarted(signal),
+                            None => Input::MeetingEnded,
+                        })
+                    });
+                }
+                continue;
             }
-            ts.seconds_remaining
         };
+        let effects = lock!(timer).handle(input);
+        apply_effects(&app, effects);
+    }
+}
 
-        // Pre-break notification.
-        if !notified_pre_warning && pre_warning_secs > 0 && seconds_remaining == pre_warning_secs {
-            notified_pre_warning = true;
-            send_pre_break_notification(&app, pre_warning_secs);
-            tray::update_icon(&app, tray::TrayIconState::Blink);
-        }
-
-        // Emit tick.
-        {
-            let ts = lock!(timer);
-            let _ = app.emit(
-                "timer:tick",
-                serde_json::json!({
-                    "seconds_remaining": ts.seconds_remaining,
-                    "is_paused": false,
-                    "pause_reason": null,
-                }),
-            );
+/// Runs a detector on a blocking thread and sends its result to the timer loop.
+///
+/// At most one poll per detector is in flight: while `busy` is set, further polls are dropped.
+#[cfg(feature = "app")]
+fn spawn_poll<F>(
+    busy: &Arc<std::sync::atomic::AtomicBool>,
+    tx: &tokio::sync::mpsc::UnboundedSender<timer::Input>,
+    poll: F,
+) where
+    F: FnOnce() -> Option<timer::Input> + Send + 'static,
+{
+    use std::sync::atomic::Ordering;
+
+    if busy.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let busy = Arc::clone(busy);
+    let tx = tx.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(input) = poll() {
+            let _ = tx.send(input);
         }
+        busy.store(false, Ordering::Release);
+    });
+}
 
-        // Update native tray
-        update_tray_menu(&app, seconds_remaining, false, is_strict);
-
-        maybe_persist(&timer, &mut persist_counter);
-
-        // Trigger break.
-        if seconds_remaining == 0 {
-            log::info!("Break time! Opening overlays.");
-            break_active = true;
-            break_seconds_left = config_break_dur;
-
-            if is_strict {
-                strict_mode::enable_strict_input_suppression();
+/// Performs the side effects requested by the [`timer::BreakScheduler`].
+///
+/// Must be called without holding the timer lock: [`timer::Effect::Persist`] locks it.
+#[cfg(feature = "app")]
+pub(crate) fn apply_effects(app: &tauri::AppHandle, effects: Vec<timer::Effect>) {
+    use timer::Effect;
+
+    for effect in effects {
+        match effect {
+            Effect::OpenOverlays { info, strict } => {
+                let theme = lock!(app.state::<AppState>().config).overlay_theme.clone();
+                overlay::open_overlays(app, &info, strict, &theme)
+            }
+            Effect::CloseOverlays => overlay::close_overlays(app),
+            Effect::EnableStrictInput => strict_mode::enable_strict_input_suppression(),
+            Effect::DisableStrictInput => strict_mode::disable_strict_input_suppression(),
+            Effect::PlaySound => audio::play_break_sound(app),
+            Effect::Notify {
+                lead_seconds,
+                snooze_seconds,
+            } => send_pre_break_notification(app, lead_seconds, snooze_seconds),
+            Effect::Remind { name, message } => send_reminder_notification(app, &name, &message),
+            Effect::SetIcon(icon) => tray::update_icon(app, icon),
+            Effect::Tick(status) => {
+                let _ = app.emit("timer:tick", &status);
+                let is_strict = {
+                    let app_state = app.state::<AppState>();
+                    let cfg = lock!(app_state.config);
+                    cfg.strict_mode
+                };
+                update_tray_menu(app, &status, is_strict);
+                tray::update_reminder_items(app, &status.reminders);
+            }
+            Effect::BreakTick { seconds_remaining } => {
+                overlay::emit_break_tick(app, seconds_remaining);
+            }
+            Effect::BreakStarted { duration, kind } => {
+                let _ = app.emit(
+                    "break:start",
+                    serde_json::json!({ "duration": duration, "kind": kind }),
+                );
+            }
+            Effect::BreakEnded { force_skipped } => {
+                let _ = app.emit(
+                    "break:end",
+                    serde_json::json!({ "force_skipped": force_skipped }),
+                );
+            }
+            Effect::Persist => {
+                let app_state = app.state::<AppState>();
+                let ts = lock!(app_state.timer);
+                timer::persist_state(&timer::state_path(), &ts.state, ts.active_break());
             }
-
-            overlay::open_overlays(&app, config_break_dur, is_strict);
-            audio::play_break_sound(&app);
-            tray::update_icon(&app, tray::TrayIconState::Rest);
-            let _ = app.emit(
-                "break:start",
-                serde_json::json!({ "duration": config_break_dur }),
-            );
         }
     }
 }
 
-/// Persist timer state at most once every 30 seconds.
-fn maybe_persist(timer: &SharedTimerState, counter: &mut u32) {
-    *counter += 1;
-    if *counter >= 30 {
-        *counter = 0;
-        let ts = lock!(timer);
-        timer::persist_state(&ts);
-    }
-}
-
-fn send_pre_break_notification(app: &tauri::AppHandle, lead_seconds: u32) {
+#[cfg(feature = "app")]
+fn send_pre_break_notification(app: &tauri::AppHandle, lead_seconds: u32, snooze_seconds: u32) {
     let minutes = lead_seconds / 60;
     let secs = lead_seconds % 60;
 
@@ -375,23 +426,57 @@ fn send_pre_break_notification(app: &tauri::AppHandle, lead_seconds: u32) {
         ),
     };
 
+    // Desktop notifications cannot carry action buttons, so point at the tray instead.
+    let body = if snooze_seconds > 0 {
+        format!(
+            "Eye break in {label} — get ready to look away, or snooze {} min from the menu bar",
+            snooze_seconds / 60
+        )
+    } else {
+        format!("Eye break in {label} — get ready to look away")
+    };
+
     use tauri_plugin_notification::NotificationExt;
     let _ = app
         .notification()
         .builder()
         .title("Twenty20")
-        .body(format!("Eye break in {label} — get ready to look away"))
+        .body(body)
         .show();
     log::info!("Pre-break notification: break in {label}");
 }
 
+#[cfg(feature = "app")]
+pub(crate) fn send_config_error_notification(app: &tauri::AppHandle, error: &str) {
+    use tauri_plugin_notification::NotificationExt;
+    let _ = app
+        .notification()
+        .builder()
+        .title("Twenty20 — config.toml could not be loaded")
+        .body(format!(
+            "{error}\nUsing default settings; the file was left unchanged."
+        ))
+        .show();
+}
+
+#[cfg(feature = "app")]
+fn send_reminder_notification(app: &tauri::AppHandle, name: &str, message: &str) {
+    use tauri_plugin_notification::NotificationExt;
+    let _ = app
+        .notification()
+        .builder()
+        .title(format!("Twenty20 — {name}"))
+        .body(message)
+        .show();
+    log::info!("Reminder notification: {name}");
+}
+
 /// Updates the system tray menu items (timer label, enabled states) based on current state.
-fn update_tray_menu(
-    app: &tauri::AppHandle,
-    seconds_remaining: u32,
-    is_paused: bool,
-    is_strict_mode: bool,
-) {
+///
+/// The label names the kind of the upcoming break when long breaks are enabled, and shows the
+/// postponed break time while snoozed.
+#[cfg(feature = "app")]
+fn update_tray_menu(app: &tauri::AppHandle, status: &timer::TimerStatus, is_strict_mode: bool) {
     use tauri::menu::MenuItemKind;
     // We access the menu via AppState since TrayIcon doesn't expose it safely in v2
     let state = app.state::<AppState>();
@@ -415,12 +500,65 @@ fn update_tray_menu(
     };
 
     // Helper to format time
-    let label = if is_paused {
-        "Paused".to_string()
+    let mmss = |secs: u32| format!("{:02}:{:02}", secs / 60, secs % 60);
+    let label = match status.pause_reason {
+        Some(timer::PauseReason::Snoozed) => {
+            let until_break =
+                status.pause_seconds_remaining.unwrap_or(0) + status.seconds_remaining;
+            format!("Snoozed — break in {}", mmss(until_break))
+        }
+        Some(timer::PauseReason::OutsideSchedule) => match status.off_until {
+            Some(t) => format!("Off until {}", t.format("%a %H:%M")),
+            None => "Off (outside working hours)".to_string(),
+        },
+        Some(timer::PauseReason::Meeting) => match &status.meeting {
+            Some(meeting) => format!("Paused — {meeting}"),
+            None => "Paused — meeting".to_string(),
+        },
+        Some(timer::PauseReason::Calendar) => match &status.calendar_event {
+            Some(event) => format!(
+                "Paused — {} until {}",
+                event.summary,
+                event.end.format("%H:%M")
+            ),
+            None => "Paused — calendar event".to_string(),
+        },
+        Some(timer::PauseReason::App) => match &status.app_rule {
+            Some(app) => format!("Paused — {app}"),
+            None => "Paused".to_string(),
+        },
+        _ if status.is_paused => "Paused".to_string(),
+        _ if status.deferred => match status.presentation.as_ref().or(status.app_rule.as_ref()) {
+            Some(what) => format!("Break deferred — {what}"),
+            None => "Break deferred".to_string(),
+        },
+        _ => {
+            let kind = match status.next_break {
+                None => "",
+                Some(timer::BreakKind::Long) => "long ",
+                Some(_) => "short ",
+            };
+            format!("Next {kind}break in {}", mmss(status.seconds_remaining))
+        }
+    };
+    let snooze_label = if status.snooze_seconds > 0 {
+        format!(
+            "Snooze break {} min ({} left)",
+            status.snooze_seconds / 60,
+            status.snoozes_left
+        )
+    } else if status.snoozes_left == 0 && !is_strict_mode {
+        "No snoozes left this cycle".to_string()
     } else {
-        let m = seconds_remaining / 60;
-        let s = seconds_remaining % 60;
-        format!("Next break in {:02}:{:02}", m, s)
+        "Snooze break".to_string()
+    };
+
+    let skip_label = match status.skips_left {
+        Some(left) if !is_strict_mode => format!(
+            "Skip next break ({left} skip{} left today)",
+            if left == 1 { "" } else { "s" }
+        ),
+        _ => "Skip next break".to_string(),
     };
 
     let items = match menu.items() {
@@ -434,13 +572,17 @@ fn update_tray_menu(
             if id == "next_break" {
                 let _ = i.set_text(&label);
             } else if id == "skip" || id == "pause_30" || id == "pause_1h" {
-                // Disable skip/pause if strict mode is on.
-                // Also if already paused, "pause" buttons could be disabled or changed to resume,
-                // but for now let's just respect strict mode for enabling/disabling.
-                // Logic: If strict mode -> disabled.
-                // If not strict mode -> enabled.
+                // Disable skip/pause if strict mode is on or today's skip budget is spent.
                 // (Simplification: even if paused, we might allow clicking pause to extend, or skip to reset)
-                let _ = i.set_enabled(!is_strict_mode);
+                let _ = i.set_enabled(!is_strict_mode && status.skips_left != Some(0));
+                if id == "skip" {
+                    let _ = i.set_text(&skip_label);
+                }
+            } else if id == "work_late" {
+                let _ = i.set_enabled(status.work_late_available);
+            } else if id == "snooze" {
+                let _ = i.set_text(&snooze_label);
+                let _ = i.set_enabled(status.snooze_seconds > 0);
             }
         }
     }
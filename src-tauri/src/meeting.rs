@@ -1,198 +1,540 @@
-/// Meeting detection for macOS.
-///
-/// Three layers polled every 30 seconds, fully local (no network):
-///   1. Native app bundle IDs via NSWorkspace.
-///   2. Window title matching via `lsappinfo` / AppleScript (requires Accessibility).
-///   3. Camera/microphone in-use indicator (best-effort, MVP stub).
+//! Meeting detection.
+//!
+//! Each way of spotting a meeting is a [`MeetingDetector`]; the platform's detectors are
+//! collected in a [`Registry`] built from the config, which the timer loop polls every 30
+//! seconds. The first detector that reports a [`MeetingSignal`] wins, and the signal says what
+//! matched so the tray can show e.g. "Paused — Zoom meeting". Everything is local (no network).
+//!
+//! On macOS, in order:
+//!   1. Conferencing apps from `meeting_apps`, by bundle ID via NSWorkspace.
+//!   2. Browser window titles matching `meeting_titles` via AppleScript (requires Accessibility).
+//!   3. A camera or microphone in use by any app, via CoreMediaIO and CoreAudio.
+//!
+//! On Linux, in order:
+//!   1. Conferencing clients from `meeting_apps`, by process name under `/proc`.
+//!   2. Uncorked microphone capture streams, via `pactl list source-outputs` (PulseAudio or
+//!      PipeWire's PulseAudio server).
+//!   3. Any process holding a camera or ALSA capture device open.
+
+use crate::av_devices::DeviceKind;
+use crate::config::{AppConfig, MeetingApp};
+
+/// A detected meeting: which detector matched, on what.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeetingSignal {
+    /// Name of the detector that matched, see [`MeetingDetector::name`].
+    pub detector: &'static str,
+    /// What matched: a bundle ID, a title pattern…
+    pub matched: String,
+    /// How the tray describes the meeting, e.g. "Zoom meeting".
+    pub label: String,
+}
+
+impl MeetingSignal {
+    /// A signal for the configured app or title `name` (falling back to `matched` when the
+    /// entry has no name).
+    pub fn new(detector: &'static str, matched: &str, name: &str) -> Self {
+        let name = if name.trim().is_empty() {
+            matched
+        } else {
+            name.trim()
+        };
+        Self {
+            detector,
+            matched: matched.to_string(),
+            label: format!("{name} meeting"),
+        }
+    }
+}
+
+/// One way of telling that the user is in a meeting.
+pub trait MeetingDetector: Send + Sync {
+    /// Short name for logs, e.g. `"app"`.
+    fn name(&self) -> &'static str;
+
+    /// Reports the meeting this detector sees, if any. Blocking: detectors may query system
+    /// APIs or spawn helper processes, so the timer loop calls this on a blocking thread.
+    fn detect(&self) -> Option<MeetingSignal>;
+}
+
+/// The meeting detectors in the order they are asked.
+#[derive(Default)]
+pub struct Registry {
+    detectors: Vec<Box<dyn MeetingDetector>>,
+}
+
+impl Registry {
+    /// The platform's detectors, configured with the `meeting_apps` and `meeting_titles` in
+    /// `config`.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut registry = Self::default();
+        platform::register(&mut registry, config);
+        registry
+    }
+
+    /// Adds a detector, asked after the ones already registered.
+    pub fn register(&mut self, detector: impl MeetingDetector + 'static) {
+        self.detectors.push(Box::new(detector));
+    }
+
+    /// The first meeting any detector reports.
+    pub fn detect(&self) -> Option<MeetingSignal> {
+        self.detectors.iter().find_map(|d| {
+            let signal = d.detect();
+            if let Some(s) = &signal {
+                log::debug!("Meeting detector {:?} matched {:?}", d.name(), s.matched);
+            }
+            signal
+        })
+    }
+}
+
+/// Last layer on every platform: a camera or microphone is in use (see
+/// [`crate::av_devices`]), whichever app uses it.
+struct AvDevices {
+    /// Names the process using the device, where the platform says which one it is.
+    apps: Vec<MeetingApp>,
+}
+
+impl MeetingDetector for AvDevices {
+    fn name(&self) -> &'static str {
+        "camera/microphone"
+    }
+
+    fn detect(&self) -> Option<MeetingSignal> {
+        let devices = crate::av_devices::devices_in_use();
+        // Prefer a device whose user is known, then cameras.
+        let device = devices
+            .iter()
+            .min_by_key(|d| (d.process.is_none(), d.kind != DeviceKind::Camera))?;
+        Some(match &device.process {
+            Some(process) => {
+                let name = self
+                    .apps
+                    .iter()
+                    .find(|a| a.id.trim().eq_ignore_ascii_case(process))
+                    .map_or(process.as_str(), |a| a.name.as_str());
+                MeetingSignal::new(self.name(), &device.device, name)
+            }
+            None => MeetingSignal {
+                detector: self.name(),
+                matched: device.device.clone(),
+                label: format!("{} in use", device.kind),
+            },
+        })
+    }
+}
+
 #[cfg(target_os = "macos")]
-mod macos {
-
-    /// Bundle IDs for known native conferencing apps.
-    const CONFERENCING_BUNDLE_IDS: &[&str] = &[
-        "us.zoom.xos",
-        "com.microsoft.teams2",
-        "Cisco-Systems.Spark",
-        "com.apple.FaceTime",
-        "com.hnc.Discord",
-    ];
+mod platform {
+    use super::{AvDevices, MeetingDetector, MeetingSignal, Registry};
+    use crate::config::{AppConfig, MeetingApp, MeetingTitle};
+    use regex::Regex;
 
-    /// Window title fragments that indicate a browser-based call.
-    const BROWSER_CALL_PATTERNS: &[&str] = &[
-        "Meet \u{2013}",
-        "Meet - ",
-        "Zoom Meeting",
-        "Microsoft Teams",
-        "On a call",
-        "Google Meet",
+    /// Bundle IDs of the browsers whose front window titles are checked.
+    const BROWSER_BUNDLE_IDS: &[&str] = &[
+        "com.google.Chrome",
+        "org.mozilla.firefox",
+        "com.apple.Safari",
+        "com.microsoft.edgemac",
     ];
 
-    /// Layer 1: Is a known native conferencing app running and not hidden?
-    ///
-    /// Checks the system's running applications for bundle identifiers that match the internal
-    /// list of known conferencing apps.
-    ///
-    /// # Returns
-    ///
-    /// `true` if a known native conferencing application is running, `false` otherwise.
-    ///
-    /// # Examples
-    ///
-    /// ```no_run
-    /// let active = is_native_conferencing_app_running();
-    /// println!("Native conferencing app running: {}", active);
-    /// ```
-    pub fn is_native_conferencing_app_running() -> bool {
-        use objc2_app_kit::NSWorkspace;
-
-        let workspace = NSWorkspace::sharedWorkspace();
-        let apps = workspace.runningApplications();
-        for app in apps.iter() {
-            if let Some(bundle_id) = app.bundleIdentifier() {
-                let s = bundle_id.to_string();
-                if CONFERENCING_BUNDLE_IDS.contains(&s.as_str()) && !app.isHidden() {
-                    return true;
+    pub fn register(registry: &mut Registry, config: &AppConfig) {
+        registry.register(NativeApps {
+            apps: config.meeting_apps.clone(),
+        });
+        registry.register(BrowserTitles::new(&config.meeting_titles));
+        registry.register(AvDevices {
+            apps: config.meeting_apps.clone(),
+        });
+    }
+
+    /// Layer 1: a configured conferencing app is running and not hidden.
+    struct NativeApps {
+        apps: Vec<MeetingApp>,
+    }
+
+    impl MeetingDetector for NativeApps {
+        fn name(&self) -> &'static str {
+            "app"
+        }
+
+        /// Checks the system's running applications for bundle identifiers listed in
+        /// `meeting_apps`.
+        fn detect(&self) -> Option<MeetingSignal> {
+            use objc2_app_kit::NSWorkspace;
+
+            let workspace = NSWorkspace::sharedWorkspace();
+            let running = workspace.runningApplications();
+            for app in running.iter() {
+                let Some(bundle_id) = app.bundleIdentifier() else {
+                    continue;
+                };
+                let bundle_id = bundle_id.to_string();
+                if app.isHidden() {
+                    continue;
+                }
+                if let Some(m) = self.apps.iter().find(|m| m.id == bundle_id) {
+                    return Some(MeetingSignal::new(self.name(), &m.id, &m.name));
                 }
             }
+            None
         }
-        false
-    }
-
-    /// Detects whether any frontmost browser window appears to be in a call based on its title.
-    ///
-    /// This checks common browser processes' front-window titles via an AppleScript and scans
-    /// them for known call-related fragments. The check is best-effort: it returns `false` if
-    /// the helper command is unavailable, if execution fails, or if permissions prevent reading
-    /// window titles for the inspected windows.
-    ///
-    /// # Returns
-    ///
-    /// `true` if any front browser window title contains a known call-related pattern, `false` otherwise.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// let in_call = is_browser_call_active();
-    /// // `in_call` will be `true` when a matching call title is detected, otherwise `false`.
-    /// ```
-    pub fn is_browser_call_active() -> bool {
-        // AppleScript to get frontmost browser window title.
-        // This approach requires Accessibility permission for non-frontmost windows
-        // but works for the active window without it.
-        let script = r#"
-            tell application "System Events"
-                set windowTitles to {}
-                set browserBundles to {"com.google.Chrome", "org.mozilla.firefox", "com.apple.Safari", "com.microsoft.edgemac"}
-                repeat with proc in (processes whose background only is false)
-                    if bundle identifier of proc is in browserBundles then
-                        try
-                            set end of windowTitles to name of front window of proc
-                        end try
-                    end if
-                end repeat
-                return windowTitles as text
-            end tell
-        "#;
-
-        // Spawn with a 5-second timeout to avoid blocking the detection loop
-        // when Accessibility permission is denied or the script hangs.
-        let mut child = match std::process::Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::null())
-            .spawn()
-        {
-            Ok(c) => c,
-            Err(_) => return false,
-        };
+    }
 
-        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
-        loop {
-            match child.try_wait() {
-                Ok(Some(_)) => {
-                    // Child exited — collect output.
-                    use std::io::Read;
-                    let mut buf = Vec::new();
-                    if let Some(mut stdout) = child.stdout.take() {
-                        let _ = stdout.read_to_end(&mut buf);
-                    }
-                    let text = String::from_utf8_lossy(&buf);
-                    return BROWSER_CALL_PATTERNS.iter().any(|p| text.contains(p));
+    /// Layer 2: a browser's front window title matches a `meeting_titles` pattern.
+    struct BrowserTitles {
+        patterns: Vec<(Regex, MeetingTitle)>,
+    }
+
+    impl BrowserTitles {
+        fn new(titles: &[MeetingTitle]) -> Self {
+            Self {
+                patterns: titles
+                    .iter()
+                    .filter_map(|t| Some((Regex::new(&t.pattern).ok()?, t.clone())))
+                    .collect(),
+            }
+        }
+
+        /// Front-window titles of the running browsers, one per line.
+        ///
+        /// The check is best-effort: `None` if osascript is unavailable, fails, takes longer
+        /// than five seconds, or permissions prevent reading window titles.
+        fn front_window_titles() -> Option<String> {
+            // This approach requires Accessibility permission for non-frontmost windows
+            // but works for the active window without it.
+            let browsers = BROWSER_BUNDLE_IDS
+                .iter()
+                .map(|id| format!("{id:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let script = format!(
+                r#"
+                tell application "System Events"
+                    set windowTitles to {{}}
+                    set browserBundles to {{{browsers}}}
+                    repeat with proc in (processes whose background only is false)
+                        if bundle identifier of proc is in browserBundles then
+                            try
+                                set end of windowTitles to name of front window of proc
+                            end try
+                        end if
+                    end repeat
+                    set AppleScript's text item delimiters to linefeed
+                    return windowTitles as text
+                end tell
+            "#
+            );
+
+            crate::subprocess::output("osascript", &["-e", &script])
+        }
+    }
+
+    impl MeetingDetector for BrowserTitles {
+        fn name(&self) -> &'static str {
+            "browser title"
+        }
+
+        fn detect(&self) -> Option<MeetingSignal> {
+            if self.patterns.is_empty() {
+                return None;
+            }
+            let titles = Self::front_window_titles()?;
+            titles.lines().find_map(|title| {
+                let (_, t) = self.patterns.iter().find(|(re, _)| re.is_match(title))?;
+                Some(MeetingSignal::new(self.name(), &t.pattern, &t.name))
+            })
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{AvDevices, MeetingDetector, MeetingSignal, Registry};
+    use crate::config::{AppConfig, MeetingApp};
+    use std::path::{Path, PathBuf};
+
+    const PROC_ROOT: &str = "/proc";
+
+    /// Capture streams from these programs are level meters, not calls.
+    const IGNORED_CAPTURE_BINARIES: &[&str] = &["pavucontrol", "pwvucontrol"];
+
+    pub fn register(registry: &mut Registry, config: &AppConfig) {
+        registry.register(Processes::new(PROC_ROOT, &config.meeting_apps));
+        registry.register(MicrophoneCapture {
+            apps: config.meeting_apps.clone(),
+        });
+        registry.register(AvDevices {
+            apps: config.meeting_apps.clone(),
+        });
+    }
+
+    /// A process found under the proc root.
+    struct Process {
+        /// `comm` (truncated by the kernel to 15 bytes) and the file name of `argv[0]`.
+        names: Vec<String>,
+    }
+
+    /// Every process under `root` whose name can be read. Processes that exit mid-scan are
+    /// skipped.
+    fn processes(root: &Path) -> Vec<Process> {
+        let Ok(entries) = std::fs::read_dir(root) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .bytes()
+                    .all(|b| b.is_ascii_digit())
+            })
+            .filter_map(|e| {
+                let dir = e.path();
+                let mut names = Vec::new();
+                if let Ok(comm) = std::fs::read_to_string(dir.join("comm")) {
+                    names.push(comm.trim().to_string());
                 }
-                Ok(None) => {
-                    if std::time::Instant::now() >= deadline {
-                        log::warn!("osascript timed out — killing child process");
-                        let _ = child.kill();
-                        let _ = child.wait(); // reap zombie
-                        return false;
+                if let Ok(cmdline) = std::fs::read(dir.join("cmdline")) {
+                    let argv0 = cmdline.split(|&b| b == 0).next().unwrap_or_default();
+                    let argv0 = String::from_utf8_lossy(argv0);
+                    if let Some(name) = Path::new(argv0.as_ref()).file_name() {
+                        names.push(name.to_string_lossy().into_owned());
                     }
-                    std::thread::sleep(std::time::Duration::from_millis(100));
                 }
-                Err(_) => return false,
+                names.retain(|n| !n.is_empty());
+                (!names.is_empty()).then_some(Process { names })
+            })
+            .collect()
+    }
+
+    /// The configured app any of `names` refers to (case-insensitive).
+    fn app_named<'a>(apps: &'a [MeetingApp], names: &[String]) -> Option<&'a MeetingApp> {
+        apps.iter()
+            .find(|a| names.iter().any(|n| n.eq_ignore_ascii_case(a.id.trim())))
+    }
+
+    /// Layer 1: a configured conferencing client is running.
+    pub struct Processes {
+        root: PathBuf,
+        apps: Vec<MeetingApp>,
+    }
+
+    impl Processes {
+        /// Scans the proc tree at `root` (normally `/proc`) for `apps`.
+        pub fn new(root: impl Into<PathBuf>, apps: &[MeetingApp]) -> Self {
+            Self {
+                root: root.into(),
+                apps: apps.to_vec(),
             }
         }
     }
 
-    /// Reports whether a camera or microphone appears to be in use (best-effort stub).
-    ///
-    /// This is a placeholder implementation for v1.0 that always returns `false`.
-    /// A complete implementation would query system APIs (e.g., CMIO/IOKit) to detect
-    /// active AV devices; keeping this as a stub avoids adding heavy native dependencies
-    /// in the initial release.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// assert!(!is_av_device_in_use());
-    /// ```
-    pub fn is_av_device_in_use() -> bool {
-        false
-    }
-
-    /// Determines whether a meeting is currently active.
-    ///
-    /// Checks multiple detection layers (native conferencing apps, browser-based calls, and AV device usage)
-    /// and returns `true` if any layer indicates an active meeting.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// let active = is_meeting_active();
-    /// // `active` is `true` when a meeting is detected, `false` otherwise
-    /// let _ = active;
-    /// ```
-    ///
-    /// # Returns
-    ///
-    /// `true` if a meeting is detected by any detection layer, `false` otherwise.
-    pub fn is_meeting_active() -> bool {
-        if is_native_conferencing_app_running() {
-            return true;
+    impl MeetingDetector for Processes {
+        fn name(&self) -> &'static str {
+            "process"
         }
-        if is_browser_call_active() {
-            return true;
+
+        fn detect(&self) -> Option<MeetingSignal> {
+            processes(&self.root).iter().find_map(|p| {
+                let app = app_named(&self.apps, &p.names)?;
+                Some(MeetingSignal::new(self.name(), &app.id, &app.name))
+            })
         }
-        is_av_device_in_use()
     }
-}
 
-#[cfg(not(target_os = "macos"))]
-mod macos {
-    /// Reports whether a meeting is active on the host system; on non-macOS builds this stub always reports no meeting.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// // On non-macOS targets this will always be false.
-    /// assert_eq!(is_meeting_active(), false);
-    /// ```
-    ///
-    /// # Returns
-    ///
-    /// `true` if a meeting is detected, `false` otherwise. On non-macOS builds this always returns `false`.
-    pub fn is_meeting_active() -> bool {
-        false
+    /// Layer 2: an application is recording from a microphone.
+    pub struct MicrophoneCapture {
+        apps: Vec<MeetingApp>,
+    }
+
+    impl MeetingDetector for MicrophoneCapture {
+        fn name(&self) -> &'static str {
+            "microphone"
+        }
+
+        fn detect(&self) -> Option<MeetingSignal> {
+            let out = crate::subprocess::run(
+                std::process::Command::new("pactl")
+                    .args(["list", "source-outputs"])
+                    .env("LC_ALL", "C"),
+            )?;
+            let streams = parse_source_outputs(&out);
+            let stream = streams.first()?;
+            let name = app_named(&self.apps, std::slice::from_ref(&stream.binary))
+                .map_or(stream.application.as_str(), |a| a.name.as_str());
+            Some(MeetingSignal::new(self.name(), &stream.binary, name))
+        }
+    }
+
+    /// A running (uncorked) capture stream from `pactl list source-outputs`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CaptureStream {
+        /// `application.process.binary`, e.g. `"firefox"`.
+        pub binary: String,
+        /// `application.name`, e.g. `"Firefox"`; the binary when unset.
+        pub application: String,
+    }
+
+    /// Parses `pactl list source-outputs` (in the C locale) into its uncorked streams, leaving
+    /// out level meters.
+    pub fn parse_source_outputs(output: &str) -> Vec<CaptureStream> {
+        let mut streams = Vec::new();
+        for block in output.split("Source Output #").skip(1) {
+            let mut corked = false;
+            let mut binary = String::new();
+            let mut application = String::new();
+            for line in block.lines().map(str::trim) {
+                if let Some(value) = line.strip_prefix("Corked:") {
+                    corked = value.trim() == "yes";
+                } else if let Some((key, value)) = line.split_once(" = ") {
+                    let value = value.trim().trim_matches('"').to_string();
+                    match key.trim() {
+                        "application.process.binary" => binary = value,
+                        "application.name" => application = value,
+                        _ => {}
+                    }
+                }
+            }
+            if corked || IGNORED_CAPTURE_BINARIES.contains(&binary.as_str()) {
+                continue;
+            }
+            if application.is_empty() {
+                application = binary.clone();
+            }
+            if !application.is_empty() {
+                streams.push(CaptureStream {
+                    binary,
+                    application,
+                });
+            }
+        }
+        streams
     }
 }
 
-pub use macos::is_meeting_active;
+/// Meeting detection is unsupported on this platform; the registry stays empty.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+mod platform {
+    use super::Registry;
+    use crate::config::AppConfig;
+
+    pub fn register(_registry: &mut Registry, _config: &AppConfig) {}
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::platform::{parse_source_outputs, CaptureStream, Processes};
+    use super::*;
+    use crate::fake_proc::FakeProc;
+
+    fn apps() -> Vec<MeetingApp> {
+        vec![
+            MeetingApp {
+                name: "Zoom".into(),
+                id: "zoom".into(),
+            },
+            MeetingApp {
+                name: "Signal".into(),
+                id: "signal-desktop".into(),
+            },
+        ]
+    }
+
+    #[test]
+    fn process_detected_by_comm() {
+        let proc = FakeProc::new("meeting-comm");
+        proc.process("1", Some("systemd"), &["/sbin/init"])
+            .process("4242", Some("zoom"), &[]);
+        let signal = Processes::new(proc.path(), &apps()).detect();
+        assert_eq!(signal, Some(MeetingSignal::new("process", "zoom", "Zoom")));
+    }
+
+    #[test]
+    fn process_detected_by_argv0_when_comm_is_truncated() {
+        let proc = FakeProc::new("meeting-argv0");
+        proc.process(
+            "77",
+            Some("signal-desktop-"),
+            &["/opt/Signal/signal-desktop", "--no-sandbox"],
+        );
+        let signal = Processes::new(proc.path(), &apps()).detect().unwrap();
+        assert_eq!(signal.label, "Signal meeting");
+    }
+
+    #[test]
+    fn process_not_detected_from_arguments_or_other_entries() {
+        let proc = FakeProc::new("meeting-none");
+        proc.process("10", Some("vim"), &["vim", "zoom"])
+            .process("11", None, &[])
+            .process("self", Some("zoom"), &["zoom"]);
+        assert_eq!(Processes::new(proc.path(), &apps()).detect(), None);
+        assert_eq!(
+            Processes::new(proc.path().join("missing"), &apps()).detect(),
+            None
+        );
+    }
+
+    const SOURCE_OUTPUTS: &str = "\
+Source Output #41
+\tDriver: PipeWire
+\tOwner Module: n/a
+\tClient: 80
+\tSource: 57
+\tCorked: no
+\tMute: no
+\tProperties:
+\t\tapplication.name = \"Firefox\"
+\t\tapplication.process.binary = \"firefox\"
+\t\tmedia.name = \"AudioCallbackDriver\"
+
+Source Output #42
+\tDriver: PipeWire
+\tCorked: yes
+\tProperties:
+\t\tapplication.name = \"Chromium\"
+\t\tapplication.process.binary = \"chromium\"
+
+Source Output #43
+\tDriver: PipeWire
+\tCorked: no
+\tProperties:
+\t\tapplication.name = \"Volume Control\"
+\t\tapplication.process.binary = \"pavucontrol\"
+
+Source Output #44
+\tDriver: PipeWire
+\tCorked: no
+\tProperties:
+\t\tapplication.process.binary = \"zoom\"
+";
+
+    #[test]
+    fn source_outputs_keep_uncorked_streams_and_skip_level_meters() {
+        assert_eq!(
+            parse_source_outputs(SOURCE_OUTPUTS),
+            vec![
+                CaptureStream {
+                    binary: "firefox".into(),
+                    application: "Firefox".into(),
+                },
+                CaptureStream {
+                    binary: "zoom".into(),
+                    application: "zoom".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn source_outputs_without_capture_streams() {
+        assert!(parse_source_outputs("").is_empty());
+        let idle = SOURCE_OUTPUTS
+            .split("\n\n")
+            .filter(|block| !block.contains("Corked: no") || block.contains("pavucontrol"))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        assert!(parse_source_outputs(&idle).is_empty());
+    }
+}
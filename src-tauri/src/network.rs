@@ -0,0 +1,130 @@
+//! Current network names, for activating profiles by Wi-Fi SSID or connection name.
+//!
+//! Best-effort and read-only: an empty list means "unknown or not connected".
+//!
+//! - macOS: the Wi-Fi SSID from `ipconfig getsummary`, falling back to
+//!   `networksetup -getairportnetwork` (recent releases may redact the SSID without Location
+//!   Services permission).
+//! - Linux: active NetworkManager connection names and the Wi-Fi SSID via `nmcli`, falling back
+//!   to `iwgetid`.
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::{parse_ipconfig_ssid, parse_networksetup_ssid};
+    use crate::subprocess::output;
+
+    /// Wi-Fi interface to query; `en0` on every Mac with built-in Wi-Fi.
+    const WIFI_INTERFACE: &str = "en0";
+
+    pub fn current_network_names() -> Vec<String> {
+        let ssid = output("ipconfig", &["getsummary", WIFI_INTERFACE])
+            .and_then(|s| parse_ipconfig_ssid(&s))
+            .or_else(|| {
+                output("networksetup", &["-getairportnetwork", WIFI_INTERFACE])
+                    .and_then(|s| parse_networksetup_ssid(&s))
+            });
+        ssid.into_iter().collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::parse_nmcli_lines;
+    use crate::subprocess::output;
+
+    pub fn current_network_names() -> Vec<String> {
+        let mut names: Vec<String> = output(
+            "nmcli",
+            &["-t", "-f", "NAME", "connection", "show", "--active"],
+        )
+        .map(|s| parse_nmcli_lines(&s))
+        .unwrap_or_default();
+        match output("iwgetid", &["-r"]) {
+            Some(ssid) if !ssid.trim().is_empty() => names.push(ssid.trim().to_string()),
+            _ => {}
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Extracts the `  SSID : name` line of `ipconfig getsummary`.
+#[cfg(any(target_os = "macos", test))]
+fn parse_ipconfig_ssid(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(" : ")?;
+        (key.trim() == "SSID")
+            .then(|| value.trim().to_string())
+            .filter(|v| !v.is_empty() && v != "<redacted>")
+    })
+}
+
+/// Parses `Current Wi-Fi Network: name`.
+#[cfg(any(target_os = "macos", test))]
+fn parse_networksetup_ssid(output: &str) -> Option<String> {
+    let (_, name) = output.trim().split_once(": ")?;
+    Some(name.trim().to_string()).filter(|n| !n.is_empty())
+}
+
+/// `nmcli -t` prints one value per line, escaping `:` and `\` with a backslash.
+#[cfg(any(target_os = "linux", test))]
+fn parse_nmcli_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| line.replace("\\:", ":").replace("\\\\", "\\"))
+        .filter(|name| !name.is_empty() && name != "lo")
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::current_network_names;
+#[cfg(target_os = "macos")]
+pub use macos::current_network_names;
+
+/// Network detection is unsupported on this platform.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn current_network_names() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ipconfig_ssid_reads_the_ssid_line() {
+        let summary = "<dictionary> {\n  BSSID : 12:34:56:78:9a:bc\n  InterfaceType : WiFi\n  \
+                       SSID : Office Wi-Fi\n  Security : WPA2_PSK\n}\n";
+        assert_eq!(
+            parse_ipconfig_ssid(summary).as_deref(),
+            Some("Office Wi-Fi")
+        );
+        assert_eq!(parse_ipconfig_ssid("  SSID : <redacted>\n"), None);
+        assert_eq!(parse_ipconfig_ssid("  SSID : \n"), None);
+        assert_eq!(parse_ipconfig_ssid("  BSSID : 12:34:56:78:9a:bc\n"), None);
+        assert_eq!(parse_ipconfig_ssid(""), None);
+    }
+
+    #[test]
+    fn parse_networksetup_ssid_reads_the_network_name() {
+        assert_eq!(
+            parse_networksetup_ssid("Current Wi-Fi Network: HomeWiFi\n").as_deref(),
+            Some("HomeWiFi")
+        );
+        assert_eq!(
+            parse_networksetup_ssid("You are not associated with an AirPort network.\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_nmcli_lines_unescapes_names_and_skips_loopback() {
+        let output = "Office WiFi\nlo\n\nVPN\\: work\nC:\\\\share\n";
+        assert_eq!(
+            parse_nmcli_lines(output),
+            ["Office WiFi", "VPN: work", "C:\\share"]
+        );
+        assert!(parse_nmcli_lines("").is_empty());
+    }
+}
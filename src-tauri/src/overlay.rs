@@ -1,12 +1,108 @@
+use crate::config::{Choice, OverlayTheme, ThemeChoice};
+use crate::themes::{self, ThemeText};
+use crate::timer::{BreakInfo, BreakKind};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
 
+/// Configuration injected into each overlay window as `window.__TWENTY20_OVERLAY_CONFIG__`.
 #[derive(Debug, Clone, Serialize)]
-#[allow(dead_code)]
+#[serde(rename_all = "camelCase")]
 pub struct OverlayConfig {
     pub break_duration: u32,
     pub is_primary: bool,
     pub is_strict_mode: bool,
+    pub break_kind: BreakKind,
+    pub headline: String,
+    pub message: String,
+    /// Snooze length offered by the overlay; 0 hides the snooze button.
+    pub snooze_seconds: u32,
+    /// A built-in theme name, or a theme pack the overlay fetches with `get_theme_pack`.
+    pub theme: String,
+}
+
+impl OverlayConfig {
+    /// Builds the primary-display config for `info`.
+    ///
+    /// A theme pack that has become invalid since the config was loaded falls back to the
+    /// default theme.
+    pub fn new(info: &BreakInfo, is_strict_mode: bool, theme: &ThemeChoice) -> Self {
+        let (theme, text) = match theme {
+            ThemeChoice::Builtin(builtin) => (builtin.name().to_string(), ThemeText::default()),
+            ThemeChoice::Pack(name) => match themes::text(&themes::themes_dir(), name) {
+                Ok(text) => (name.clone(), text),
+                Err(e) => {
+                    log::warn!("Theme pack {name:?} is unusable, using the default theme: {e}");
+                    (
+                        OverlayTheme::default().name().to_string(),
+                        ThemeText::default(),
+                    )
+                }
+            },
+        };
+        let (headline, message) = overlay_text(info.kind, info.duration, &info.reminders, &text);
+        Self {
+            break_duration: info.duration,
+            is_primary: true,
+            is_strict_mode,
+            break_kind: info.kind,
+            headline,
+            message,
+            snooze_seconds: info.snooze_seconds,
+            theme,
+        }
+    }
+}
+
+/// Headline and instruction text shown by the primary overlay for a break of `kind`.
+///
+/// `reminders` are the messages of reminder schedules folded into this break; they are
+/// appended to the instruction text, or become the headline for a reminder-only break.
+/// `text` replaces the wording of short and long breaks, filling in `{duration}`.
+pub fn overlay_text(
+    kind: BreakKind,
+    break_duration: u32,
+    reminders: &[String],
+    text: &ThemeText,
+) -> (String, String) {
+    let duration_label = if break_duration >= 60 {
+        let minutes = break_duration.div_ceil(60);
+        format!("{minutes} minute{}", if minutes == 1 { "" } else { "s" })
+    } else {
+        format!("{break_duration} seconds")
+    };
+    let custom = |t: &Option<String>| t.as_ref().map(|t| t.replace("{duration}", &duration_label));
+    let (headline, message) = match kind {
+        BreakKind::Short => (
+            custom(&text.short_headline).unwrap_or_else(|| "Look 20 feet away".to_string()),
+            custom(&text.short_message)
+                .unwrap_or_else(|| format!("Rest your eyes for {duration_label}")),
+        ),
+        BreakKind::Long => (
+            custom(&text.long_headline).unwrap_or_else(|| "Time for a longer break".to_string()),
+            custom(&text.long_message).unwrap_or_else(|| {
+                format!("Stand up, stretch and rest your eyes for {duration_label}")
+            }),
+        ),
+        BreakKind::Reminder => {
+            let headline = reminders
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "Time for a break".into());
+            let mut message = format!("Take {duration_label}");
+            if reminders.len() > 1 {
+                message.push_str(&format!(" · Also: {}", reminders[1..].join(", ")));
+            }
+            return (headline, message);
+        }
+    };
+    if reminders.is_empty() {
+        (headline, message)
+    } else {
+        (
+            headline,
+            format!("{message} · Also: {}", reminders.join(", ")),
+        )
+    }
 }
 
 /// Open full-screen overlay windows across displays.
@@ -16,17 +112,20 @@ pub struct OverlayConfig {
 ///
 /// # Arguments
 ///
-/// - `break_duration`: break length in seconds shown by the primary overlay.
+/// - `info`: the break to show (kind, duration, folded-in reminders, snooze offer).
 /// - `strict_mode`: when `true`, overlays run in strict mode (affects overlay behavior).
+/// - `theme`: the configured overlay theme or theme pack.
 ///
 /// # Examples
 ///
 /// ```no_run
 /// // `app` is an instance of `tauri::AppHandle` available in your runtime.
 /// let app: &tauri::AppHandle = unimplemented!();
-/// open_overlays(app, 300, true);
+/// let info: BreakInfo = unimplemented!();
+/// open_overlays(app, &info, true, &ThemeChoice::default());
 /// ```
-pub fn open_overlays(app: &AppHandle, break_duration: u32, strict_mode: bool) {
+pub fn open_overlays(app: &AppHandle, info: &BreakInfo, strict_mode: bool, theme: &ThemeChoice) {
+    let config = OverlayConfig::new(info, strict_mode, theme);
     let app_handle = app.clone();
     let _ = app.clone().run_on_main_thread(move || {
         #[cfg(target_os = "macos")]
@@ -38,7 +137,7 @@ pub fn open_overlays(app: &AppHandle, break_duration: u32, strict_mode: bool) {
             let screens = NSScreen::screens(mtm);
             let screen_count = screens.count();
             for i in 0..screen_count {
-                open_overlay_window(&app_handle, i, screen_count, break_duration, strict_mode);
+                open_overlay_window(&app_handle, i, screen_count, &config);
             }
             // Set presentation options once after all windows are built.
             set_presentation_options_for_overlay();
@@ -46,7 +145,7 @@ pub fn open_overlays(app: &AppHandle, break_duration: u32, strict_mode: bool) {
 
         #[cfg(not(target_os = "macos"))]
         {
-            open_overlay_window(&app_handle, 0, 1, break_duration, strict_mode);
+            open_overlay_window(&app_handle, 0, 1, &config);
         }
     });
 }
@@ -54,8 +153,8 @@ pub fn open_overlays(app: &AppHandle, break_duration: u32, strict_mode: bool) {
 /// Create and open a fullscreen overlay webview for a specific display index.
 ///
 /// The created window loads `overlay.html` and receives an initialization script that sets
-/// `window.__TWENTY20_OVERLAY_CONFIG__` with the fields `breakDuration`, `isPrimary`, and
-/// `isStrictMode`.
+/// `window.__TWENTY20_OVERLAY_CONFIG__` to the serialised [`OverlayConfig`], with `isPrimary`
+/// set for this window.
 ///
 /// `index` selects which display the overlay targets; an overlay with `index == 0` is treated
 /// as the primary overlay. On macOS, successful creation adjusts presentation options to hide
@@ -64,22 +163,15 @@ pub fn open_overlays(app: &AppHandle, break_duration: u32, strict_mode: bool) {
 /// # Examples
 ///
 /// ```no_run
-/// // assuming `app` is a `tauri::AppHandle`
-/// open_overlay_window(&app, 0, 1, 300, true);
+/// // assuming `app` is a `tauri::AppHandle` and `config` an `OverlayConfig`
+/// open_overlay_window(&app, 0, 1, &config);
 /// ```
 ///
 /// # Parameters
 ///
 /// - `index`: Zero-based display index identifying this overlay (0 is primary).
-/// - `break_duration`: Break duration in seconds injected into the overlay config.
-/// - `strict_mode`: Whether the overlay should run in strict mode.
-fn open_overlay_window(
-    app: &AppHandle,
-    index: usize,
-    _total: usize,
-    break_duration: u32,
-    strict_mode: bool,
-) {
+/// - `config`: Overlay configuration shared by all windows of this break.
+fn open_overlay_window(app: &AppHandle, index: usize, _total: usize, config: &OverlayConfig) {
     let label = format!("overlay_{index}");
     // Close existing if any.
     if let Some(win) = app.get_webview_window(&label) {
@@ -87,6 +179,11 @@ fn open_overlay_window(
     }
 
     let is_primary = index == 0;
+    let config = OverlayConfig {
+        is_primary,
+        ..config.clone()
+    };
+    let config_json = serde_json::to_string(&config).unwrap_or_else(|_| "{}".into());
 
     // Create the window hidden; we configure its level and frame before showing it.
     // Do NOT use .fullscreen(true) — on macOS that triggers the native fullscreen
@@ -99,13 +196,7 @@ fn open_overlay_window(
         .transparent(true)
         .visible(false)
         .initialization_script(format!(
-            r#"
-            window.__TWENTY20_OVERLAY_CONFIG__ = {{
-                breakDuration: {break_duration},
-                isPrimary: {is_primary},
-                isStrictMode: {strict_mode},
-            }};
-            "#
+            "window.__TWENTY20_OVERLAY_CONFIG__ = {config_json};"
         ))
         .build();
 
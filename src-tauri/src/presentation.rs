@@ -0,0 +1,161 @@
+//! Presentation and screen-sharing detection, for holding back an overdue break.
+//!
+//! Polled by the timer loop every 30 seconds. Best-effort and read-only: `None` means "not
+//! presenting, or unknown".
+//!
+//! - Slide shows: Keynote or PowerPoint playing one (macOS, asked through their scripting
+//!   dictionaries only while they run); LibreOffice Impress or a PDF presenter focused and full
+//!   screen (Linux X11, via `xprop`).
+//! - Screen sharing: the toolbars and notices conferencing apps and browsers show only while
+//!   sharing ([`SHARING_WINDOWS`]), found by window title through System Events on macOS
+//!   (requires Accessibility permission) and `xwininfo` on X11.
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use crate::subprocess::output;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use regex::Regex;
+
+/// Window titles that exist only while the screen is being shared, and what they mean.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+const SHARING_WINDOWS: &[(&str, &str)] = &[
+    (
+        r"(?i)^zoom share (toolbar|statusbar) window$",
+        "Zoom screen share",
+    ),
+    (r"^as_toolbar$", "Zoom screen share"),
+    (r"(?i)^sharing control bar", "Teams screen share"),
+    (
+        r"(?i) is sharing (your screen|a window|this tab)",
+        "browser screen share",
+    ),
+];
+
+/// What is being presented or shared, e.g. "Keynote slide show", if anything.
+///
+/// Blocking; the timer loop runs it on a blocking thread.
+pub fn detect() -> Option<String> {
+    platform::detect()
+}
+
+/// The first of `titles` that only exists while sharing the screen, as a label.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn sharing<'a>(titles: impl Iterator<Item = &'a str>) -> Option<String> {
+    let patterns: Vec<(Regex, &str)> = SHARING_WINDOWS
+        .iter()
+        .filter_map(|(pattern, label)| Some((Regex::new(pattern).ok()?, *label)))
+        .collect();
+    titles.map(str::trim).find_map(|title| {
+        patterns
+            .iter()
+            .find(|(re, _)| re.is_match(title))
+            .map(|(_, label)| label.to_string())
+    })
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{output, sharing};
+
+    /// Presentation apps: bundle ID, label, and a script answering `true` during a slide show.
+    const SLIDE_SHOWS: &[(&str, &str, &str)] = &[
+        (
+            "com.apple.iWork.Keynote",
+            "Keynote slide show",
+            r#"tell application id "com.apple.iWork.Keynote" to return playing"#,
+        ),
+        (
+            "com.microsoft.Powerpoint",
+            "PowerPoint slide show",
+            r#"tell application id "com.microsoft.Powerpoint" to return (count of slide show windows) > 0"#,
+        ),
+    ];
+
+    const WINDOW_TITLES: &str = r#"
+        tell application "System Events"
+            set windowTitles to {}
+            repeat with proc in (processes whose background only is false)
+                try
+                    set windowTitles to windowTitles & (name of every window of proc)
+                end try
+            end repeat
+            set AppleScript's text item delimiters to linefeed
+            return windowTitles as text
+        end tell
+    "#;
+
+    pub fn detect() -> Option<String> {
+        use objc2_app_kit::NSWorkspace;
+
+        // Scripting an app that is not running would launch it.
+        let running: Vec<String> = NSWorkspace::sharedWorkspace()
+            .runningApplications()
+            .iter()
+            .filter_map(|app| app.bundleIdentifier())
+            .map(|id| id.to_string())
+            .collect();
+        for (id, label, script) in SLIDE_SHOWS {
+            if running.iter().any(|r| r == id)
+                && output("osascript", &["-e", script]).is_some_and(|o| o.trim() == "true")
+            {
+                return Some(label.to_string());
+            }
+        }
+        sharing(output("osascript", &["-e", WINDOW_TITLES])?.lines())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{output, sharing};
+    use crate::app_rules::linux::{parse_active_window, parse_property, quoted_strings};
+
+    /// Window classes of slide-show and PDF presenters, which present full screen.
+    const PRESENTERS: &[(&str, &str)] = &[
+        ("libreoffice-impress", "Impress slide show"),
+        ("pdfpc", "pdfpc presentation"),
+        ("impressive", "Impressive presentation"),
+        ("okular", "Okular presentation"),
+        ("evince", "Evince presentation"),
+    ];
+
+    pub fn detect() -> Option<String> {
+        full_screen_presenter().or_else(|| {
+            let tree = output("xwininfo", &["-root", "-tree"])?;
+            sharing(tree.lines().filter_map(window_name))
+        })
+    }
+
+    /// The focused window, if it is a presenter in full screen.
+    fn full_screen_presenter() -> Option<String> {
+        let window = parse_active_window(&output("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?)?;
+        let props = output("xprop", &["-id", &window, "WM_CLASS", "_NET_WM_STATE"])?;
+        let full_screen = parse_property(&props, "_NET_WM_STATE")
+            .is_some_and(|state| state.contains("_NET_WM_STATE_FULLSCREEN"));
+        if !full_screen {
+            return None;
+        }
+        let classes = parse_property(&props, "WM_CLASS")
+            .map(quoted_strings)
+            .unwrap_or_default();
+        PRESENTERS.iter().find_map(|(class, label)| {
+            classes
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(class))
+                .then(|| label.to_string())
+        })
+    }
+
+    /// The name in an `xwininfo -tree` line: `0x1e00003 "as_toolbar": ("as_toolbar" "zoom")`.
+    fn window_name(line: &str) -> Option<&str> {
+        let (_, rest) = line.split_once(" \"")?;
+        Some(rest.rsplit_once("\": (").map_or(rest, |(name, _)| name))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+mod platform {
+    /// Presentation detection is unsupported on this platform.
+    pub fn detect() -> Option<String> {
+        None
+    }
+}
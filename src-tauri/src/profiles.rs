@@ -0,0 +1,298 @@
+//! Named config profiles.
+//!
+//! `AppState::base_config` holds the settings as written in `config.toml`; `AppState::config`
+//! holds the effective settings — the base with the profile in effect laid on top — which the
+//! rest of the app reads. A profile picked from the tray's "Profile" submenu is pinned in
+//! `active_profile`; with "Automatic" the first profile whose `networks` or `hours` match is
+//! used, and the base settings when none does.
+
+#[cfg(feature = "app")]
+use crate::commands::AppState;
+use crate::config::{AppConfig, ProfileConfig};
+use crate::schedule::WeeklySchedule;
+use crate::timer::BreakScheduler;
+use chrono::{DateTime, Local};
+#[cfg(feature = "app")]
+use std::sync::{Mutex, OnceLock};
+#[cfg(feature = "app")]
+use std::time::Duration;
+#[cfg(feature = "app")]
+use tauri::{AppHandle, Manager};
+#[cfg(feature = "app")]
+use tauri_plugin_autostart::ManagerExt;
+
+/// Lock a Mutex, recovering from a poisoned state gracefully.
+#[cfg(feature = "app")]
+macro_rules! lock {
+    ($m:expr) => {
+        $m.lock().unwrap_or_else(|e| e.into_inner())
+    };
+}
+
+/// How often automatic activation is re-evaluated.
+#[cfg(feature = "app")]
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Network names seen by the last poll.
+#[cfg(feature = "app")]
+fn networks() -> &'static Mutex<Vec<String>> {
+    static NETWORKS: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+    NETWORKS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// The profile in effect: the pinned one, else the first whose automatic rules match.
+pub fn resolve<'a>(
+    config: &'a AppConfig,
+    now: DateTime<Local>,
+    networks: &[String],
+) -> Option<&'a ProfileConfig> {
+    if let Some(name) = &config.active_profile {
+        return config.profile(name);
+    }
+    config.profiles.iter().find(|p| {
+        let on_network = p.networks.iter().any(|n| networks.contains(n));
+        let in_hours = p
+            .hours
+            .as_ref()
+            .is_some_and(|h| WeeklySchedule::from_days(h).is_active(now));
+        on_network || in_hours
+    })
+}
+
+/// The effective config and the name of the profile behind it.
+pub fn effective(base: &AppConfig, networks: &[String]) -> (AppConfig, Option<String>) {
+    let profile = resolve(base, Local::now(), networks);
+    (
+        base.with_profile(profile).validated(),
+        profile.map(|p| p.name.clone()),
+    )
+}
+
+/// Whether switching from `current` to `new` has to wait: strict mode is never turned off
+/// under a break that is on screen.
+fn must_wait(current: &AppConfig, new: &AppConfig, timer: &BreakScheduler) -> bool {
+    current.strict_mode && !new.strict_mode && timer.current_break().is_some()
+}
+
+/// Recomputes the effective config and applies it to [`AppState`] and the scheduler, the
+/// way the settings window's save action does.
+///
+/// Returns `false` without applying anything if the change would turn strict mode off while a
+/// break is on screen; the next poll tries again.
+#[cfg(feature = "app")]
+pub fn apply(app: &AppHandle) -> bool {
+    commit(app, None)
+}
+
+/// Like [`apply`], with `base` replacing the base config. The replacement is only kept if it
+/// is applied, so a refused one can be offered again.
+#[cfg(feature = "app")]
+pub fn apply_base(app: &AppHandle, base: AppConfig) -> bool {
+    commit(app, Some(base))
+}
+
+#[cfg(feature = "app")]
+fn commit(app: &AppHandle, replacement: Option<AppConfig>) -> bool {
+    let state = app.state::<AppState>();
+    let networks = lock!(networks()).clone();
+    let launch = {
+        let mut base = lock!(state.base_config);
+        let (new, name) = effective(replacement.as_ref().unwrap_or(&base), &networks);
+        let mut config = lock!(state.config);
+        let mut ts = lock!(state.timer);
+        if must_wait(&config, &new, &ts) {
+            return false;
+        }
+        if let Some(replacement) = replacement {
+            *base = replacement;
+        }
+        let mut active = lock!(state.active_profile);
+        if *active != name {
+            log::info!(
+                "Profile in effect: {}",
+                name.as_deref().unwrap_or("(base settings)")
+            );
+            *active = name;
+        }
+        let launch = (toml::to_string(&new).ok() != toml::to_string(&*config).ok()).then(|| {
+            ts.configure(&new);
+            new.launch_at_login
+        });
+        // Keep the new config even when its values are the same: a policy change may lock
+        // settings without changing them.
+        *config = new;
+        launch
+    };
+
+    // Update autolaunch via plugin
+    match launch {
+        Some(true) => {
+            let _ = app.autolaunch().enable();
+        }
+        Some(false) => {
+            let _ = app.autolaunch().disable();
+        }
+        None => {}
+    }
+    crate::tray::update_profile_items(app);
+    true
+}
+
+/// Pins `name` (or automatic selection for `None`) from the tray, saves it to `config.toml`
+/// and applies it.
+#[cfg(feature = "app")]
+pub fn select(app: &AppHandle, name: Option<String>) {
+    {
+        let state = app.state::<AppState>();
+        let mut base = lock!(state.base_config);
+        base.active_profile = name;
+        if let Err(e) = base.save() {
+            log::error!("Failed to save config: {e}");
+        }
+    }
+    if !apply(app) {
+        log::warn!("Profile turns strict mode off — applying after the break");
+    }
+}
+
+/// Spawns the task that re-evaluates automatic activation for the lifetime of the application.
+#[cfg(feature = "app")]
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let by_network = lock!(app.state::<AppState>().base_config)
+                .profiles
+                .iter()
+                .any(|p| !p.networks.is_empty());
+            let names = if by_network {
+                tokio::task::spawn_blocking(crate::network::current_network_names)
+                    .await
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            *lock!(networks()) = names;
+            apply(&app);
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WeekDays;
+    use crate::timer::{BreakKind, PersistedBreak, TimerState};
+    use chrono::TimeZone;
+
+    fn profile(name: &str, overrides: &str) -> ProfileConfig {
+        ProfileConfig {
+            name: name.into(),
+            overrides: overrides.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// "Office" on the office Wi-Fi, "Evening" after 18:00 on Mondays, "Focus" by hand only.
+    fn config() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.profiles = vec![
+            ProfileConfig {
+                networks: vec!["OfficeWiFi".into()],
+                ..profile("Office", "")
+            },
+            ProfileConfig {
+                hours: Some(WeekDays {
+                    monday: vec!["18:00-24:00".into()],
+                    ..Default::default()
+                }),
+                ..profile("Evening", "")
+            },
+            profile("Focus", "work_interval_minutes = 50"),
+        ];
+        config
+    }
+
+    fn monday(h: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, 2, h, 0, 0).unwrap()
+    }
+
+    fn resolved(config: &AppConfig, now: DateTime<Local>, networks: &[&str]) -> Option<String> {
+        let networks: Vec<String> = networks.iter().map(|n| n.to_string()).collect();
+        resolve(config, now, &networks).map(|p| p.name.clone())
+    }
+
+    #[test]
+    fn resolve_picks_the_first_profile_whose_rules_match() {
+        let config = config();
+        assert_eq!(resolved(&config, monday(10), &[]), None);
+        assert_eq!(resolved(&config, monday(10), &["HomeWiFi"]), None);
+        assert_eq!(
+            resolved(&config, monday(10), &["Wired", "OfficeWiFi"]).as_deref(),
+            Some("Office")
+        );
+        assert_eq!(
+            resolved(&config, monday(19), &[]).as_deref(),
+            Some("Evening")
+        );
+        assert_eq!(
+            resolved(&config, monday(19), &["OfficeWiFi"]).as_deref(),
+            Some("Office")
+        );
+    }
+
+    #[test]
+    fn resolve_prefers_the_pinned_profile() {
+        let mut config = config();
+        config.active_profile = Some("Focus".into());
+        assert_eq!(
+            resolved(&config, monday(19), &["OfficeWiFi"]).as_deref(),
+            Some("Focus")
+        );
+        // A pinned profile that no longer exists means the base settings.
+        config.active_profile = Some("Gone".into());
+        assert_eq!(resolved(&config, monday(19), &["OfficeWiFi"]), None);
+    }
+
+    #[test]
+    fn effective_applies_and_validates_the_profile() {
+        let mut config = config();
+        let (base, name) = effective(&config, &[]);
+        assert_eq!(name, None);
+        assert_eq!(base.work_interval_minutes, config.work_interval_minutes);
+
+        config.active_profile = Some("Focus".into());
+        let (focus, name) = effective(&config, &[]);
+        assert_eq!(name.as_deref(), Some("Focus"));
+        assert_eq!(focus.work_interval_minutes, 50);
+
+        config.profiles[2] = profile("Focus", "work_interval_minutes = 500");
+        assert_eq!(effective(&config, &[]).0.work_interval_minutes, 60);
+
+        // Overrides of the wrong type are ignored; the profile is still the one in effect.
+        config.profiles[2] = profile("Focus", r#"work_interval_minutes = "long""#);
+        let (ignored, name) = effective(&config, &[]);
+        assert_eq!(name.as_deref(), Some("Focus"));
+        assert_eq!(ignored.work_interval_minutes, config.work_interval_minutes);
+    }
+
+    #[test]
+    fn strict_mode_is_not_turned_off_during_a_break() {
+        let mut strict = AppConfig::default();
+        strict.strict_mode = true;
+        let relaxed = AppConfig::default();
+        let mut timer = BreakScheduler::new(TimerState::new(&strict), &strict);
+        assert!(!must_wait(&strict, &relaxed, &timer));
+
+        timer.resume_break(PersistedBreak {
+            kind: BreakKind::Short,
+            duration: 20,
+            seconds_left: 10,
+            reminders: Vec::new(),
+        });
+        assert!(must_wait(&strict, &relaxed, &timer));
+        assert!(!must_wait(&strict, &strict, &timer));
+        assert!(!must_wait(&relaxed, &strict, &timer));
+        assert!(!must_wait(&relaxed, &relaxed, &timer));
+    }
+}
@@ -0,0 +1,267 @@
+//! Weekly working-hours schedule.
+//!
+//! Breaks only run inside the configured per-day time ranges. Times are local wall-clock
+//! times (`chrono::Local` in the app), so the schedule follows the system timezone and DST
+//! changes.
+
+use crate::config::{WeekDays, WorkSchedule};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+/// A half-open range of minutes since local midnight, `start..end` (`end` may be 1440).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl TimeRange {
+    /// Parses `"HH:MM-HH:MM"`. The end may be `24:00`; ranges must not cross midnight.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (start, end) = s.split_once('-')?;
+        let start = parse_hhmm(start.trim())?;
+        let end = parse_hhmm(end.trim())?;
+        (start < end).then_some(Self { start, end })
+    }
+
+    fn contains(&self, minute: u32) -> bool {
+        self.start <= minute && minute < self.end
+    }
+}
+
+/// Parses `"HH:MM"` into minutes since midnight (`24:00` allowed).
+fn parse_hhmm(s: &str) -> Option<u32> {
+    let (h, m) = s.split_once(':')?;
+    let h: u32 = h.parse().ok()?;
+    let m: u32 = m.parse().ok()?;
+    if m >= 60 || h > 24 || (h == 24 && m != 0) {
+        return None;
+    }
+    Some(h * 60 + m)
+}
+
+/// Parsed form of [`WorkSchedule`]: working ranges per weekday, Monday first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeeklySchedule {
+    days: [Vec<TimeRange>; 7],
+}
+
+impl WeeklySchedule {
+    /// Parses the configured schedule, or returns `None` when it is disabled.
+    pub fn from_config(config: &WorkSchedule) -> Option<Self> {
+        config.enabled.then(|| Self::from_days(&config.days))
+    }
+
+    /// Parses per-day ranges. Unparsable ranges are skipped; `AppConfig::validated` has
+    /// already dropped them.
+    pub fn from_days(days: &WeekDays) -> Self {
+        Self {
+            days: days.all().map(|ranges| {
+                let mut parsed: Vec<_> =
+                    ranges.iter().filter_map(|r| TimeRange::parse(r)).collect();
+                parsed.sort_by_key(|r| r.start);
+                parsed
+            }),
+        }
+    }
+
+    fn ranges_on(&self, date: NaiveDate) -> &[TimeRange] {
+        &self.days[date.weekday().num_days_from_monday() as usize]
+    }
+
+    /// Whether `now` falls inside a working range.
+    pub fn is_active<Tz: TimeZone>(&self, now: DateTime<Tz>) -> bool {
+        let minute = now.hour() * 60 + now.minute();
+        self.ranges_on(now.date_naive())
+            .iter()
+            .any(|r| r.contains(minute))
+    }
+
+    /// Start of the next working range after `now`, looking up to a week ahead.
+    pub fn next_start<Tz: TimeZone>(&self, now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let today = now.date_naive();
+        let zone = now.timezone();
+        (0..=7).find_map(|offset| {
+            let date = today + Duration::days(offset);
+            self.ranges_on(date).iter().find_map(|r| {
+                let start = date.and_hms_opt(r.start / 60, r.start % 60, 0)?;
+                let start = first_valid(&zone, start)?;
+                (start > now).then_some(start)
+            })
+        })
+    }
+}
+
+/// `time` in `zone`, or the first minute after it that exists when it falls in a daylight-saving
+/// gap (a range starting at 02:30 starts at 03:00 on the day clocks go forward).
+fn first_valid<Tz: TimeZone>(zone: &Tz, time: NaiveDateTime) -> Option<DateTime<Tz>> {
+    (0..=180).find_map(|minutes| {
+        zone.from_local_datetime(&(time + Duration::minutes(minutes)))
+            .earliest()
+    })
+}
+
+/// Local midnight at the end of `now`'s day, used by the "work late tonight" override.
+pub fn end_of_day<Tz: TimeZone>(now: DateTime<Tz>) -> DateTime<Tz> {
+    let tomorrow = now.date_naive() + Duration::days(1);
+    tomorrow
+        .and_hms_opt(0, 0, 0)
+        .and_then(|t| first_valid(&now.timezone(), t))
+        .unwrap_or(now + Duration::hours(24))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    /// Monday to Friday, 09:00-12:00 and 13:00-17:00.
+    fn office_hours() -> WeeklySchedule {
+        let day = vec!["13:00-17:00".to_string(), "09:00-12:00".to_string()];
+        WeeklySchedule::from_days(&WeekDays {
+            monday: day.clone(),
+            tuesday: day.clone(),
+            wednesday: day.clone(),
+            thursday: day.clone(),
+            friday: day,
+            saturday: Vec::new(),
+            sunday: Vec::new(),
+        })
+    }
+
+    fn berlin(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<chrono_tz::Tz> {
+        Berlin
+            .with_ymd_and_hms(y, mo, d, h, mi, 0)
+            .earliest()
+            .unwrap()
+    }
+
+    #[test]
+    fn time_ranges_parse_hh_mm_pairs() {
+        let cases = [
+            ("09:00-17:30", Some((540, 1050))),
+            (" 9:00 - 12:00 ", Some((540, 720))),
+            ("00:00-24:00", Some((0, 1440))),
+            ("23:00-24:00", Some((1380, 1440))),
+            ("24:00-24:00", None),
+            ("22:00-02:00", None),
+            ("09:00-09:00", None),
+            ("09:00-24:30", None),
+            ("09:60-10:00", None),
+            ("25:00-26:00", None),
+            ("9-17", None),
+            ("09:00", None),
+            ("nine:00-17:00", None),
+            ("", None),
+        ];
+        for (input, want) in cases {
+            assert_eq!(
+                TimeRange::parse(input).map(|r| (r.start, r.end)),
+                want,
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn is_active_inside_half_open_ranges_only() {
+        let schedule = office_hours();
+        // 2026-03-02 is a Monday.
+        for (h, mi, want) in [
+            (8, 59, false),
+            (9, 0, true),
+            (11, 59, true),
+            (12, 0, false),
+            (12, 30, false),
+            (13, 0, true),
+            (16, 59, true),
+            (17, 0, false),
+        ] {
+            assert_eq!(
+                schedule.is_active(berlin(2026, 3, 2, h, mi)),
+                want,
+                "{h}:{mi}"
+            );
+        }
+        assert!(!schedule.is_active(berlin(2026, 3, 7, 10, 0)));
+
+        let all_day = WeeklySchedule::from_days(&WeekDays {
+            sunday: vec!["00:00-24:00".into()],
+            ..Default::default()
+        });
+        assert!(all_day.is_active(berlin(2026, 3, 8, 0, 0)));
+        assert!(all_day.is_active(berlin(2026, 3, 8, 23, 59)));
+        assert!(!all_day.is_active(berlin(2026, 3, 9, 0, 0)));
+    }
+
+    #[test]
+    fn next_start_finds_the_next_range_across_the_week() {
+        let schedule = office_hours();
+        let cases = [
+            // Before, between and after the ranges of a working day.
+            (berlin(2026, 3, 2, 7, 0), berlin(2026, 3, 2, 9, 0)),
+            (berlin(2026, 3, 2, 12, 15), berlin(2026, 3, 2, 13, 0)),
+            (berlin(2026, 3, 2, 9, 0), berlin(2026, 3, 2, 13, 0)),
+            (berlin(2026, 3, 2, 18, 0), berlin(2026, 3, 3, 9, 0)),
+            // Friday evening and the weekend wait for Monday.
+            (berlin(2026, 3, 6, 17, 0), berlin(2026, 3, 9, 9, 0)),
+            (berlin(2026, 3, 8, 23, 59), berlin(2026, 3, 9, 9, 0)),
+        ];
+        for (now, want) in cases {
+            assert_eq!(schedule.next_start(now), Some(want), "{now}");
+        }
+
+        // A single working day is found a full week ahead.
+        let monday = WeeklySchedule::from_days(&WeekDays {
+            monday: vec!["09:00-10:00".into()],
+            ..Default::default()
+        });
+        assert_eq!(
+            monday.next_start(berlin(2026, 3, 2, 9, 30)),
+            Some(berlin(2026, 3, 9, 9, 0))
+        );
+        assert_eq!(
+            WeeklySchedule::from_days(&WeekDays::default()).next_start(berlin(2026, 3, 2, 9, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn next_start_across_daylight_saving_changes() {
+        // Clocks go forward from 02:00 to 03:00 on Sunday 29 March 2026, and back from 03:00
+        // to 02:00 on Sunday 25 October 2026.
+        let schedule = WeeklySchedule::from_days(&WeekDays {
+            sunday: vec!["02:30-06:00".into()],
+            ..Default::default()
+        });
+
+        let start = schedule.next_start(berlin(2026, 3, 29, 0, 0)).unwrap();
+        assert_eq!(start, berlin(2026, 3, 29, 3, 0));
+        assert!(schedule.is_active(start));
+
+        // The first 02:30, still in summer time.
+        let start = schedule.next_start(berlin(2026, 10, 25, 0, 0)).unwrap();
+        assert_eq!(
+            start.with_timezone(&chrono::Utc),
+            chrono::Utc
+                .with_ymd_and_hms(2026, 10, 25, 0, 30, 0)
+                .unwrap()
+        );
+        assert_eq!(start.format("%H:%M %Z").to_string(), "02:30 CEST");
+    }
+
+    #[test]
+    fn end_of_day_is_the_next_local_midnight() {
+        assert_eq!(
+            end_of_day(berlin(2026, 3, 2, 15, 0)),
+            berlin(2026, 3, 3, 0, 0)
+        );
+        assert_eq!(
+            end_of_day(berlin(2026, 3, 2, 0, 0)),
+            berlin(2026, 3, 3, 0, 0)
+        );
+        // A 23-hour day.
+        let now = berlin(2026, 3, 29, 1, 0);
+        assert_eq!(end_of_day(now), berlin(2026, 3, 30, 0, 0));
+        assert_eq!(end_of_day(now) - now, Duration::hours(22));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::commands::AppState;
+use crate::config::{Choice, Diagnostic, OverlayTheme, PreWarning, SoundChoice, ThemeChoice};
 use objc2::runtime::{AnyClass, ClassBuilder, Sel};
 use objc2::{msg_send, rc::Retained, sel, ClassType};
 use objc2_app_kit::{
-    NSBackingStoreType, NSBezelStyle, NSBox, NSButton, NSColor, NSFont, NSGridView,
+    NSBackingStoreType, NSBezelStyle, NSBox, NSButton, NSColor, NSControl, NSFont, NSGridView,
     NSLayoutConstraint, NSPanel, NSPopUpButton, NSStackView, NSSwitch, NSTextField,
     NSUserInterfaceLayoutOrientation, NSView, NSWindowStyleMask,
 };
@@ -21,8 +22,12 @@ struct SettingsControls {
     launch_switch: Retained<NSSwitch>,
     meet_switch: Retained<NSSwitch>,
     theme_popup: Retained<NSPopUpButton>,
+    /// Built-in themes, then installed theme packs, as listed in `theme_popup`.
+    theme_choices: Vec<ThemeChoice>,
     sound_popup: Retained<NSPopUpButton>,
     warning_popup: Retained<NSPopUpButton>,
+    /// Lead times listed in `warning_popup`, in order.
+    warning_choices: Vec<PreWarning>,
 }
 
 struct SettingsControlsWrapper(SettingsControls);
@@ -76,26 +81,10 @@ extern "C" fn save_action(_this: &NSObject, _cmd: Sel, _sender: Option<&NSObject
         let launch = controls.launch_switch.state() == 1;
         let meet = controls.meet_switch.state() == 1;
 
-        let theme = controls
-            .theme_popup
-            .titleOfSelectedItem()
-            .map(|s| s.to_string())
-            .unwrap_or("dark".to_string());
-        let sound = controls
-            .sound_popup
-            .titleOfSelectedItem()
-            .map(|s| s.to_string())
-            .unwrap_or("off".to_string());
-        let warn_val = controls
-            .warning_popup
-            .titleOfSelectedItem()
-            .map(|s| s.to_string())
-            .unwrap_or("Off".to_string());
-        let pre_warn = if warn_val == "Off" {
-            0
-        } else {
-            warn_val.trim_end_matches('s').parse().unwrap_or(60)
-        };
+        let theme = selected(&controls.theme_popup, &controls.theme_choices).unwrap_or_default();
+        let sound = selected(&controls.sound_popup, SoundChoice::ALL).unwrap_or_default();
+        let pre_warn =
+            selected(&controls.warning_popup, &controls.warning_choices).unwrap_or_default();
 
         log::info!(
             "Saving: Work={}, Break={}, Strict={}, Launch={}, Meet={}, Theme={}, Sound={}, Warn={}",
@@ -104,14 +93,14 @@ extern "C" fn save_action(_this: &NSObject, _cmd: Sel, _sender: Option<&NSObject
             strict,
             launch,
             meet,
-            theme,
-            sound,
-            pre_warn
+            theme.name(),
+            sound.name(),
+            pre_warn.seconds()
         );
 
-        // Update config and Timer
+        // Update the base config; the profile in effect is laid on top by `profiles::apply`.
         {
-            let mut config = state.config.lock().unwrap();
+            let mut config = state.base_config.lock().unwrap();
             config.work_interval_minutes = work_mins;
             config.break_duration_seconds = break_secs;
             config.strict_mode = strict;
@@ -119,23 +108,21 @@ extern "C" fn save_action(_this: &NSObject, _cmd: Sel, _sender: Option<&NSObject
             config.meeting_detection = meet;
             config.overlay_theme = theme;
             config.sound = sound;
-            config.pre_warning_seconds = pre_warn;
+            config.pre_warning = pre_warn;
 
             // Validate and save
-            let validated = config.clone().validated();
-            *config = validated.clone();
+            *config = config.clone().validated();
 
             if let Err(e) = config.save() {
                 log::error!("Failed to save config: {}", e);
             } else {
                 log::info!("Config saved to disk");
+                // The file now holds the adjusted values.
+                state.config_diagnostics.lock().unwrap().clear();
             }
-
-            // Update Timer state
-            let mut ts = state.timer.lock().unwrap_or_else(|e| e.into_inner());
-            ts.is_strict_mode = validated.strict_mode;
-            ts.work_interval_seconds = validated.work_interval_minutes * 60;
         }
+        // Update effective config and Timer state
+        crate::profiles::apply(app);
 
         // Update autolaunch via plugin
         if launch {
@@ -163,16 +150,29 @@ pub fn show_settings(app: &AppHandle) {
 
     // Read config
     let state = app.state::<AppState>();
-    let config = state.config.lock().unwrap();
+    let config = state.base_config.lock().unwrap();
     let work_mins = config.work_interval_minutes;
     let break_secs = config.break_duration_seconds;
     let strict = config.strict_mode;
     let launch = config.launch_at_login;
     let meet = config.meeting_detection;
     let theme = config.overlay_theme.clone();
-    let sound = config.sound.clone();
-    let pre_warn = config.pre_warning_seconds;
+    let sound = config.sound;
+    let pre_warn = config.pre_warning;
+    // Settings pinned by the managed policy, the environment or `--set` flags cannot be
+    // edited here.
+    let constraints: Vec<Constraint> = SETTING_FIELDS
+        .iter()
+        .filter_map(|&field| match config.pinned_by(field) {
+            Some(source) => Some((field, format!("Set by {source}."), true)),
+            None => config.policy_minimum(field).map(|min| {
+                let text = format!("The managed policy requires at least {min}.");
+                (field, text, false)
+            }),
+        })
+        .collect();
     drop(config);
+    let diagnostics = state.config_diagnostics.lock().unwrap().clone();
 
     // Check if window already exists
     let mut guard = SETTINGS_WINDOW
@@ -253,12 +253,28 @@ pub fn show_settings(app: &AppHandle) {
         create_number_row("Work interval (minutes)", work_mins as i32, mtm);
     let views_work: [&NSView; 2] = [&lbl_work, &input_work];
     grid_timer.addRowWithViews(&NSArray::from_slice(&views_work));
+    add_setting_notes(
+        &grid_timer,
+        &input_work,
+        "work_interval_minutes",
+        &diagnostics,
+        &constraints,
+        mtm,
+    );
 
     // Break Duration
     let (lbl_break, input_break) =
         create_number_row("Break duration (seconds)", break_secs as i32, mtm);
     let views_break: [&NSView; 2] = [&lbl_break, &input_break];
     grid_timer.addRowWithViews(&NSArray::from_slice(&views_break));
+    add_setting_notes(
+        &grid_timer,
+        &input_break,
+        "break_duration_seconds",
+        &diagnostics,
+        &constraints,
+        mtm,
+    );
 
     main_stack.addArrangedSubview(&grid_timer);
 
@@ -277,14 +293,38 @@ pub fn show_settings(app: &AppHandle) {
     let desc_strict = create_small_text("Disable skip/pause. Press Esc × 3 to exit.", mtm);
     let views_strict: [&NSView; 2] = [&lbl_strict, &switch_strict];
     grid_behavior.addRowWithViews(&NSArray::from_slice(&views_strict));
+    add_setting_notes(
+        &grid_behavior,
+        &switch_strict,
+        "strict_mode",
+        &diagnostics,
+        &constraints,
+        mtm,
+    );
 
     let (lbl_login, switch_login) = create_switch_row("Launch at login", launch, mtm);
     let views_login: [&NSView; 2] = [&lbl_login, &switch_login];
     grid_behavior.addRowWithViews(&NSArray::from_slice(&views_login));
+    add_setting_notes(
+        &grid_behavior,
+        &switch_login,
+        "launch_at_login",
+        &diagnostics,
+        &constraints,
+        mtm,
+    );
 
     let (lbl_meet, switch_meet) = create_switch_row("Meeting detection", meet, mtm);
     let views_meet: [&NSView; 2] = [&lbl_meet, &switch_meet];
     grid_behavior.addRowWithViews(&NSArray::from_slice(&views_meet));
+    add_setting_notes(
+        &grid_behavior,
+        &switch_meet,
+        "meeting_detection",
+        &diagnostics,
+        &constraints,
+        mtm,
+    );
 
     main_stack.addArrangedSubview(&grid_behavior);
     main_stack.addArrangedSubview(&desc_strict); // Place description below strict row group
@@ -298,29 +338,66 @@ pub fn show_settings(app: &AppHandle) {
     grid_appearance.setColumnSpacing(12.0);
     grid_appearance.setXPlacement(objc2_app_kit::NSGridCellPlacement::Leading);
 
+    let theme_choices: Vec<ThemeChoice> = OverlayTheme::ALL
+        .iter()
+        .copied()
+        .map(ThemeChoice::Builtin)
+        .chain(
+            crate::themes::installed(&crate::themes::themes_dir())
+                .into_iter()
+                .map(ThemeChoice::Pack),
+        )
+        .collect();
+    let theme_names: Vec<&str> = theme_choices.iter().map(ThemeChoice::name).collect();
     let (lbl_theme, popup_theme) =
-        create_dropdown_row("Overlay Theme", &["dark", "light", "nature"], &theme, mtm);
+        create_dropdown_row("Overlay Theme", &theme_names, theme.name(), mtm);
     let views_theme: [&NSView; 2] = [&lbl_theme, &popup_theme];
     grid_appearance.addRowWithViews(&NSArray::from_slice(&views_theme));
+    add_setting_notes(
+        &grid_appearance,
+        &popup_theme,
+        "overlay_theme",
+        &diagnostics,
+        &constraints,
+        mtm,
+    );
 
-    let (lbl_sound, popup_sound) =
-        create_dropdown_row("Timer Sound", &["off", "chime", "whitenoise"], &sound, mtm);
+    let (lbl_sound, popup_sound) = create_dropdown_row(
+        "Timer Sound",
+        &choice_names(SoundChoice::ALL),
+        sound.name(),
+        mtm,
+    );
     let views_sound: [&NSView; 2] = [&lbl_sound, &popup_sound];
     grid_appearance.addRowWithViews(&NSArray::from_slice(&views_sound));
-
-    let warn_str = if pre_warn == 0 {
-        "Off".to_string()
-    } else {
-        format!("{}s", pre_warn)
-    };
-    let (lbl_warn, popup_warn) = create_dropdown_row(
-        "Pre-break Warning",
-        &["Off", "30s", "60s", "90s", "120s"],
-        &warn_str,
+    add_setting_notes(
+        &grid_appearance,
+        &popup_sound,
+        "sound",
+        &diagnostics,
+        &constraints,
         mtm,
     );
+
+    // Keep a lead time set in config.toml selectable even if it is not one of the presets.
+    let mut warning_choices = PreWarning::CHOICES.to_vec();
+    if !warning_choices.contains(&pre_warn) {
+        warning_choices.push(pre_warn);
+        warning_choices.sort_by_key(|w| w.seconds());
+    }
+    let warning_labels: Vec<String> = warning_choices.iter().map(|w| w.label()).collect();
+    let (lbl_warn, popup_warn) =
+        create_dropdown_row("Pre-break Warning", &warning_labels, &pre_warn.label(), mtm);
     let views_warn: [&NSView; 2] = [&lbl_warn, &popup_warn];
     grid_appearance.addRowWithViews(&NSArray::from_slice(&views_warn));
+    add_setting_notes(
+        &grid_appearance,
+        &popup_warn,
+        "pre_warning_seconds",
+        &diagnostics,
+        &constraints,
+        mtm,
+    );
 
     main_stack.addArrangedSubview(&grid_appearance);
 
@@ -350,8 +427,10 @@ pub fn show_settings(app: &AppHandle) {
         launch_switch: switch_login,
         meet_switch: switch_meet,
         theme_popup: popup_theme,
+        theme_choices,
         sound_popup: popup_sound,
         warning_popup: popup_warn,
+        warning_choices,
     };
     *SETTINGS_CONTROLS
         .get_or_init(|| Mutex::new(None))
@@ -449,9 +528,71 @@ fn create_small_text(text: &str, mtm: MainThreadMarker) -> Retained<NSTextField>
     label
 }
 
+/// Settings shown in the window, by their `config.toml` names.
+const SETTING_FIELDS: [&str; 8] = [
+    "work_interval_minutes",
+    "break_duration_seconds",
+    "strict_mode",
+    "launch_at_login",
+    "meeting_detection",
+    "overlay_theme",
+    "sound",
+    "pre_warning_seconds",
+];
+
+/// A setting's name, a note about the layer constraining it, and whether it is pinned
+/// (read-only here) rather than merely bounded.
+type Constraint = (&'static str, String, bool);
+
+/// Adds rows under the setting `field` explaining how its value in `config.toml` was adjusted
+/// and what constrains it, and disables `control` if the setting is pinned.
+fn add_setting_notes(
+    grid: &NSGridView,
+    control: &NSControl,
+    field: &str,
+    diagnostics: &[Diagnostic],
+    constraints: &[Constraint],
+    mtm: MainThreadMarker,
+) {
+    let mut notes = Vec::new();
+    if let Some(d) = diagnostics.iter().find(|d| d.field == field) {
+        let text = create_small_text(
+            &format!(
+                "config.toml has {}: {}. Using {}.",
+                d.value, d.reason, d.applied
+            ),
+            mtm,
+        );
+        text.setTextColor(Some(&NSColor::systemOrangeColor()));
+        notes.push(text);
+    }
+    if let Some((_, text, pinned)) = constraints.iter().find(|(f, ..)| *f == field) {
+        if *pinned {
+            control.setEnabled(false);
+        }
+        notes.push(create_small_text(text, mtm));
+    }
+    for text in notes {
+        let spacer = NSView::new(mtm);
+        let views: [&NSView; 2] = [&spacer, &text];
+        grid.addRowWithViews(&NSArray::from_slice(&views));
+    }
+}
+
+/// Names of `choices` as dropdown options.
+fn choice_names<T: Choice>(choices: &[T]) -> Vec<&'static str> {
+    choices.iter().map(|c| c.name()).collect()
+}
+
+/// The entry of `choices` selected in `popup`, whose items were built from them in order.
+fn selected<T: Clone>(popup: &NSPopUpButton, choices: &[T]) -> Option<T> {
+    let index = usize::try_from(popup.indexOfSelectedItem()).ok()?;
+    choices.get(index).cloned()
+}
+
 fn create_dropdown_row(
     label_text: &str,
-    options: &[&str],
+    options: &[impl AsRef<str>],
     selected: &str,
     mtm: MainThreadMarker,
 ) -> (Retained<NSTextField>, Retained<NSPopUpButton>) {
@@ -468,7 +609,7 @@ fn create_dropdown_row(
     let popup = popup.expect("failed to init popup");
 
     for option in options {
-        popup.addItemWithTitle(&NSString::from_str(option));
+        popup.addItemWithTitle(&NSString::from_str(option.as_ref()));
     }
     popup.selectItemWithTitle(&NSString::from_str(selected));
 
@@ -1,13 +1,16 @@
-//! macOS sleep/wake awareness via IOKit power management notifications.
+//! macOS sleep/wake and screen lock awareness.
 //!
-//! Registers a system power callback and bridges the Cocoa main-thread events
-//! into the async Tokio timer loop via a `tokio::sync::watch` channel.
-//! No new Cargo dependencies — only IOKit and CoreFoundation, which macOS
-//! links automatically for Tauri apps.
+//! Registers a system power callback (IOKit) and observers for the
+//! `com.apple.screenIsLocked` / `com.apple.screenIsUnlocked` distributed
+//! notifications, and bridges them into the async Tokio timer loop via a
+//! `tokio::sync::watch` channel that is `true` while the system sleeps or the
+//! screen is locked. No new Cargo dependencies — only IOKit and CoreFoundation,
+//! which macOS links automatically for Tauri apps.
 
 #[cfg(target_os = "macos")]
 mod macos {
-    use std::ffi::c_void;
+    use std::ffi::{c_char, c_void};
+    use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::OnceLock;
     use tokio::sync::watch;
 
@@ -45,7 +48,30 @@ mod macos {
         fn IOAllowPowerChange(root_port: IoConnectT, notif_id: isize);
     }
 
+    /// `kCFStringEncodingUTF8`.
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    /// `CFNotificationSuspensionBehaviorDeliverImmediately`.
+    const K_CF_NOTIFICATION_DELIVER_IMMEDIATELY: isize = 4;
+
+    type CFNotificationCallback =
+        unsafe extern "C" fn(*mut c_void, *mut c_void, *const c_void, *const c_void, *const c_void);
+
     extern "C" {
+        fn CFNotificationCenterGetDistributedCenter() -> *mut c_void;
+        fn CFNotificationCenterAddObserver(
+            center: *mut c_void,
+            observer: *const c_void,
+            callback: CFNotificationCallback,
+            name: *const c_void,
+            object: *const c_void,
+            suspension_behavior: isize,
+        );
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            c_str: *const c_char,
+            encoding: u32,
+        ) -> *const c_void;
         fn CFRunLoopAddSource(rl: *mut c_void, source: *mut c_void, mode: *const c_void);
         fn CFRunLoopGetCurrent() -> *mut c_void;
         fn CFRunLoopRun();
@@ -55,6 +81,18 @@ mod macos {
     /// Channel sender written once at startup, read from the C callback.
     static SLEEP_SENDER: OnceLock<watch::Sender<bool>> = OnceLock::new();
 
+    /// Whether the system is asleep / the screen is locked; the channel carries their union.
+    static SLEEPING: AtomicBool = AtomicBool::new(false);
+    static LOCKED: AtomicBool = AtomicBool::new(false);
+
+    /// Sends `true` while the system sleeps or the screen is locked, `false` otherwise.
+    fn signal() {
+        let away = SLEEPING.load(Ordering::SeqCst) || LOCKED.load(Ordering::SeqCst);
+        if let Some(tx) = SLEEP_SENDER.get() {
+            let _ = tx.send(away);
+        }
+    }
+
     /// IOKit root port stored after `IORegisterForSystemPower` succeeds;
     /// used by the callback to call `IOAllowPowerChange`.
     static ROOT_PORT: OnceLock<IoConnectT> = OnceLock::new();
@@ -72,9 +110,8 @@ mod macos {
         match message_type {
             K_IO_MESSAGE_SYSTEM_WILL_SLEEP => {
                 log::info!("System will sleep — signalling timer loop");
-                if let Some(tx) = SLEEP_SENDER.get() {
-                    let _ = tx.send(true);
-                }
+                SLEEPING.store(true, Ordering::SeqCst);
+                signal();
                 // Acknowledge sleep; without this the system hangs for ~30 s.
                 if let Some(&root_port) = ROOT_PORT.get() {
                     IOAllowPowerChange(root_port, message_argument as isize);
@@ -82,25 +119,72 @@ mod macos {
             }
             K_IO_MESSAGE_SYSTEM_HAS_POWERED_ON => {
                 log::info!("System woke — signalling timer loop");
-                if let Some(tx) = SLEEP_SENDER.get() {
-                    let _ = tx.send(false);
-                }
+                SLEEPING.store(false, Ordering::SeqCst);
+                signal();
             }
             _ => {}
         }
     }
 
-    /// Registers IOKit sleep/wake observers and bridges events to `sender`.
+    unsafe extern "C" fn screen_locked_callback(
+        _center: *mut c_void,
+        _observer: *mut c_void,
+        _name: *const c_void,
+        _object: *const c_void,
+        _user_info: *const c_void,
+    ) {
+        log::info!("Screen locked — signalling timer loop");
+        LOCKED.store(true, Ordering::SeqCst);
+        signal();
+    }
+
+    unsafe extern "C" fn screen_unlocked_callback(
+        _center: *mut c_void,
+        _observer: *mut c_void,
+        _name: *const c_void,
+        _object: *const c_void,
+        _user_info: *const c_void,
+    ) {
+        log::info!("Screen unlocked — signalling timer loop");
+        LOCKED.store(false, Ordering::SeqCst);
+        signal();
+    }
+
+    /// Observes the distributed notification `name` (a NUL-terminated C string).
+    unsafe fn observe_distributed(name: &[u8], callback: CFNotificationCallback) {
+        let name = CFStringCreateWithCString(
+            std::ptr::null(),
+            name.as_ptr().cast(),
+            K_CF_STRING_ENCODING_UTF8,
+        );
+        if name.is_null() {
+            return;
+        }
+        CFNotificationCenterAddObserver(
+            CFNotificationCenterGetDistributedCenter(),
+            std::ptr::null(),
+            callback,
+            name,
+            std::ptr::null(),
+            K_CF_NOTIFICATION_DELIVER_IMMEDIATELY,
+        );
+    }
+
+    /// Registers IOKit sleep/wake and screen lock observers and bridges events to `sender`.
     ///
     /// Spawns a background thread that runs a `CFRunLoop` to receive IOKit
     /// power-management notifications for the lifetime of the application.
-    /// Sends `true` when the system is about to sleep and `false` on wake.
+    /// Sends `true` when the system is about to sleep or the screen locks, and
+    /// `false` once it is both awake and unlocked.
     pub fn setup(sender: watch::Sender<bool>) {
         SLEEP_SENDER.set(sender).ok();
 
         std::thread::Builder::new()
             .name("sleep-watch".into())
             .spawn(|| unsafe {
+                observe_distributed(b"com.apple.screenIsLocked\0", screen_locked_callback);
+                observe_distributed(b"com.apple.screenIsUnlocked\0", screen_unlocked_callback);
+
                 let mut notify_port: IONotificationPortRef = std::ptr::null_mut();
                 let mut notifier: IoObjectT = 0;
 
@@ -0,0 +1,97 @@
+//! Running the command-line helpers the detectors read (`xprop`, `osascript`, `pactl`, `nmcli`,
+//! ...).
+//!
+//! Every helper runs with a timeout: detectors poll on blocking threads, and a helper that hangs
+//! (osascript waiting for Accessibility permission, `gdbus` on a wedged session bus) must not
+//! hold up its detector indefinitely.
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// How long a helper may run before it is killed.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs `program` and returns its stdout, or `None` if it is missing, fails or takes longer
+/// than five seconds.
+pub fn output(program: &str, args: &[&str]) -> Option<String> {
+    run(Command::new(program).args(args))
+}
+
+/// Like [`output`], for a command that needs more setup (environment variables, ...).
+pub fn run(command: &mut Command) -> Option<String> {
+    run_within(command, TIMEOUT)
+}
+
+fn run_within(command: &mut Command, timeout: Duration) -> Option<String> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let program = command.get_program().to_string_lossy().into_owned();
+    // Drain stdout while waiting: a child blocks once the pipe buffer (64 KiB on Linux) is full,
+    // and `xwininfo -root -tree` easily prints more than that.
+    let mut stdout = child.stdout.take()?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = tx.send(stdout.read_to_end(&mut buf).map(|_| buf));
+    });
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            Ok(None) => {
+                log::warn!("{program} timed out — killing child process");
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            Err(_) => return None,
+        }
+    };
+    // A grandchild may still hold the pipe open; wait for it only until the deadline.
+    let buf = rx
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .ok()?
+        .ok()?;
+    status
+        .success()
+        .then(|| String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_reads_more_than_a_pipe_buffer() {
+        let out = output("head", &["-c", "200000", "/dev/zero"]).expect("head runs");
+        assert_eq!(out.len(), 200_000);
+    }
+
+    #[test]
+    fn output_fails_for_a_missing_or_failing_program() {
+        assert_eq!(output("twenty20-no-such-program", &[]), None);
+        assert_eq!(output("false", &[]), None);
+    }
+
+    #[test]
+    fn run_passes_the_command_setup_through() {
+        let out = run(Command::new("sh")
+            .args(["-c", "echo $LC_ALL"])
+            .env("LC_ALL", "C"));
+        assert_eq!(out.as_deref(), Some("C\n"));
+    }
+
+    #[test]
+    fn a_program_that_runs_too_long_is_killed() {
+        let started = Instant::now();
+        let out = run_within(Command::new("sleep").arg("10"), Duration::from_millis(200));
+        assert_eq!(out, None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,415 @@
+//! Overlay theme packs, installed as directories under `<config dir>/twenty20/themes/`.
+//!
+//! A pack is chosen with `overlay_theme = "<directory name>"`. Its `theme.toml` manifest sets
+//! CSS variables for the overlay's colours, and can name a background image stored next to it
+//! and replace the instruction text:
+//!
+//! ```toml
+//! background_image = "waves.jpg"
+//!
+//! [variables]
+//! backdrop = "rgba(8, 47, 73, 0.8)"
+//! ring = "#38bdf8"
+//!
+//! [text]
+//! short_headline = "Watch the horizon"
+//! short_message = "Let your eyes drift for {duration}"
+//! ```
+//!
+//! Packs are read each time they are listed or shown, so edits apply from the next break.
+
+use crate::config::{Choice, OverlayTheme};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Manifest file name inside a pack directory.
+const MANIFEST: &str = "theme.toml";
+
+/// CSS variables the overlay reads, as `--<name>`: the dimmed backdrop, the breathing circle,
+/// the countdown ring and the text colour.
+const VARIABLES: &[&str] = &["backdrop", "glow", "ring", "text"];
+
+/// Upper bound on a CSS variable value and on each instruction text.
+const MAX_TEXT_CHARS: usize = 200;
+
+/// Upper bound on a background image; it is sent to the overlay inline.
+const MAX_IMAGE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Background image extensions and their MIME types.
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("webp", "image/webp"),
+    ("gif", "image/gif"),
+];
+
+/// `theme.toml`, as written.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Manifest {
+    /// Image file in the pack directory, drawn under the backdrop colour.
+    background_image: Option<String>,
+    /// CSS variables by name, without the leading `--`.
+    variables: BTreeMap<String, String>,
+    text: ThemeText,
+}
+
+/// Instruction text replacing the built-in wording; `{duration}` stands for the break length.
+/// Reminder breaks keep their own text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeText {
+    pub short_headline: Option<String>,
+    pub short_message: Option<String>,
+    pub long_headline: Option<String>,
+    pub long_message: Option<String>,
+}
+
+/// A validated theme pack, as sent to the overlay by `get_theme_pack`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemePack {
+    pub name: String,
+    /// CSS variables by name, without the leading `--`.
+    pub variables: BTreeMap<String, String>,
+    /// The background image as a `data:` URL, since the overlay cannot read the config
+    /// directory.
+    pub background_image: Option<String>,
+}
+
+/// Directory holding the installed packs, next to `config.toml`.
+pub fn themes_dir() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("twenty20");
+    path.push("themes");
+    path
+}
+
+/// Names of the packs installed in `themes` that pass validation, sorted. Invalid packs are
+/// logged and left out.
+pub fn installed(themes: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(themes) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().join(MANIFEST).is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| match read(themes, name) {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("Ignoring theme pack {name:?}: {e}");
+                false
+            }
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Loads the pack called `name` from `themes`, with its background image inlined.
+pub fn load(themes: &Path, name: &str) -> Result<ThemePack, String> {
+    let (dir, manifest) = read(themes, name)?;
+    let background_image = match &manifest.background_image {
+        Some(file) => {
+            let path = dir.join(file);
+            let bytes =
+                fs::read(&path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+            Some(format!(
+                "data:{};base64,{}",
+                image_type(file).unwrap_or("application/octet-stream"),
+                base64::engine::general_purpose::STANDARD.encode(bytes)
+            ))
+        }
+        None => None,
+    };
+    Ok(ThemePack {
+        name: name.to_string(),
+        variables: manifest.variables,
+        background_image,
+    })
+}
+
+/// The instruction text of the pack called `name` in `themes`.
+pub fn text(themes: &Path, name: &str) -> Result<ThemeText, String> {
+    read(themes, name).map(|(_, manifest)| manifest.text)
+}
+
+/// Reads and validates the manifest of the pack called `name` in `themes`, returning its
+/// directory.
+fn read(themes: &Path, name: &str) -> Result<(PathBuf, Manifest), String> {
+    if !valid_name(name) {
+        return Err("names may only use letters, digits, '-' and '_'".into());
+    }
+    if OverlayTheme::from_name(name).is_some() {
+        return Err("the name is taken by a built-in theme".into());
+    }
+    let dir = themes.join(name);
+    let path = dir.join(MANIFEST);
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let mut manifest: Manifest =
+        toml::from_str(&contents).map_err(|e| format!("invalid {}: {e}", path.display()))?;
+
+    for (variable, value) in &mut manifest.variables {
+        if !VARIABLES.contains(&variable.as_str()) {
+            return Err(format!(
+                "unknown variable {variable:?}; expected one of {}",
+                VARIABLES.join(", ")
+            ));
+        }
+        *value = value.trim().to_string();
+        if !valid_css_value(value) {
+            return Err(format!(
+                "variables.{variable} = {value:?} is not a plain CSS value"
+            ));
+        }
+    }
+    let text = &mut manifest.text;
+    for (field, value) in [
+        ("short_headline", &mut text.short_headline),
+        ("short_message", &mut text.short_message),
+        ("long_headline", &mut text.long_headline),
+        ("long_message", &mut text.long_message),
+    ] {
+        if let Some(t) = value.as_deref().map(str::trim) {
+            if t.chars().count() > MAX_TEXT_CHARS {
+                return Err(format!(
+                    "text.{field} is longer than {MAX_TEXT_CHARS} characters"
+                ));
+            }
+            *value = (!t.is_empty()).then(|| t.to_string());
+        }
+    }
+    if let Some(file) = &manifest.background_image {
+        check_image(&dir, file)?;
+    }
+    Ok((dir, manifest))
+}
+
+/// Pack names are directory names that cannot leave the themes directory.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// A colour, length or gradient: nothing that could end the declaration or load a resource.
+fn valid_css_value(value: &str) -> bool {
+    !value.is_empty()
+        && value.chars().count() <= MAX_TEXT_CHARS
+        && !value
+            .chars()
+            .any(|c| matches!(c, ';' | '{' | '}' | '<' | '>' | '\\' | '"' | '\'') || c.is_control())
+        && !value.to_ascii_lowercase().contains("url(")
+}
+
+/// The background image must be a supported image file directly inside the pack directory.
+fn check_image(dir: &Path, file: &str) -> Result<(), String> {
+    let relative = Path::new(file);
+    if relative.components().count() != 1
+        || !matches!(relative.components().next(), Some(Component::Normal(_)))
+    {
+        return Err(format!(
+            "background_image {file:?} must be a file in the theme directory"
+        ));
+    }
+    if image_type(file).is_none() {
+        let extensions: Vec<&str> = IMAGE_TYPES.iter().map(|(ext, _)| *ext).collect();
+        return Err(format!(
+            "background_image {file:?} must be one of {}",
+            extensions.join(", ")
+        ));
+    }
+    let path = dir.join(relative);
+    // A symlink could point anywhere; the file must resolve inside the pack.
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    if !dir
+        .canonicalize()
+        .is_ok_and(|dir| resolved.starts_with(dir))
+    {
+        return Err(format!(
+            "background_image {file:?} points outside the theme directory"
+        ));
+    }
+    let len = fs::metadata(&resolved)
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?
+        .len();
+    if len > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "background_image {file:?} is larger than {} MB",
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// MIME type of an image file, by extension.
+fn image_type(file: &str) -> Option<&'static str> {
+    let ext = Path::new(file).extension()?.to_str()?;
+    IMAGE_TYPES
+        .iter()
+        .find(|(e, _)| e.eq_ignore_ascii_case(ext))
+        .map(|(_, mime)| *mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty themes directory of its own for one test.
+    fn themes(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("twenty20-themes-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Installs a pack called `name` with the given manifest, returning its directory.
+    fn pack(themes: &Path, name: &str, manifest: &str) -> PathBuf {
+        let dir = themes.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST), manifest).unwrap();
+        dir
+    }
+
+    #[test]
+    fn valid_name_allows_only_plain_directory_names() {
+        for name in ["ocean", "Dark_mode-2", &"a".repeat(64)] {
+            assert!(valid_name(name), "{name:?}");
+        }
+        for name in [
+            "",
+            "..",
+            "../ocean",
+            "a/b",
+            "a\\b",
+            "a.b",
+            "sea side",
+            "océan",
+            &"a".repeat(65),
+        ] {
+            assert!(!valid_name(name), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn valid_css_value_rejects_anything_beyond_a_plain_value() {
+        for value in [
+            "#38bdf8",
+            "rgba(8, 47, 73, 0.8)",
+            "linear-gradient(135deg, #0f172a 0%, #1e3a8a 100%)",
+            "2px",
+        ] {
+            assert!(valid_css_value(value), "{value:?}");
+        }
+        for value in [
+            "",
+            "url(https://example.com/x.png)",
+            "URL(x.png)",
+            "image-set( url(x.png) 1x)",
+            "red; background: blue",
+            "red } body { color: blue",
+            "{",
+            "\"quoted\"",
+            "</style>",
+            "red\nblue",
+            "\\75rl(x)",
+            &"a".repeat(MAX_TEXT_CHARS + 1),
+        ] {
+            assert!(!valid_css_value(value), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn check_image_accepts_a_supported_file_in_the_pack() {
+        let dir = pack(&themes("image-ok"), "ocean", "");
+        fs::write(dir.join("waves.JPG"), b"jpeg").unwrap();
+        assert_eq!(check_image(&dir, "waves.JPG"), Ok(()));
+
+        for file in ["../waves.jpg", "/etc/waves.jpg", "sub/waves.jpg", "."] {
+            let err = check_image(&dir, file).unwrap_err();
+            assert!(
+                err.contains("must be a file in the theme directory"),
+                "{file}: {err}"
+            );
+        }
+        fs::write(dir.join("waves.bmp"), b"bmp").unwrap();
+        assert!(check_image(&dir, "waves.bmp")
+            .unwrap_err()
+            .contains("must be one of png"));
+        assert!(check_image(&dir, "missing.png")
+            .unwrap_err()
+            .starts_with("cannot read"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_image_rejects_a_symlink_out_of_the_pack() {
+        let themes = themes("image-symlink");
+        let dir = pack(&themes, "ocean", "");
+        let outside = themes.join("secret.png");
+        fs::write(&outside, b"png").unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("waves.png")).unwrap();
+        assert!(check_image(&dir, "waves.png")
+            .unwrap_err()
+            .contains("points outside the theme directory"));
+
+        // A link to another file in the pack is fine.
+        fs::write(dir.join("real.png"), b"png").unwrap();
+        std::os::unix::fs::symlink(dir.join("real.png"), dir.join("alias.png")).unwrap();
+        assert_eq!(check_image(&dir, "alias.png"), Ok(()));
+    }
+
+    #[test]
+    fn check_image_rejects_an_oversized_file() {
+        let dir = pack(&themes("image-large"), "ocean", "");
+        let file = fs::File::create(dir.join("huge.png")).unwrap();
+        file.set_len(MAX_IMAGE_BYTES + 1).unwrap();
+        assert!(check_image(&dir, "huge.png")
+            .unwrap_err()
+            .contains("larger than 8 MB"));
+        file.set_len(MAX_IMAGE_BYTES).unwrap();
+        assert_eq!(check_image(&dir, "huge.png"), Ok(()));
+    }
+
+    #[test]
+    fn installed_lists_only_valid_packs() {
+        let themes = themes("installed");
+        let ocean = pack(
+            &themes,
+            "ocean",
+            "background_image = \"waves.png\"\n[variables]\nring = \"#38bdf8\"\n[text]\nshort_headline = \"  Look away  \"\n",
+        );
+        fs::write(ocean.join("waves.png"), b"png").unwrap();
+        pack(&themes, "forest", "[variables]\nring = \"url(x)\"\n");
+        pack(&themes, "desert", "[variables]\nsand = \"#fde68a\"\n");
+        pack(&themes, "nature", "");
+        fs::create_dir_all(themes.join("no-manifest")).unwrap();
+        assert_eq!(installed(&themes), ["ocean"]);
+        assert!(installed(&themes.join("missing")).is_empty());
+
+        let loaded = load(&themes, "ocean").unwrap();
+        assert_eq!(loaded.variables["ring"], "#38bdf8");
+        assert_eq!(
+            loaded.background_image.as_deref(),
+            Some("data:image/png;base64,cG5n")
+        );
+        let text = text(&themes, "ocean").unwrap();
+        assert_eq!(text.short_headline.as_deref(), Some("Look away"));
+        assert!(load(&themes, "desert")
+            .unwrap_err()
+            .contains("unknown variable \"sand\""));
+        assert!(load(&themes, "nature")
+            .unwrap_err()
+            .contains("built-in theme"));
+    }
+}
@@ -1,14 +1,47 @@
-use crate::config::AppConfig;
+use crate::calendar::CalendarEvent;
+use crate::config::{AppAction, AppConfig, ReminderDelivery, SleepPolicy};
+use crate::meeting::MeetingSignal;
+use crate::schedule::{self, WeeklySchedule};
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PauseReason {
     Manual,
     Meeting,
+    /// No keyboard or pointer input for at least `idle_pause_seconds`.
+    Idle,
+    /// The user postponed the break; resumes when the snooze runs out.
+    Snoozed,
+    /// Outside the configured working hours.
+    OutsideSchedule,
+    /// The focused application has a `pause` rule.
+    App,
+    /// A busy event in one of the configured calendars.
+    Calendar,
+}
+
+/// The per-application rule that applies to the focused application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusedRule {
+    pub action: AppAction,
+    /// What the rule matched, for the tray.
+    pub app: String,
+}
+
+/// Kind of break: the regular eye break, the periodic long break, or an overlay reminder
+/// (stretch, hydration…) that fell due on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakKind {
+    Short,
+    Long,
+    Reminder,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,9 +53,19 @@ pub struct TimerState {
     pub is_strict_mode: bool,
     /// Total work interval seconds (for resets).
     pub work_interval_seconds: u32,
-    /// Countdown for a manual pause (seconds remaining before auto-resume).
-    /// Set by the pause_timer command; decremented by the timer loop.
+    /// Countdown for a timed pause — manual pause or snooze (seconds remaining before
+    /// auto-resume). Set by the tray/overlay; decremented by the timer loop.
     pub manual_pause_seconds_remaining: Option<u32>,
+    /// Snoozes used in the current work cycle.
+    pub snoozes_this_cycle: u32,
+    /// Short breaks completed since the last long break (the long-break cycle counter).
+    pub short_breaks_since_long: u32,
+    /// Tray skips and pauses used on `skip_budget_day`.
+    #[serde(default)]
+    pub skips_used_today: u32,
+    /// Local date `skips_used_today` counts against.
+    #[serde(default)]
+    pub skip_budget_day: Option<NaiveDate>,
 }
 
 impl TimerState {
@@ -30,7 +73,8 @@ impl TimerState {
     ///
     /// The returned state uses `config.work_interval_minutes` to set both `seconds_remaining`
     /// and `work_interval_seconds`, sets `is_paused` to `false`, `pause_reason` to `None`,
-    /// `is_strict_mode` from `config.strict_mode`, and leaves `manual_pause_seconds_remaining` as `None`.
+    /// `is_strict_mode` from `config.strict_mode`, leaves `manual_pause_seconds_remaining` as `None`
+    /// and starts the long-break cycle counter at zero.
     ///
     /// # Examples
     ///
@@ -52,85 +96,150 @@ impl TimerState {
             is_strict_mode: config.strict_mode,
             work_interval_seconds: config.work_interval_minutes * 60,
             manual_pause_seconds_remaining: None,
+            snoozes_this_cycle: 0,
+            short_breaks_since_long: 0,
+            skips_used_today: 0,
+            skip_budget_day: None,
         }
     }
+
+    /// Kind of the next break given the configured long-break cadence.
+    ///
+    /// With `long_break_every = N` (N > 0), every Nth break is long.
+    pub fn next_break_kind(&self, long_break_every: u32) -> BreakKind {
+        if long_break_every > 0 && self.short_breaks_since_long + 1 >= long_break_every {
+            BreakKind::Long
+        } else {
+            BreakKind::Short
+        }
+    }
+}
+
+/// Version of the timer state file format. Files without a `version` field predate
+/// versioning and only carry the work countdown.
+const PERSIST_FORMAT_VERSION: u32 = 2;
+
+/// A break in progress, as saved to disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedBreak {
+    pub kind: BreakKind,
+    pub duration: u32,
+    pub seconds_left: u32,
+    /// Messages of overlay reminders folded into the break.
+    #[serde(default)]
+    pub reminders: Vec<String>,
 }
 
 /// Persistent state saved to disk across restarts.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct PersistedTimer {
-    seconds_remaining: u32,
+    version: u32,
     /// Unix timestamp in seconds when state was saved.
     saved_at: u64,
+    state: TimerState,
+    /// The break that was on screen when the state was saved, if any.
+    #[serde(default)]
+    active_break: Option<PersistedBreak>,
 }
 
-impl PersistedTimer {
-    /// Builds the filesystem path for the timer state JSON file.
-    ///
-    /// Uses the platform-specific data-local directory when available; otherwise falls back to the current directory.
-    /// The resulting path ends with `twenty20/timer_state.json`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// let p = path();
-    /// assert!(p.ends_with(std::path::Path::new("twenty20/timer_state.json")));
-    /// ```
-    fn path() -> PathBuf {
-        let mut p = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
-        p.push("twenty20");
-        p.push("timer_state.json");
-        p
-    }
+/// Timer state file written before the format was versioned.
+#[derive(Deserialize)]
+struct LegacyPersistedTimer {
+    seconds_remaining: u32,
+    saved_at: u64,
+    #[serde(default)]
+    short_breaks_since_long: u32,
+}
 
-    /// Loads the persisted timer state from disk if it exists and is valid JSON.
+/// Builds the filesystem path for the timer state JSON file.
+///
+/// Uses the platform-specific data-local directory when available; otherwise falls back to the
+/// current directory. The resulting path ends with `twenty20/timer_state.json`.
+///
+/// # Examples
+///
+/// ```
+/// let p = state_path();
+/// assert!(p.ends_with(std::path::Path::new("twenty20/timer_state.json")));
+/// ```
+pub fn state_path() -> PathBuf {
+    let mut p = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    p.push("twenty20");
+    p.push("timer_state.json");
+    p
+}
+
+impl PersistedTimer {
+    /// Loads the persisted timer state from `path` if it exists and is valid JSON.
     ///
-    /// Attempts to read the timer state file at the configured path and deserialize it into a
-    /// `PersistedTimer`.
+    /// Unversioned files from older releases are upgraded: their countdown and long-break
+    /// counter are kept and every other field starts from `config`. Files written by a newer
+    /// release are ignored.
     ///
     /// # Returns
     ///
-    /// `Some(PersistedTimer)` if the file exists and contains valid JSON, `None` otherwise.
+    /// `Some(PersistedTimer)` if the file exists and could be read, `None` otherwise.
     ///
     /// # Examples
     ///
     /// ```
     /// // Attempt to restore persisted timer state; handle absence gracefully.
-    /// if let Some(persisted) = crate::timer::PersistedTimer::load() {
-    ///     println!("restored {} seconds", persisted.seconds_remaining);
+    /// if let Some(persisted) = PersistedTimer::load(&state_path(), &config) {
+    ///     println!("restored {} seconds", persisted.state.seconds_remaining);
     /// } else {
     ///     println!("no persisted timer state found");
     /// }
     /// ```
-    fn load() -> Option<Self> {
-        let path = Self::path();
-        let contents = fs::read_to_string(&path).ok()?;
-        serde_json::from_str(&contents).ok()
+    fn load(path: &Path, config: &AppConfig) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        let value: serde_json::Value = serde_json::from_str(&contents).ok()?;
+        match value.get("version").and_then(serde_json::Value::as_u64) {
+            None => {
+                let legacy: LegacyPersistedTimer = serde_json::from_value(value).ok()?;
+                Some(Self {
+                    version: PERSIST_FORMAT_VERSION,
+                    saved_at: legacy.saved_at,
+                    state: TimerState {
+                        seconds_remaining: legacy.seconds_remaining,
+                        short_breaks_since_long: legacy.short_breaks_since_long,
+                        ..TimerState::new(config)
+                    },
+                    active_break: None,
+                })
+            }
+            Some(v) if v == u64::from(PERSIST_FORMAT_VERSION) => serde_json::from_value(value).ok(),
+            Some(v) => {
+                log::warn!("Ignoring timer state format version {v} from a newer release");
+                None
+            }
+        }
     }
 
-    /// Persists the provided remaining seconds to the timer state file with the current Unix timestamp.
+    /// Persists the timer state and any break in progress to `path` with the current Unix
+    /// timestamp.
     ///
-    /// The function writes a JSON object containing `seconds_remaining` and the save time (`saved_at`)
-    /// to the module's timer state path, creating parent directories if necessary. I/O failures are
-    /// ignored.
+    /// The JSON is written to a temporary file next to the state file and renamed over it, so
+    /// a crash mid-write never leaves a truncated file behind. Parent directories are created
+    /// if necessary. I/O failures are logged and otherwise ignored.
     ///
     /// # Examples
     ///
     /// ```
-    /// // Persist 90 seconds remaining to the timer state file.
-    /// PersistedTimer::save(90);
+    /// // Persist a working-phase state with no break on screen.
+    /// PersistedTimer::save(&state_path(), &state, None);
     /// ```
-    fn save(seconds_remaining: u32) {
+    fn save(path: &Path, state: &TimerState, active_break: Option<PersistedBreak>) {
         use std::time::{SystemTime, UNIX_EPOCH};
         let saved_at = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map(|d| d.as_secs())
             .unwrap_or(0);
-        let state = PersistedTimer {
-            seconds_remaining,
+        let persisted = PersistedTimer {
+            version: PERSIST_FORMAT_VERSION,
             saved_at,
+            state: state.clone(),
+            active_break,
         };
-        let path = Self::path();
         if let Some(parent) = path.parent() {
             if let Err(e) = fs::create_dir_all(parent) {
                 log::warn!(
@@ -140,9 +249,10 @@ impl PersistedTimer {
                 return;
             }
         }
-        match serde_json::to_string(&state) {
+        match serde_json::to_string(&persisted) {
             Ok(json) => {
-                if let Err(e) = fs::write(&path, json) {
+                let tmp = path.with_extension("json.tmp");
+                if let Err(e) = fs::write(&tmp, json).and_then(|()| fs::rename(&tmp, path)) {
                     log::warn!("Failed to write timer state to {}: {e}", path.display());
                 }
             }
@@ -151,81 +261,2350 @@ impl PersistedTimer {
     }
 }
 
-pub type SharedTimerState = Arc<Mutex<TimerState>>;
+pub type SharedTimerState = Arc<Mutex<BreakScheduler>>;
+
+/// Timer state restored at launch.
+#[derive(Debug, Clone)]
+pub struct RestoredTimer {
+    pub state: TimerState,
+    /// A break to reopen (strict mode only); see [`BreakScheduler::resume_break`].
+    pub active_break: Option<PersistedBreak>,
+}
 
-/// Initialise timer state from persisted disk state (if available).
+/// Initialise timer state from the state persisted at `path` (if available).
 ///
-/// Subtracts the time elapsed since the state was saved so that the countdown
-/// continues correctly across restarts and sleep/wake cycles.
+/// Subtracts the time elapsed since the state was saved so that the countdown continues
+/// correctly across restarts and sleep/wake cycles:
 ///
-/// If a persisted state exists and its `seconds_remaining` is less than or equal to `config.work_interval_minutes * 60`, the persisted remaining seconds are used while other runtime fields are initialized from `config`. If no compatible persisted state is found, a new `TimerState` is returned via `TimerState::new`.
+/// - A manual pause or snooze keeps running down while the app is closed; once it has run out,
+///   the rest of the elapsed time counts against the work countdown.
+/// - A meeting pause is kept (the meeting poll lifts it); idle, app-rule, calendar and
+///   out-of-hours pauses are dropped and re-detected by their polls.
+/// - A break still in progress is handed back in [`RestoredTimer::active_break`] with its
+///   remaining seconds when strict mode is on, so quitting cannot dodge a strict break. A break
+///   that would have finished while the app was closed counts as completed; outside strict
+///   mode an unfinished one counts as skipped.
+///
+/// If the work interval changed, or the countdown would have expired while the app was away,
+/// a fresh cycle starts (keeping the long-break cycle counter).
 ///
 /// # Examples
 ///
 /// ```no_run
-/// let config = crate::AppConfig {
-///     work_interval_minutes: 25,
-///     strict_mode: false,
-///     // fill other fields as required by AppConfig...
-/// };
-/// let state = crate::timer::restore_or_create(&config);
+/// let config = crate::AppConfig::default();
+/// let restored = crate::timer::restore_or_create(&crate::timer::state_path(), &config);
+/// let scheduler = crate::timer::BreakScheduler::new(restored.state, &config);
 /// ```
-pub fn restore_or_create(config: &AppConfig) -> TimerState {
+pub fn restore_or_create(path: &Path, config: &AppConfig) -> RestoredTimer {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     let interval = config.work_interval_minutes * 60;
+    let restored = |state: TimerState| RestoredTimer {
+        state,
+        active_break: None,
+    };
 
-    if let Some(persisted) = PersistedTimer::load() {
-        // Skip restore if the work interval setting changed.
-        if persisted.seconds_remaining > interval {
-            return TimerState::new(config);
-        }
+    let Some(persisted) = PersistedTimer::load(path, config) else {
+        return restored(TimerState::new(config));
+    };
+    let mut state = persisted.state;
+    state.is_strict_mode = config.strict_mode;
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(persisted.saved_at);
+    // The long-break cycle and the daily skip budget survive restarts even when the
+    // countdown does not.
+    let mut fresh = TimerState {
+        short_breaks_since_long: state.short_breaks_since_long,
+        skips_used_today: state.skips_used_today,
+        skip_budget_day: state.skip_budget_day,
+        ..TimerState::new(config)
+    };
 
-        let elapsed = now.saturating_sub(persisted.saved_at);
-        let adjusted = persisted.seconds_remaining.saturating_sub(elapsed as u32);
+    // Skip restore if the work interval setting changed.
+    if state.work_interval_seconds != interval || state.seconds_remaining > interval {
+        return restored(fresh);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(persisted.saved_at);
+    let elapsed = u32::try_from(now.saturating_sub(persisted.saved_at)).unwrap_or(u32::MAX);
 
-        // If the timer would have expired while the app was away, start fresh.
-        if adjusted == 0 {
-            return TimerState::new(config);
+    if let Some(saved) = persisted.active_break {
+        if saved.kind != BreakKind::Reminder {
+            if elapsed < saved.seconds_left && config.strict_mode {
+                log::info!("Resuming strict break interrupted by a restart");
+                return RestoredTimer {
+                    state,
+                    active_break: Some(PersistedBreak {
+                        seconds_left: saved.seconds_left - elapsed,
+                        ..saved
+                    }),
+                };
+            }
+            if elapsed >= saved.seconds_left {
+                match saved.kind {
+                    BreakKind::Long => fresh.short_breaks_since_long = 0,
+                    _ => fresh.short_breaks_since_long += 1,
+                }
+            }
+            return restored(fresh);
         }
+        // A reminder-only break leaves the eye countdown where it was.
+    }
 
-        return TimerState {
-            seconds_remaining: adjusted,
-            is_paused: false,
-            pause_reason: None,
-            is_strict_mode: config.strict_mode,
-            work_interval_seconds: interval,
-            manual_pause_seconds_remaining: None,
-        };
+    let mut work_elapsed = elapsed;
+    match state.pause_reason {
+        Some(PauseReason::Manual | PauseReason::Snoozed) => {
+            let left = state.manual_pause_seconds_remaining.unwrap_or(0);
+            if elapsed < left {
+                state.manual_pause_seconds_remaining = Some(left - elapsed);
+                return restored(state);
+            }
+            work_elapsed = elapsed - left;
+        }
+        Some(PauseReason::Meeting) if config.meeting_detection => return restored(state),
+        Some(_) => work_elapsed = 0,
+        None => {}
     }
-    TimerState::new(config)
+    state.is_paused = false;
+    state.pause_reason = None;
+    state.manual_pause_seconds_remaining = None;
+
+    let adjusted = state.seconds_remaining.saturating_sub(work_elapsed);
+    // If the timer would have expired while the app was away, start fresh.
+    if adjusted == 0 {
+        return restored(fresh);
+    }
+    state.seconds_remaining = adjusted;
+    restored(state)
 }
 
-/// Persists the timer's current seconds remaining to durable storage.
-///
-/// Saves the state's `seconds_remaining` value so it can be restored on a later run.
+/// Persists the full timer state and any break in progress to `path`.
 ///
 /// # Examples
 ///
 /// ```
-/// use crate::timer::{TimerState, persist_state};
-///
-/// let state = TimerState {
-///     seconds_remaining: 120,
-///     is_paused: false,
-///     pause_reason: None,
-///     is_strict_mode: false,
-///     work_interval_seconds: 1500,
-///     manual_pause_seconds_remaining: None,
-/// };
+/// use crate::timer::persist_state;
 ///
-/// persist_state(&state);
+/// let ts = lock!(app_state.timer);
+/// persist_state(&state_path(), &ts.state, ts.active_break());
 /// ```
-pub fn persist_state(state: &TimerState) {
-    PersistedTimer::save(state.seconds_remaining);
+pub fn persist_state(path: &Path, state: &TimerState, active_break: Option<PersistedBreak>) {
+    PersistedTimer::save(path, state, active_break);
+}
+
+/// Source of time for the [`BreakScheduler`].
+///
+/// The scheduler never sleeps or reads the system clock itself; it asks its clock how much
+/// time has passed whenever it receives [`Input::Tick`], and for the local time of day when
+/// checking the working-hours schedule. Production code uses [`SystemClock`], tests can
+/// substitute a manually advanced clock.
+pub trait Clock: Send {
+    fn now(&self) -> Instant;
+    fn local_now(&self) -> DateTime<Local>;
+}
+
+/// [`Clock`] backed by [`Instant::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn local_now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// Events fed into the [`BreakScheduler`] by the timer loop, tray and commands.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// Periodic wake-up; the scheduler advances by however many whole seconds have elapsed.
+    Tick,
+    /// A meeting detector matched; re-sent every poll while the meeting lasts.
+    MeetingStarted(MeetingSignal),
+    MeetingEnded,
+    /// Latest reading of the user's idle time, in seconds since the last input.
+    Idle {
+        seconds: u32,
+    },
+    /// The system is about to sleep.
+    Sleep,
+    /// The system has woken from sleep.
+    Wake,
+    /// Skip the next break (tray, ignored in strict mode).
+    Skip,
+    /// Pause the work timer for the given number of seconds (tray, ignored in strict mode).
+    Pause {
+        seconds: u32,
+    },
+    /// Emergency exit from the overlay (Esc × 3); honoured even in strict mode.
+    ForceSkip,
+    /// "Work late tonight": ignore the working-hours schedule until local midnight.
+    WorkLate,
+    /// Postpone the upcoming or in-progress break (tray/overlay, ignored in strict mode and
+    /// once the per-cycle snooze limit is reached).
+    Snooze,
+    /// Latest evaluation of the per-application rules: the rule for the focused application,
+    /// or `None` when no rule applies.
+    AppRule(Option<FocusedRule>),
+    /// Latest presentation check: what is being presented or shared, if anything.
+    Presentation(Option<String>),
+    /// Latest calendar lookahead: the busy event in progress and the next one to start.
+    Calendar {
+        current: Option<CalendarEvent>,
+        next: Option<CalendarEvent>,
+    },
+}
+
+/// What the overlay shows for one break.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakInfo {
+    pub kind: BreakKind,
+    pub duration: u32,
+    /// Messages of overlay reminders folded into this break.
+    pub reminders: Vec<String>,
+    /// Length of the snooze the overlay may offer; 0 when snoozing is unavailable.
+    pub snooze_seconds: u32,
+}
+
+/// Snapshot of the work timer for the `timer:tick` event and the tray menu.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimerStatus {
+    pub seconds_remaining: u32,
+    pub is_paused: bool,
+    pub pause_reason: Option<PauseReason>,
+    /// Seconds left of a timed pause (manual pause or snooze).
+    pub pause_seconds_remaining: Option<u32>,
+    /// Kind of the upcoming break; `None` when long breaks are disabled.
+    pub next_break: Option<BreakKind>,
+    pub reminders: Vec<ReminderStatus>,
+    /// Length of the next snooze; 0 when snoozing is unavailable.
+    pub snooze_seconds: u32,
+    pub snoozes_left: u32,
+    /// Tray skips and pauses left today; `None` when the daily budget is unlimited.
+    pub skips_left: Option<u32>,
+    /// When working hours resume, while paused outside the schedule.
+    pub off_until: Option<DateTime<Local>>,
+    /// Whether "work late tonight" is available (schedule enabled and not already in use).
+    pub work_late_available: bool,
+    /// What the meeting behind a meeting pause is, e.g. "Zoom meeting".
+    pub meeting: Option<String>,
+    /// What the per-application rule in effect matched, if any.
+    pub app_rule: Option<String>,
+    /// Whether a break that is due is held back by a `defer` rule or a presentation.
+    pub deferred: bool,
+    /// What is being presented or shared, if anything.
+    pub presentation: Option<String>,
+    /// The event behind a calendar pause.
+    pub calendar_event: Option<CalendarEvent>,
+}
+
+/// The menu bar icon: eyes open while working, blinking before a break, closed during one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayIconState {
+    Open,
+    Blink,
+    Rest,
+}
+
+/// Side effects requested by the [`BreakScheduler`], applied by the Tauri adapter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    OpenOverlays {
+        info: BreakInfo,
+        strict: bool,
+    },
+    CloseOverlays,
+    EnableStrictInput,
+    DisableStrictInput,
+    PlaySound,
+    /// Send the pre-break notification `lead_seconds` before the break.
+    Notify {
+        lead_seconds: u32,
+        /// Snooze length on offer (0 = none), mentioned in the notification.
+        snooze_seconds: u32,
+    },
+    /// Send a reminder notification: a reminder schedule, or a break held for too long.
+    Remind {
+        name: String,
+        message: String,
+    },
+    SetIcon(TrayIconState),
+    /// Work timer state changed; emit `timer:tick` and refresh the tray menu.
+    Tick(TimerStatus),
+    /// Break countdown changed; emit `break:tick` to the overlays.
+    BreakTick {
+        seconds_remaining: u32,
+    },
+    BreakStarted {
+        duration: u32,
+        kind: BreakKind,
+    },
+    BreakEnded {
+        force_skipped: bool,
+    },
+    /// Write the timer state to disk.
+    Persist,
+}
+
+/// Phase of the work/break cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Working,
+    Break {
+        seconds_left: u32,
+        duration: u32,
+        kind: BreakKind,
+    },
+    /// The system is asleep or the screen is locked; no time is counted.
+    Asleep,
+}
+
+/// Persist at most once every this many counted seconds.
+const PERSIST_EVERY_SECONDS: u32 = 30;
+
+/// An overlay reminder due within this many seconds of a break is folded into that break
+/// instead of opening its own overlay.
+const REMINDER_MERGE_WINDOW_SECONDS: u32 = 60;
+
+/// Next due time of one reminder schedule, for the tray.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReminderStatus {
+    pub name: String,
+    pub seconds_remaining: u32,
+}
+
+/// Runtime countdown for one enabled reminder schedule.
+#[derive(Debug, Clone)]
+struct Reminder {
+    name: String,
+    message: String,
+    interval_seconds: u32,
+    duration_seconds: u32,
+    delivery: ReminderDelivery,
+    seconds_remaining: u32,
+}
+
+impl Reminder {
+    /// Builds the countdowns for the enabled reminders in `config`, keeping the progress of
+    /// schedules that already exist in `previous` (matched by name).
+    fn from_config(config: &AppConfig, previous: &[Reminder]) -> Vec<Reminder> {
+        config
+            .reminders
+            .iter()
+            .filter(|r| r.enabled)
+            .map(|r| {
+                let interval_seconds = r.interval_minutes * 60;
+                let seconds_remaining = previous
+                    .iter()
+                    .find(|p| p.name == r.name)
+                    .map(|p| p.seconds_remaining.min(interval_seconds))
+                    .unwrap_or(interval_seconds);
+                Reminder {
+                    name: r.name.clone(),
+                    message: r.display_message(),
+                    interval_seconds,
+                    duration_seconds: r.duration_seconds,
+                    delivery: r.delivery,
+                    seconds_remaining,
+                }
+            })
+            .collect()
+    }
+
+    fn reset(&mut self) {
+        self.seconds_remaining = self.interval_seconds;
+    }
+}
+
+/// Pure state machine for the work/break cycle.
+///
+/// Owns the shared [`TimerState`] plus the break phase and bookkeeping that used to live as
+/// locals in the timer loop. Every transition happens in [`BreakScheduler::handle`], which
+/// returns the [`Effect`]s the caller must perform; the scheduler itself never touches the
+/// `AppHandle`, so it can be driven deterministically with a fake [`Clock`].
+pub struct BreakScheduler<C: Clock = SystemClock> {
+    pub state: TimerState,
+    phase: Phase,
+    break_duration_seconds: u32,
+    long_break_duration_seconds: u32,
+    long_break_every: u32,
+    pre_warning_seconds: u32,
+    idle_pause_seconds: u32,
+    snooze_minutes: u32,
+    snooze_limit: u32,
+    snooze_shrinks: bool,
+    daily_skip_budget: u32,
+    sleep_policy: SleepPolicy,
+    /// Phase interrupted by sleep and when the sleep began.
+    slept_from: Option<(Phase, DateTime<Local>)>,
+    /// Longest idle reading seen during the current idle pause.
+    idle_peak_seconds: u32,
+    notified_pre_warning: bool,
+    reminders: Vec<Reminder>,
+    /// Working hours; `None` when the schedule is disabled.
+    schedule: Option<WeeklySchedule>,
+    /// End of an active "work late tonight" override.
+    work_late_until: Option<DateTime<Local>>,
+    /// When working hours resume, while paused outside the schedule.
+    off_until: Option<DateTime<Local>>,
+    /// The per-application rule for the focused application, if any.
+    app_rule: Option<FocusedRule>,
+    /// The last meeting detected, while a meeting pause may be in effect.
+    meeting: Option<MeetingSignal>,
+    meeting_start_polls: u32,
+    meeting_end_polls: u32,
+    meeting_grace_seconds: u32,
+    meeting_catch_up_seconds: u32,
+    /// Consecutive polls that saw a meeting while not paused for one, and that saw none while
+    /// paused for one.
+    meeting_hits: u32,
+    meeting_misses: u32,
+    /// When the current meeting pause began; `None` for a pause restored from disk.
+    meeting_since: Option<DateTime<Local>>,
+    /// What is being presented or shared, if anything.
+    presentation: Option<String>,
+    presentation_max_deferral_seconds: u32,
+    /// How long the due break has been held for the current presentation.
+    presentation_held_seconds: u32,
+    pause_during_events: bool,
+    /// How far a break may be moved forward to end before a calendar event; 0 = never.
+    pull_forward_seconds: u32,
+    /// The busy calendar event in progress, when `pause_during_events` is on.
+    calendar_event: Option<CalendarEvent>,
+    /// The break in progress, if any.
+    current_break: Option<BreakInfo>,
+    persist_counter: u32,
+    clock: C,
+    /// Instant up to which time has been accounted for.
+    last_step: Instant,
+}
+
+impl BreakScheduler<SystemClock> {
+    /// Creates a scheduler driven by the system clock.
+    pub fn new(state: TimerState, config: &AppConfig) -> Self {
+        Self::with_clock(state, config, SystemClock)
+    }
+}
+
+impl<C: Clock> BreakScheduler<C> {
+    /// Creates a scheduler driven by `clock`, starting in the working phase.
+    pub fn with_clock(state: TimerState, config: &AppConfig, clock: C) -> Self {
+        let last_step = clock.now();
+        Self {
+            state,
+            phase: Phase::Working,
+            break_duration_seconds: config.break_duration_seconds,
+            long_break_duration_seconds: config.long_break_duration_seconds,
+            long_break_every: config.long_break_every,
+            pre_warning_seconds: config.pre_warning.seconds(),
+            idle_pause_seconds: config.idle_pause_seconds,
+            snooze_minutes: config.snooze_minutes,
+            snooze_limit: config.snooze_limit,
+            snooze_shrinks: config.snooze_shrinks,
+            daily_skip_budget: config.daily_skip_budget,
+            sleep_policy: config.sleep_policy,
+            slept_from: None,
+            idle_peak_seconds: 0,
+            notified_pre_warning: false,
+            reminders: Reminder::from_config(config, &[]),
+            schedule: WeeklySchedule::from_config(&config.schedule),
+            work_late_until: None,
+            off_until: None,
+            app_rule: None,
+            meeting: None,
+            meeting_start_polls: config.meeting_start_polls,
+            meeting_end_polls: config.meeting_end_polls,
+            meeting_grace_seconds: config.meeting_grace_seconds,
+            meeting_catch_up_seconds: config.meeting_catch_up_minutes * 60,
+            meeting_hits: 0,
+            meeting_misses: 0,
+            meeting_since: None,
+            presentation: None,
+            presentation_max_deferral_seconds: config.presentation_max_deferral_minutes * 60,
+            presentation_held_seconds: 0,
+            pause_during_events: config.calendar.pause_during_events,
+            pull_forward_seconds: config.calendar.pull_forward_minutes * 60,
+            calendar_event: None,
+            current_break: None,
+            persist_counter: 0,
+            clock,
+            last_step,
+        }
+    }
+
+    /// The break in progress, if any.
+    pub fn current_break(&self) -> Option<&BreakInfo> {
+        self.current_break.as_ref()
+    }
+
+    /// Tray skips and pauses left today, or `None` when the daily budget is unlimited.
+    ///
+    /// The budget resets at local midnight.
+    pub fn skips_left(&self) -> Option<u32> {
+        if self.daily_skip_budget == 0 {
+            return None;
+        }
+        let today = self.clock.local_now().date_naive();
+        let used = if self.state.skip_budget_day == Some(today) {
+            self.state.skips_used_today
+        } else {
+            0
+        };
+        Some(self.daily_skip_budget.saturating_sub(used))
+    }
+
+    /// Spends one skip or pause from today's budget; `false` once it is used up.
+    fn take_skip(&mut self) -> bool {
+        if self.skips_left() == Some(0) {
+            log::info!("Daily skip budget used up — ignoring skip/pause");
+            return false;
+        }
+        let today = self.clock.local_now().date_naive();
+        if self.state.skip_budget_day != Some(today) {
+            self.state.skip_budget_day = Some(today);
+            self.state.skips_used_today = 0;
+        }
+        self.state.skips_used_today += 1;
+        true
+    }
+
+    /// When the next break starts or the running break ends, on the monotonic clock.
+    ///
+    /// The timer loop wakes at this instant so a phase change does not wait for the next
+    /// once-a-second UI tick. `None` while paused, asleep or holding a deferred break.
+    ///
+    /// Never earlier than a second after the last step: a countdown already at zero (after a
+    /// snooze taken during a break, or a break pulled forward to now) only moves on once a
+    /// whole second has passed, and waking before that would find nothing to do.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let seconds = match self.phase {
+            Phase::Working if self.break_deferred() => return None,
+            Phase::Working if !self.state.is_paused => self.state.seconds_remaining,
+            Phase::Break { seconds_left, .. } => seconds_left,
+            _ => return None,
+        };
+        Some(self.last_step + Duration::from_secs(seconds.max(1).into()))
+    }
+
+    /// The break in progress in its on-disk form, for [`persist_state`].
+    pub fn active_break(&self) -> Option<PersistedBreak> {
+        let Phase::Break {
+            seconds_left,
+            duration,
+            kind,
+        } = self.phase
+        else {
+            return None;
+        };
+        Some(PersistedBreak {
+            kind,
+            duration,
+            seconds_left,
+            reminders: self
+                .current_break
+                .as_ref()
+                .map(|b| b.reminders.clone())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Reopens a break restored from disk with the time it had left.
+    pub fn resume_break(&mut self, saved: PersistedBreak) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        self.open_break(saved.kind, saved.duration, saved.reminders, &mut effects);
+        self.phase = Phase::Break {
+            seconds_left: saved.seconds_left,
+            duration: saved.duration,
+            kind: saved.kind,
+        };
+        effects.push(Effect::BreakTick {
+            seconds_remaining: saved.seconds_left,
+        });
+        effects
+    }
+
+    /// Length of the snooze currently on offer, or 0 when snoozing is unavailable (strict mode,
+    /// snoozing disabled, per-cycle limit reached, or a reminder-only break).
+    ///
+    /// With `snooze_shrinks`, each snooze in a cycle halves the next one, down to one minute.
+    pub fn snooze_seconds(&self) -> u32 {
+        if self.state.is_strict_mode
+            || self.snooze_limit == 0
+            || self.state.snoozes_this_cycle >= self.snooze_limit
+            || matches!(
+                self.phase,
+                Phase::Asleep
+                    | Phase::Break {
+                        kind: BreakKind::Reminder,
+                        ..
+                    }
+            )
+        {
+            return 0;
+        }
+        let base = self.snooze_minutes * 60;
+        if self.snooze_shrinks {
+            (base >> self.state.snoozes_this_cycle.min(31)).max(60)
+        } else {
+            base
+        }
+    }
+
+    /// Next due time of every enabled reminder schedule.
+    pub fn reminder_statuses(&self) -> Vec<ReminderStatus> {
+        self.reminders
+            .iter()
+            .map(|r| ReminderStatus {
+                name: r.name.clone(),
+                seconds_remaining: r.seconds_remaining,
+            })
+            .collect()
+    }
+
+    /// Kind of the upcoming break, or `None` when long breaks are disabled.
+    pub fn next_break(&self) -> Option<BreakKind> {
+        (self.long_break_every > 0).then(|| self.state.next_break_kind(self.long_break_every))
+    }
+
+    /// Applies updated settings. Takes effect from the next cycle for the interval and
+    /// break duration; strict mode applies immediately.
+    pub fn configure(&mut self, config: &AppConfig) {
+        self.state.is_strict_mode = config.strict_mode;
+        self.state.work_interval_seconds = config.work_interval_minutes * 60;
+        self.break_duration_seconds = config.break_duration_seconds;
+        self.long_break_duration_seconds = config.long_break_duration_seconds;
+        self.long_break_every = config.long_break_every;
+        self.pre_warning_seconds = config.pre_warning.seconds();
+        self.idle_pause_seconds = config.idle_pause_seconds;
+        self.snooze_minutes = config.snooze_minutes;
+        self.snooze_limit = config.snooze_limit;
+        self.snooze_shrinks = config.snooze_shrinks;
+        self.daily_skip_budget = config.daily_skip_budget;
+        self.sleep_policy = config.sleep_policy;
+        self.reminders = Reminder::from_config(config, &self.reminders);
+        self.schedule = WeeklySchedule::from_config(&config.schedule);
+        self.meeting_start_polls = config.meeting_start_polls;
+        self.meeting_end_polls = config.meeting_end_polls;
+        self.meeting_grace_seconds = config.meeting_grace_seconds;
+        self.meeting_catch_up_seconds = config.meeting_catch_up_minutes * 60;
+        self.presentation_max_deferral_seconds = config.presentation_max_deferral_minutes * 60;
+        self.pause_during_events = config.calendar.pause_during_events;
+        self.pull_forward_seconds = config.calendar.pull_forward_minutes * 60;
+        // Recompute `off_until` against the new schedule on the next tick.
+        if self.state.pause_reason == Some(PauseReason::OutsideSchedule) {
+            self.state.is_paused = false;
+            self.state.pause_reason = None;
+            self.off_until = None;
+        }
+    }
+
+    /// Feeds one input into the state machine and returns the effects to perform, in order.
+    pub fn handle(&mut self, input: Input) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        match input {
+            Input::Tick => self.on_tick(&mut effects),
+            Input::MeetingStarted(signal) => self.on_meeting_started(signal, &mut effects),
+            Input::MeetingEnded => self.on_meeting_ended(&mut effects),
+            Input::Idle { seconds } => self.on_idle(seconds, &mut effects),
+            Input::Sleep => self.on_sleep(&mut effects),
+            Input::Wake => self.on_wake(&mut effects),
+            Input::Skip => {
+                if !self.state.is_strict_mode && self.take_skip() {
+                    self.reset_cycle();
+                    effects.push(Effect::Persist);
+                    effects.push(self.tick_effect());
+                    log::info!("Break skipped via tray");
+                }
+            }
+            Input::Pause { seconds } => {
+                if !self.state.is_strict_mode
+                    && self.state.pause_reason != Some(PauseReason::OutsideSchedule)
+                    && self.take_skip()
+                {
+                    self.state.is_paused = true;
+                    self.state.pause_reason = Some(PauseReason::Manual);
+                    self.state.manual_pause_seconds_remaining = Some(seconds);
+                    effects.push(Effect::Persist);
+                    effects.push(self.tick_effect());
+                    log::info!("Timer paused for {seconds} s via tray");
+                }
+            }
+            Input::ForceSkip => self.end_break(true, &mut effects),
+            Input::Snooze => self.on_snooze(&mut effects),
+            Input::AppRule(rule) => self.on_app_rule(rule, &mut effects),
+            Input::Presentation(presentation) => {
+                if presentation != self.presentation {
+                    match &presentation {
+                        Some(p) => log::info!("Presenting ({p}) — a due break waits until it ends"),
+                        None => log::info!("Presentation over"),
+                    }
+                    self.presentation = presentation;
+                    self.presentation_held_seconds = 0;
+                    effects.push(self.tick_effect());
+                }
+            }
+            Input::Calendar { current, next } => self.on_calendar(current, next, &mut effects),
+            Input::WorkLate => {
+                if self.schedule.is_some() {
+                    let until = schedule::end_of_day(self.clock.local_now());
+                    log::info!("Working late — schedule ignored until {until}");
+                    self.work_late_until = Some(until);
+                    self.apply_schedule(&mut effects);
+                    effects.push(self.tick_effect());
+                }
+            }
+        }
+        effects
+    }
+
+    /// Snoozes the break for the overlay's snooze button, failing when no snooze is on offer
+    /// (see [`Self::snooze_seconds`]) or another pause blocks it.
+    pub fn snooze(&mut self) -> Result<Vec<Effect>, String> {
+        let effects = self.handle(Input::Snooze);
+        if effects.is_empty() {
+            return Err("Snooze is not available".into());
+        }
+        Ok(effects)
+    }
+
+    fn on_tick(&mut self, effects: &mut Vec<Effect>) {
+        let now = self.clock.now();
+        if self.phase == Phase::Asleep {
+            self.last_step = now;
+            return;
+        }
+        self.apply_schedule(effects);
+        let elapsed = now.saturating_duration_since(self.last_step).as_secs();
+        self.last_step += Duration::from_secs(elapsed);
+        for _ in 0..elapsed {
+            self.step_second(effects);
+        }
+        if elapsed == 0 {
+            return;
+        }
+        match self.phase {
+            Phase::Break { seconds_left, .. } => effects.push(Effect::BreakTick {
+                seconds_remaining: seconds_left,
+            }),
+            _ => effects.push(self.tick_effect()),
+        }
+    }
+
+    /// Advances the state machine by exactly one second.
+    fn step_second(&mut self, effects: &mut Vec<Effect>) {
+        match self.phase {
+            Phase::Asleep => {}
+            Phase::Break {
+                seconds_left,
+                duration,
+                kind,
+            } => {
+                // Decrement first, then check for completion so the break lasts exactly
+                // as long as configured.
+                let seconds_left = seconds_left.saturating_sub(1);
+                if seconds_left == 0 {
+                    match kind {
+                        BreakKind::Short => self.state.short_breaks_since_long += 1,
+                        BreakKind::Long => self.state.short_breaks_since_long = 0,
+                        BreakKind::Reminder => {}
+                    }
+                    self.end_break(false, effects);
+                    log::info!("Break complete — restarting work timer");
+                } else {
+                    self.phase = Phase::Break {
+                        seconds_left,
+                        duration,
+                        kind,
+                    };
+                }
+            }
+            Phase::Working if self.state.is_paused => {
+                // Manual pause / snooze auto-resume, as soon as its last second has passed so
+                // the next second counts towards work again.
+                if let Some(r) = self.state.manual_pause_seconds_remaining.as_mut() {
+                    *r = r.saturating_sub(1);
+                    if *r == 0 {
+                        self.state.manual_pause_seconds_remaining = None;
+                        if matches!(
+                            self.state.pause_reason,
+                            Some(PauseReason::Manual | PauseReason::Snoozed)
+                        ) {
+                            self.state.is_paused = false;
+                            self.state.pause_reason = None;
+                        }
+                    }
+                }
+                self.maybe_persist(effects);
+            }
+            Phase::Working => {
+                self.state.seconds_remaining = self.state.seconds_remaining.saturating_sub(1);
+                let remaining = self.state.seconds_remaining;
+
+                if !self.notified_pre_warning
+                    && self.pre_warning_seconds > 0
+                    && remaining == self.pre_warning_seconds
+                {
+                    self.notified_pre_warning = true;
+                    effects.push(Effect::Notify {
+                        lead_seconds: self.pre_warning_seconds,
+                        snooze_seconds: self.snooze_seconds(),
+                    });
+                    effects.push(Effect::SetIcon(TrayIconState::Blink));
+                }
+
+                self.maybe_persist(effects);
+
+                if remaining == 0 && !self.break_deferred() {
+                    self.start_break(effects);
+                } else {
+                    if remaining == 0 && self.presentation.is_some() {
+                        self.hold_for_presentation(effects);
+                    }
+                    self.step_reminders(effects);
+                }
+            }
+        }
+    }
+
+    /// Advances the reminder countdowns by one working second and delivers any that fall due.
+    ///
+    /// Notification reminders fire immediately. Overlay reminders that fall due while the eye
+    /// break is more than [`REMINDER_MERGE_WINDOW_SECONDS`] away open their own overlay; closer
+    /// than that they wait at zero and are folded into the eye break by [`Self::start_break`].
+    fn step_reminders(&mut self, effects: &mut Vec<Effect>) {
+        let eye_break_near = self.state.seconds_remaining <= REMINDER_MERGE_WINDOW_SECONDS;
+        let mut due_overlays = false;
+        for r in &mut self.reminders {
+            r.seconds_remaining = r.seconds_remaining.saturating_sub(1);
+            if r.seconds_remaining > 0 {
+                continue;
+            }
+            match r.delivery {
+                ReminderDelivery::Notification => {
+                    log::info!("Reminder due: {}", r.name);
+                    effects.push(Effect::Remind {
+                        name: r.name.clone(),
+                        message: r.message.clone(),
+                    });
+                    r.reset();
+                }
+                ReminderDelivery::Overlay => due_overlays |= !eye_break_near,
+            }
+        }
+        if due_overlays {
+            self.start_reminder_break(effects);
+        }
+    }
+
+    /// Opens an overlay for every overlay reminder that is due, without touching the eye cycle.
+    fn start_reminder_break(&mut self, effects: &mut Vec<Effect>) {
+        let mut duration = 0;
+        let messages = self.take_overlay_reminders(0, &mut duration);
+        log::info!(
+            "Reminder break ({}) — opening overlays",
+            messages.join(", ")
+        );
+        self.open_break(BreakKind::Reminder, duration, messages, effects);
+    }
+
+    /// Resets every overlay reminder due within `window` seconds and returns their messages,
+    /// raising `duration` to the longest of their durations.
+    fn take_overlay_reminders(&mut self, window: u32, duration: &mut u32) -> Vec<String> {
+        let mut messages = Vec::new();
+        for r in &mut self.reminders {
+            if r.delivery == ReminderDelivery::Overlay && r.seconds_remaining <= window {
+                messages.push(r.message.clone());
+                *duration = (*duration).max(r.duration_seconds);
+                r.reset();
+            }
+        }
+        messages
+    }
+
+    fn start_break(&mut self, effects: &mut Vec<Effect>) {
+        let kind = self.state.next_break_kind(self.long_break_every);
+        let mut duration = match kind {
+            BreakKind::Long => self.long_break_duration_seconds,
+            _ => self.break_duration_seconds,
+        };
+        // Fold overlay reminders that are due soon into this break.
+        let reminders = self.take_overlay_reminders(REMINDER_MERGE_WINDOW_SECONDS, &mut duration);
+        log::info!("Break time ({kind:?}, {duration} s)! Opening overlays.");
+        self.open_break(kind, duration, reminders, effects);
+    }
+
+    fn open_break(
+        &mut self,
+        kind: BreakKind,
+        duration: u32,
+        reminders: Vec<String>,
+        effects: &mut Vec<Effect>,
+    ) {
+        let strict = self.state.is_strict_mode;
+        self.phase = Phase::Break {
+            seconds_left: duration,
+            duration,
+            kind,
+        };
+        let info = BreakInfo {
+            kind,
+            duration,
+            reminders,
+            snooze_seconds: self.snooze_seconds(),
+        };
+        self.current_break = Some(info.clone());
+        if strict {
+            effects.push(Effect::EnableStrictInput);
+        }
+        effects.push(Effect::OpenOverlays { info, strict });
+        effects.push(Effect::PlaySound);
+        effects.push(Effect::SetIcon(TrayIconState::Rest));
+        effects.push(Effect::BreakStarted { duration, kind });
+        effects.push(Effect::Persist);
+    }
+
+    /// Closes the break (completed or force-skipped) and starts a fresh work cycle.
+    ///
+    /// A reminder-only break leaves the eye countdown where it was.
+    fn end_break(&mut self, force_skipped: bool, effects: &mut Vec<Effect>) {
+        let reminder_only = matches!(
+            self.phase,
+            Phase::Break {
+                kind: BreakKind::Reminder,
+                ..
+            }
+        );
+        self.phase = Phase::Working;
+        self.current_break = None;
+        if !reminder_only {
+            self.reset_cycle();
+        }
+        effects.push(Effect::CloseOverlays);
+        effects.push(Effect::DisableStrictInput);
+        effects.push(Effect::BreakEnded { force_skipped });
+        effects.push(Effect::Persist);
+        effects.push(Effect::SetIcon(TrayIconState::Open));
+    }
+
+    /// Pauses the work timer outside working hours and starts a fresh cycle when they begin.
+    ///
+    /// A break already in progress is allowed to finish; the pause applies from the next tick.
+    fn apply_schedule(&mut self, effects: &mut Vec<Effect>) {
+        let outside = self.state.pause_reason == Some(PauseReason::OutsideSchedule);
+        let Some(schedule) = &self.schedule else {
+            return;
+        };
+        let now = self.clock.local_now();
+        if self.work_late_until.is_some_and(|until| now >= until) {
+            self.work_late_until = None;
+        }
+        let active = self.work_late_until.is_some() || schedule.is_active(now);
+        if !active && !outside && self.phase == Phase::Working {
+            self.off_until = schedule.next_start(now);
+            log::info!(
+                "Outside working hours — timer off until {:?}",
+                self.off_until
+            );
+            self.state.is_paused = true;
+            self.state.pause_reason = Some(PauseReason::OutsideSchedule);
+            self.state.manual_pause_seconds_remaining = None;
+            effects.push(Effect::SetIcon(TrayIconState::Open));
+            effects.push(self.tick_effect());
+        } else if active && outside {
+            log::info!("Working hours started — starting a fresh cycle");
+            self.off_until = None;
+            self.reset_cycle();
+            effects.push(self.tick_effect());
+        }
+    }
+
+    /// Pauses the timer once `meeting_start_polls` polls in a row have seen a meeting, closing
+    /// a break in progress.
+    fn on_meeting_started(&mut self, signal: MeetingSignal, effects: &mut Vec<Effect>) {
+        self.meeting_misses = 0;
+        if self.app_action() == Some(AppAction::Allow) {
+            self.meeting_hits = 0;
+            return;
+        }
+        match self.state.pause_reason {
+            Some(PauseReason::Meeting) => {
+                // Same pause, possibly a different app (or the first poll after a restart).
+                if self.meeting.as_ref() != Some(&signal) {
+                    self.meeting = Some(signal);
+                    effects.push(self.tick_effect());
+                }
+                return;
+            }
+            Some(PauseReason::OutsideSchedule) => return,
+            _ => {}
+        }
+        self.meeting_hits += 1;
+        if self.meeting_hits < self.meeting_start_polls {
+            return;
+        }
+        self.meeting_hits = 0;
+        self.meeting_since = Some(self.clock.local_now());
+        log::info!(
+            "Meeting detected ({}: {}) — pausing timer",
+            signal.detector,
+            signal.matched
+        );
+        self.meeting = Some(signal);
+        if let Phase::Break { kind, .. } = self.phase {
+            self.phase = Phase::Working;
+            self.current_break = None;
+            if kind != BreakKind::Reminder {
+                self.state.seconds_remaining = self.state.work_interval_seconds;
+                self.notified_pre_warning = false;
+            }
+            effects.push(Effect::CloseOverlays);
+            effects.push(Effect::DisableStrictInput);
+            effects.push(Effect::SetIcon(TrayIconState::Open));
+        }
+        self.state.is_paused = true;
+        self.state.pause_reason = Some(PauseReason::Meeting);
+        effects.push(Effect::Persist);
+        effects.push(self.tick_effect());
+    }
+
+    /// Resumes the timer once `meeting_end_polls` polls in a row have seen no meeting.
+    ///
+    /// No break starts within `meeting_grace_seconds` of the end; after a meeting of at least
+    /// `meeting_catch_up_seconds`, the break starts as soon as the grace period is over.
+    fn on_meeting_ended(&mut self, effects: &mut Vec<Effect>) {
+        self.meeting_hits = 0;
+        if self.state.pause_reason != Some(PauseReason::Meeting) {
+            self.meeting = None;
+            self.meeting_since = None;
+            self.meeting_misses = 0;
+            return;
+        }
+        self.meeting_misses += 1;
+        if self.meeting_misses < self.meeting_end_polls {
+            return;
+        }
+        self.meeting_misses = 0;
+        self.meeting = None;
+        self.state.is_paused = false;
+        self.state.pause_reason = None;
+        let length = self
+            .meeting_since
+            .take()
+            .map(|since| (self.clock.local_now() - since).num_seconds())
+            .and_then(|s| u32::try_from(s).ok());
+        let grace = self.meeting_grace_seconds;
+        match length {
+            Some(length)
+                if self.meeting_catch_up_seconds > 0 && length >= self.meeting_catch_up_seconds =>
+            {
+                log::info!(
+                    "Meeting ended after {} min — catch-up break in {grace} s",
+                    length / 60
+                );
+                self.state.seconds_remaining = grace;
+            }
+            _ => {
+                log::info!("Meeting ended — resuming timer");
+                self.state.seconds_remaining = self.state.seconds_remaining.max(grace);
+            }
+        }
+        self.warn_if_due(effects);
+        effects.push(Effect::Persist);
+        effects.push(self.tick_effect());
+    }
+
+    /// Sends the pre-break warning now if the countdown was moved to within it, since
+    /// [`Self::step_second`] only sends it when the countdown passes the warning point.
+    fn warn_if_due(&mut self, effects: &mut Vec<Effect>) {
+        let remaining = self.state.seconds_remaining;
+        if !self.notified_pre_warning && remaining > 0 && remaining <= self.pre_warning_seconds {
+            self.notified_pre_warning = true;
+            effects.push(Effect::Notify {
+                lead_seconds: remaining,
+                snooze_seconds: self.snooze_seconds(),
+            });
+            effects.push(Effect::SetIcon(TrayIconState::Blink));
+        }
+    }
+
+    /// Applies the latest per-application rule evaluation.
+    ///
+    /// `pause` pauses the work countdown unless another pause is already in effect; `defer`
+    /// holds a break that falls due at zero until the rule stops applying; `allow` lifts a
+    /// meeting or calendar pause and keeps meeting detection and calendars from pausing.
+    /// Re-sent every poll, so a pause cleared by something else (a skip, the end of a meeting)
+    /// is re-applied.
+    fn on_app_rule(&mut self, rule: Option<FocusedRule>, effects: &mut Vec<Effect>) {
+        let changed = rule != self.app_rule;
+        if changed {
+            match &rule {
+                Some(r) => log::info!("App rule: {} while {} is focused", r.action, r.app),
+                None => log::info!("No app rule applies to the focused application"),
+            }
+        }
+        self.app_rule = rule;
+        let resume = match (self.app_action(), &self.state.pause_reason) {
+            (Some(AppAction::Pause), None) if self.phase == Phase::Working => {
+                self.state.is_paused = true;
+                self.state.pause_reason = Some(PauseReason::App);
+                effects.push(Effect::Persist);
+                effects.push(self.tick_effect());
+                return;
+            }
+            (Some(AppAction::Pause), _) => false,
+            (Some(AppAction::Allow), Some(PauseReason::Meeting | PauseReason::Calendar)) => true,
+            (_, reason) => *reason == Some(PauseReason::App),
+        };
+        if resume {
+            self.state.is_paused = false;
+            self.state.pause_reason = None;
+            effects.push(Effect::Persist);
+        }
+        if resume || changed {
+            effects.push(self.tick_effect());
+        }
+    }
+
+    /// Applies the latest calendar lookahead.
+    ///
+    /// With `pause_during_events`, the work countdown pauses while a busy event is in progress
+    /// (unless another pause is in effect or an `allow` rule applies) and resumes when it is
+    /// over. A break in progress is allowed to finish first. Before the next event, the break
+    /// may be moved forward; see [`Self::pull_break_forward`].
+    fn on_calendar(
+        &mut self,
+        current: Option<CalendarEvent>,
+        next: Option<CalendarEvent>,
+        effects: &mut Vec<Effect>,
+    ) {
+        let current = current.filter(|_| self.pause_during_events);
+        let changed = current != self.calendar_event;
+        self.calendar_event = current;
+        match (&self.calendar_event, &self.state.pause_reason) {
+            (Some(event), None)
+                if self.phase == Phase::Working && self.app_action() != Some(AppAction::Allow) =>
+            {
+                log::info!(
+                    "Calendar event \"{}\" until {} — pausing timer",
+                    event.summary,
+                    event.end.format("%H:%M")
+                );
+                self.state.is_paused = true;
+                self.state.pause_reason = Some(PauseReason::Calendar);
+                effects.push(Effect::Persist);
+                effects.push(self.tick_effect());
+                return;
+            }
+            (None, Some(PauseReason::Calendar)) => {
+                log::info!("Calendar event over — resuming timer");
+                self.state.is_paused = false;
+                self.state.pause_reason = None;
+                effects.push(Effect::Persist);
+                effects.push(self.tick_effect());
+                return;
+            }
+            _ => {}
+        }
+        let pulled = next.is_some_and(|event| self.pull_break_forward(&event, effects));
+        if pulled || changed {
+            effects.push(self.tick_effect());
+        }
+    }
+
+    /// Moves the next break forward so it ends as `event` starts, when it would otherwise not
+    /// be over by then and is due no more than `pull_forward_seconds` later than that. Only a
+    /// running work countdown is moved, and only if there is still time for the whole break.
+    ///
+    /// Returns whether the break was moved.
+    fn pull_break_forward(&mut self, event: &CalendarEvent, effects: &mut Vec<Effect>) -> bool {
+        if self.pull_forward_seconds == 0 || self.phase != Phase::Working || self.state.is_paused {
+            return false;
+        }
+        let Ok(lead) = u32::try_from((event.start - self.clock.local_now()).num_seconds()) else {
+            return false;
+        };
+        let break_seconds = match self.state.next_break_kind(self.long_break_every) {
+            BreakKind::Long => self.long_break_duration_seconds,
+            _ => self.break_duration_seconds,
+        };
+        let Some(target) = lead.checked_sub(break_seconds) else {
+            return false;
+        };
+        let remaining = self.state.seconds_remaining;
+        if remaining <= target || remaining - target > self.pull_forward_seconds {
+            return false;
+        }
+        log::info!(
+            "Break moved {} s forward to end as \"{}\" starts",
+            remaining - target,
+            event.summary
+        );
+        self.state.seconds_remaining = target;
+        self.warn_if_due(effects);
+        effects.push(Effect::Persist);
+        true
+    }
+
+    fn app_action(&self) -> Option<AppAction> {
+        self.app_rule.as_ref().map(|r| r.action)
+    }
+
+    /// Whether a break is due but held back by a `defer` rule or a presentation.
+    fn break_deferred(&self) -> bool {
+        self.state.seconds_remaining == 0
+            && (self.app_action() == Some(AppAction::Defer) || self.presentation.is_some())
+    }
+
+    /// Counts one second of a due break held for a presentation. Once it has been held for
+    /// `presentation_max_deferral_seconds`, a notification suggests a break instead of the
+    /// overlay and a new cycle starts, so the break is not left pending for the rest of the
+    /// presentation.
+    fn hold_for_presentation(&mut self, effects: &mut Vec<Effect>) {
+        if self.presentation_held_seconds < self.presentation_max_deferral_seconds {
+            self.presentation_held_seconds += 1;
+            return;
+        }
+        let presentation = self.presentation.as_deref().unwrap_or("presentation");
+        let minutes = self.presentation_max_deferral_seconds / 60;
+        log::info!("Break held for {presentation} for too long — notifying instead");
+        effects.push(Effect::Remind {
+            name: "Break overdue".to_string(),
+            message: format!(
+                "Your eye break has waited {minutes} minute{} for the {presentation}. Look away \
+                 for a moment when you can; the next break is a full cycle away.",
+                if minutes == 1 { "" } else { "s" }
+            ),
+        });
+        self.presentation_held_seconds = 0;
+        self.reset_cycle();
+        effects.push(Effect::Persist);
+        effects.push(Effect::SetIcon(TrayIconState::Open));
+        effects.push(self.tick_effect());
+    }
+
+    /// Postpones the break by [`Self::snooze_seconds`].
+    ///
+    /// Before the break this pauses the work countdown; during a break it closes the overlay
+    /// and the break reopens as soon as the snooze runs out. Snoozing again while snoozed
+    /// extends the snooze. Manual, meeting and idle pauses take precedence and block snoozing.
+    fn on_snooze(&mut self, effects: &mut Vec<Effect>) {
+        let seconds = self.snooze_seconds();
+        if seconds == 0 {
+            return;
+        }
+        if self.phase == Phase::Working
+            && self.state.is_paused
+            && self.state.pause_reason != Some(PauseReason::Snoozed)
+        {
+            return;
+        }
+        if let Phase::Break { .. } = self.phase {
+            self.phase = Phase::Working;
+            self.current_break = None;
+            self.state.seconds_remaining = 0;
+            effects.push(Effect::CloseOverlays);
+            effects.push(Effect::BreakEnded {
+                force_skipped: false,
+            });
+            effects.push(Effect::SetIcon(TrayIconState::Blink));
+        }
+        let pending = match self.state.pause_reason {
+            Some(PauseReason::Snoozed) => self.state.manual_pause_seconds_remaining.unwrap_or(0),
+            _ => 0,
+        };
+        self.state.snoozes_this_cycle += 1;
+        self.state.is_paused = true;
+        self.state.pause_reason = Some(PauseReason::Snoozed);
+        self.state.manual_pause_seconds_remaining = Some(pending + seconds);
+        log::info!(
+            "Break snoozed for {seconds} s ({} of {} this cycle)",
+            self.state.snoozes_this_cycle,
+            self.snooze_limit
+        );
+        effects.push(Effect::Persist);
+        effects.push(self.tick_effect());
+    }
+
+    /// Pauses the work timer once the user has been idle for `idle_pause_seconds`, and resumes
+    /// it when input is seen again (the idle reading drops).
+    ///
+    /// An absence longer than the break itself counts as a completed break: the work cycle is
+    /// reset and the long-break cycle advances as if the break had run.
+    fn on_idle(&mut self, idle: u32, effects: &mut Vec<Effect>) {
+        if self.idle_pause_seconds == 0 || self.phase != Phase::Working {
+            return;
+        }
+        match self.state.pause_reason {
+            None if idle >= self.idle_pause_seconds => {
+                log::info!("User idle for {idle} s — pausing timer");
+                self.state.is_paused = true;
+                self.state.pause_reason = Some(PauseReason::Idle);
+                self.idle_peak_seconds = idle;
+                effects.push(self.tick_effect());
+            }
+            Some(PauseReason::Idle) if idle >= self.idle_peak_seconds => {
+                self.idle_peak_seconds = idle;
+            }
+            Some(PauseReason::Idle) => {
+                let away = self.idle_peak_seconds;
+                self.idle_peak_seconds = 0;
+                self.state.is_paused = false;
+                self.state.pause_reason = None;
+                if away > self.break_duration_seconds {
+                    match self.state.next_break_kind(self.long_break_every) {
+                        BreakKind::Long if away >= self.long_break_duration_seconds => {
+                            self.state.short_breaks_since_long = 0;
+                        }
+                        BreakKind::Long => {}
+                        _ => self.state.short_breaks_since_long += 1,
+                    }
+                    self.reset_cycle();
+                    effects.push(Effect::SetIcon(TrayIconState::Open));
+                    effects.push(Effect::Persist);
+                    log::info!("User back after {away} s idle — counted as a break");
+                } else {
+                    log::info!("User back after {away} s idle — resuming timer");
+                }
+                effects.push(self.tick_effect());
+            }
+            _ => {}
+        }
+    }
+
+    fn on_sleep(&mut self, effects: &mut Vec<Effect>) {
+        if self.phase == Phase::Asleep {
+            return;
+        }
+        self.slept_from = Some((self.phase, self.clock.local_now()));
+        self.phase = Phase::Asleep;
+        self.notified_pre_warning = false;
+        effects.push(Effect::CloseOverlays);
+        effects.push(Effect::DisableStrictInput);
+        log::info!("System asleep or screen locked — timer loop suspended");
+    }
+
+    /// Ends a sleep or screen lock according to the configured [`SleepPolicy`].
+    ///
+    /// Time away is measured on the wall clock, since the monotonic clock stops during system
+    /// sleep. Resuming an interrupted break reopens it; under `reset_if_rested` the time away
+    /// counts towards it, and a break fully covered by the time away counts as taken.
+    fn on_wake(&mut self, effects: &mut Vec<Effect>) {
+        if self.phase != Phase::Asleep {
+            return;
+        }
+        let (previous, since) = self
+            .slept_from
+            .take()
+            .unwrap_or((Phase::Working, self.clock.local_now()));
+        let away = u32::try_from((self.clock.local_now() - since).num_seconds()).unwrap_or(0);
+        self.phase = Phase::Working;
+        self.last_step = self.clock.now();
+        let reminders = self
+            .current_break
+            .take()
+            .map(|b| b.reminders)
+            .unwrap_or_default();
+
+        // The break that was interrupted, or the one that would have come next.
+        let (kind, break_left) = match previous {
+            Phase::Break {
+                seconds_left, kind, ..
+            } => (kind, seconds_left),
+            _ => match self.state.next_break_kind(self.long_break_every) {
+                BreakKind::Long => (BreakKind::Long, self.long_break_duration_seconds),
+                kind => (kind, self.break_duration_seconds),
+            },
+        };
+        let rested = away >= break_left;
+        let resume = match self.sleep_policy {
+            SleepPolicy::Reset => false,
+            SleepPolicy::Resume => true,
+            SleepPolicy::ResetIfRested => !rested,
+        };
+
+        if resume {
+            log::info!("Awake after {away} s — resuming where the timer left off");
+            if let Phase::Break {
+                seconds_left,
+                duration,
+                kind,
+            } = previous
+            {
+                let seconds_left = match self.sleep_policy {
+                    SleepPolicy::ResetIfRested => seconds_left - away,
+                    _ => seconds_left,
+                };
+                effects.extend(self.resume_break(PersistedBreak {
+                    kind,
+                    duration,
+                    seconds_left,
+                    reminders,
+                }));
+            } else {
+                effects.push(self.tick_effect());
+            }
+            return;
+        }
+
+        // Start a fresh cycle; time spent away counts as rest.
+        if rested {
+            match kind {
+                BreakKind::Short => self.state.short_breaks_since_long += 1,
+                BreakKind::Long => self.state.short_breaks_since_long = 0,
+                BreakKind::Reminder => {}
+            }
+        }
+        self.reset_cycle();
+        self.reminders.iter_mut().for_each(Reminder::reset);
+        effects.push(Effect::Persist);
+        effects.push(Effect::SetIcon(TrayIconState::Open));
+        effects.push(self.tick_effect());
+        log::info!("Awake after {away} s — timer reset to full cycle");
+    }
+
+    /// Resets the work countdown to a full interval and clears any pause.
+    fn reset_cycle(&mut self) {
+        self.state.seconds_remaining = self.state.work_interval_seconds;
+        self.state.is_paused = false;
+        self.state.pause_reason = None;
+        self.state.manual_pause_seconds_remaining = None;
+        self.state.snoozes_this_cycle = 0;
+        self.notified_pre_warning = false;
+    }
+
+    fn maybe_persist(&mut self, effects: &mut Vec<Effect>) {
+        self.persist_counter += 1;
+        if self.persist_counter >= PERSIST_EVERY_SECONDS {
+            self.persist_counter = 0;
+            effects.push(Effect::Persist);
+        }
+    }
+
+    /// Snapshot of the work timer for the UI.
+    pub fn status(&self) -> TimerStatus {
+        TimerStatus {
+            seconds_remaining: self.state.seconds_remaining,
+            is_paused: self.state.is_paused,
+            pause_reason: self.state.pause_reason.clone(),
+            pause_seconds_remaining: self.state.manual_pause_seconds_remaining,
+            next_break: self.next_break(),
+            reminders: self.reminder_statuses(),
+            snooze_seconds: self.snooze_seconds(),
+            snoozes_left: self
+                .snooze_limit
+                .saturating_sub(self.state.snoozes_this_cycle),
+            skips_left: self.skips_left(),
+            off_until: self.off_until,
+            work_late_available: self.schedule.is_some() && self.work_late_until.is_none(),
+            meeting: self
+                .meeting
+                .as_ref()
+                .filter(|_| self.state.pause_reason == Some(PauseReason::Meeting))
+                .map(|m| m.label.clone()),
+            app_rule: self.app_rule.as_ref().map(|r| r.app.clone()),
+            deferred: self.break_deferred(),
+            presentation: self.presentation.clone(),
+            calendar_event: self
+                .calendar_event
+                .clone()
+                .filter(|_| self.state.pause_reason == Some(PauseReason::Calendar)),
+        }
+    }
+
+    fn tick_effect(&self) -> Effect {
+        Effect::Tick(self.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReminderConfig;
+    use chrono::TimeZone;
+
+    /// A clock that only moves when told to.
+    #[derive(Clone)]
+    struct FakeClock(Arc<Mutex<(Instant, DateTime<Local>)>>);
+
+    impl FakeClock {
+        fn new() -> Self {
+            // A Monday morning.
+            let local = Local.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+            Self(Arc::new(Mutex::new((Instant::now(), local))))
+        }
+
+        fn advance(&self, seconds: u64) {
+            let mut now = self.0.lock().unwrap();
+            now.0 += Duration::from_secs(seconds);
+            now.1 += chrono::Duration::seconds(seconds as i64);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.lock().unwrap().0
+        }
+
+        fn local_now(&self) -> DateTime<Local> {
+            self.0.lock().unwrap().1
+        }
+    }
+
+    /// One-minute work intervals, 20 s breaks and a 10 s pre-warning; meetings take effect on
+    /// the first poll with no grace period.
+    fn config() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.work_interval_minutes = 1;
+        config.break_duration_seconds = 20;
+        config.pre_warning = crate::config::PreWarning::Seconds(10);
+        config.idle_pause_seconds = 0;
+        config.meeting_start_polls = 1;
+        config.meeting_end_polls = 1;
+        config.meeting_grace_seconds = 0;
+        config.meeting_catch_up_minutes = 0;
+        config
+    }
+
+    struct Harness {
+        scheduler: BreakScheduler<FakeClock>,
+        clock: FakeClock,
+    }
+
+    impl Harness {
+        fn new(config: &AppConfig) -> Self {
+            let clock = FakeClock::new();
+            let scheduler =
+                BreakScheduler::with_clock(TimerState::new(config), config, clock.clone());
+            Self { scheduler, clock }
+        }
+
+        fn handle(&mut self, input: Input) -> Vec<Effect> {
+            self.scheduler.handle(input)
+        }
+
+        /// Lets `seconds` pass before a single tick, as when the loop was blocked.
+        fn tick_after(&mut self, seconds: u64) -> Vec<Effect> {
+            self.clock.advance(seconds);
+            self.handle(Input::Tick)
+        }
+
+        /// Ticks once a second for `seconds` seconds.
+        fn tick_each(&mut self, seconds: u64) -> Vec<Effect> {
+            (0..seconds).flat_map(|_| self.tick_after(1)).collect()
+        }
+
+        fn remaining(&self) -> u32 {
+            self.scheduler.state.seconds_remaining
+        }
+    }
+
+    fn opened(effects: &[Effect]) -> Option<&BreakInfo> {
+        effects.iter().find_map(|e| match e {
+            Effect::OpenOverlays { info, .. } => Some(info),
+            _ => None,
+        })
+    }
+
+    fn ended(effects: &[Effect]) -> Option<bool> {
+        effects.iter().find_map(|e| match e {
+            Effect::BreakEnded { force_skipped } => Some(*force_skipped),
+            _ => None,
+        })
+    }
+
+    fn meeting() -> MeetingSignal {
+        MeetingSignal::new("apps", "us.zoom.xos", "Zoom")
+    }
+
+    #[test]
+    fn break_starts_exactly_when_the_interval_runs_out() {
+        let mut h = Harness::new(&config());
+        assert!(opened(&h.tick_each(59)).is_none());
+        assert_eq!(h.remaining(), 1);
+        let effects = h.tick_each(1);
+        let info = opened(&effects).expect("break opens on the 60th second");
+        assert_eq!(info.kind, BreakKind::Short);
+        assert_eq!(info.duration, 20);
+        assert!(effects.contains(&Effect::BreakStarted {
+            duration: 20,
+            kind: BreakKind::Short
+        }));
+    }
+
+    #[test]
+    fn pre_warning_is_sent_once_at_the_lead_time() {
+        let mut h = Harness::new(&config());
+        let notified = |effects: &[Effect]| {
+            effects
+                .iter()
+                .filter(|e| matches!(e, Effect::Notify { .. }))
+                .count()
+        };
+        assert_eq!(notified(&h.tick_each(49)), 0);
+        let effects = h.tick_each(1);
+        assert_eq!(h.remaining(), 10);
+        assert!(effects.contains(&Effect::Notify {
+            lead_seconds: 10,
+            snooze_seconds: h.scheduler.snooze_seconds(),
+        }));
+        assert_eq!(notified(&h.tick_each(9)), 0);
+    }
+
+    #[test]
+    fn break_lasts_exactly_its_duration_then_restarts_the_cycle() {
+        let mut h = Harness::new(&config());
+        h.tick_each(60);
+        assert!(ended(&h.tick_each(19)).is_none());
+        assert!(h.scheduler.current_break().is_some());
+        let effects = h.tick_each(1);
+        assert_eq!(ended(&effects), Some(false));
+        assert!(effects.contains(&Effect::CloseOverlays));
+        assert!(h.scheduler.current_break().is_none());
+        assert_eq!(h.remaining(), 60);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 1);
+    }
+
+    #[test]
+    fn every_nth_break_is_long() {
+        let mut config = config();
+        config.long_break_every = 2;
+        config.long_break_duration_seconds = 300;
+        let mut h = Harness::new(&config);
+        let first = h.tick_each(60);
+        assert_eq!(opened(&first).map(|i| i.kind), Some(BreakKind::Short));
+        h.tick_each(20);
+        let second = h.tick_each(60);
+        assert_eq!(opened(&second).map(|i| i.kind), Some(BreakKind::Long));
+        assert!(ended(&h.tick_each(300)).is_some());
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 0);
+    }
+
+    #[test]
+    fn meeting_pauses_the_countdown_until_it_ends() {
+        let mut h = Harness::new(&config());
+        h.tick_each(30);
+        h.handle(Input::MeetingStarted(meeting()));
+        assert_eq!(h.scheduler.state.pause_reason, Some(PauseReason::Meeting));
+        assert_eq!(
+            h.scheduler.status().meeting.as_deref(),
+            Some("Zoom meeting")
+        );
+        assert!(opened(&h.tick_each(120)).is_none());
+        assert_eq!(h.remaining(), 30);
+
+        h.handle(Input::MeetingEnded);
+        assert!(!h.scheduler.state.is_paused);
+        assert!(opened(&h.tick_each(29)).is_none());
+        assert!(opened(&h.tick_each(1)).is_some());
+    }
+
+    #[test]
+    fn meeting_closes_a_break_in_progress() {
+        let mut h = Harness::new(&config());
+        h.tick_each(65);
+        let effects = h.handle(Input::MeetingStarted(meeting()));
+        assert!(effects.contains(&Effect::CloseOverlays));
+        assert!(effects.contains(&Effect::DisableStrictInput));
+        assert!(h.scheduler.current_break().is_none());
+        assert_eq!(h.remaining(), 60);
+    }
+
+    #[test]
+    fn meeting_needs_consecutive_polls_to_start_and_end() {
+        let mut config = config();
+        config.meeting_start_polls = 2;
+        config.meeting_end_polls = 2;
+        let mut h = Harness::new(&config);
+        h.handle(Input::MeetingStarted(meeting()));
+        h.handle(Input::MeetingEnded);
+        h.handle(Input::MeetingStarted(meeting()));
+        assert!(!h.scheduler.state.is_paused);
+        h.handle(Input::MeetingStarted(meeting()));
+        assert_eq!(h.scheduler.state.pause_reason, Some(PauseReason::Meeting));
+        h.handle(Input::MeetingEnded);
+        h.handle(Input::MeetingStarted(meeting()));
+        h.handle(Input::MeetingEnded);
+        assert!(h.scheduler.state.is_paused);
+        h.handle(Input::MeetingEnded);
+        assert!(!h.scheduler.state.is_paused);
+    }
+
+    #[test]
+    fn no_break_within_the_grace_period_after_a_meeting() {
+        let mut config = config();
+        config.meeting_grace_seconds = 45;
+        let mut h = Harness::new(&config);
+        h.tick_each(55);
+        h.handle(Input::MeetingStarted(meeting()));
+        h.tick_each(300);
+        h.handle(Input::MeetingEnded);
+        assert_eq!(h.remaining(), 45);
+    }
+
+    #[test]
+    fn long_meeting_is_followed_by_a_catch_up_break() {
+        let mut config = config();
+        config.work_interval_minutes = 20;
+        config.meeting_grace_seconds = 30;
+        config.meeting_catch_up_minutes = 10;
+        let mut h = Harness::new(&config);
+        h.tick_each(60);
+        h.handle(Input::MeetingStarted(meeting()));
+        h.tick_each(10 * 60);
+        h.handle(Input::MeetingEnded);
+        assert_eq!(h.remaining(), 30);
+    }
+
+    #[test]
+    fn time_asleep_is_not_counted_and_reset_starts_a_fresh_cycle() {
+        let mut config = config();
+        config.sleep_policy = SleepPolicy::Reset;
+        let mut h = Harness::new(&config);
+        h.tick_each(50);
+        h.handle(Input::Sleep);
+        assert!(opened(&h.tick_after(3600)).is_none());
+        let effects = h.handle(Input::Wake);
+        assert!(effects.contains(&Effect::Persist));
+        assert_eq!(h.remaining(), 60);
+        assert!(opened(&h.tick_each(59)).is_none());
+        assert!(opened(&h.tick_each(1)).is_some());
+    }
+
+    #[test]
+    fn short_sleep_resumes_under_reset_if_rested() {
+        let mut h = Harness::new(&config());
+        h.tick_each(30);
+        h.handle(Input::Sleep);
+        h.clock.advance(5);
+        h.handle(Input::Wake);
+        assert_eq!(h.remaining(), 30);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 0);
+    }
+
+    #[test]
+    fn sleep_longer_than_a_break_counts_as_one_under_reset_if_rested() {
+        let mut h = Harness::new(&config());
+        h.tick_each(30);
+        h.handle(Input::Sleep);
+        h.clock.advance(20);
+        h.handle(Input::Wake);
+        assert_eq!(h.remaining(), 60);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 1);
+    }
+
+    #[test]
+    fn break_interrupted_by_sleep_resumes_with_the_time_away_taken_off() {
+        let mut h = Harness::new(&config());
+        h.tick_each(62);
+        h.handle(Input::Sleep);
+        h.clock.advance(8);
+        let effects = h.handle(Input::Wake);
+        assert!(opened(&effects).is_some());
+        assert!(effects.contains(&Effect::BreakTick {
+            seconds_remaining: 10
+        }));
+        assert!(ended(&h.tick_each(9)).is_none());
+        assert_eq!(ended(&h.tick_each(1)), Some(false));
+    }
+
+    #[test]
+    fn skip_restarts_the_cycle_except_in_strict_mode() {
+        let mut h = Harness::new(&config());
+        h.tick_each(40);
+        h.handle(Input::Skip);
+        assert_eq!(h.remaining(), 60);
+
+        let mut config = config();
+        config.strict_mode = true;
+        let mut h = Harness::new(&config);
+        h.tick_each(40);
+        assert!(h.handle(Input::Skip).is_empty());
+        assert_eq!(h.remaining(), 20);
+    }
+
+    #[test]
+    fn skips_are_limited_by_the_daily_budget() {
+        let mut config = config();
+        config.daily_skip_budget = 1;
+        let mut h = Harness::new(&config);
+        h.handle(Input::Skip);
+        assert_eq!(h.scheduler.skips_left(), Some(0));
+        h.tick_each(10);
+        assert!(h.handle(Input::Skip).is_empty());
+        assert_eq!(h.remaining(), 50);
+    }
+
+    #[test]
+    fn pause_lasts_exactly_the_requested_seconds() {
+        let mut h = Harness::new(&config());
+        h.tick_each(10);
+        h.handle(Input::Pause { seconds: 5 });
+        h.tick_each(4);
+        assert!(h.scheduler.state.is_paused);
+        h.tick_each(1);
+        assert!(!h.scheduler.state.is_paused);
+        assert_eq!(h.remaining(), 50);
+        h.tick_each(1);
+        assert_eq!(h.remaining(), 49);
+    }
+
+    #[test]
+    fn pause_is_ignored_in_strict_mode() {
+        let mut config = config();
+        config.strict_mode = true;
+        let mut h = Harness::new(&config);
+        assert!(h.handle(Input::Pause { seconds: 60 }).is_empty());
+        assert!(!h.scheduler.state.is_paused);
+    }
+
+    #[test]
+    fn force_skip_ends_a_strict_break_without_counting_it() {
+        let mut config = config();
+        config.strict_mode = true;
+        let mut h = Harness::new(&config);
+        let effects = h.tick_each(60);
+        assert!(effects.contains(&Effect::EnableStrictInput));
+        h.tick_each(3);
+        let effects = h.handle(Input::ForceSkip);
+        assert_eq!(ended(&effects), Some(true));
+        assert!(effects.contains(&Effect::DisableStrictInput));
+        assert_eq!(h.remaining(), 60);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 0);
+    }
+
+    #[test]
+    fn blocked_tick_does_not_delay_the_break() {
+        let mut h = Harness::new(&config());
+        h.tick_each(30);
+        // The loop was stuck for 35 s: the break started 5 s ago and has 15 s left.
+        let effects = h.tick_after(35);
+        assert!(opened(&effects).is_some());
+        assert!(effects.contains(&Effect::BreakTick {
+            seconds_remaining: 15
+        }));
+        assert_eq!(ended(&h.tick_each(15)), Some(false));
+    }
+
+    #[test]
+    fn blocked_tick_spanning_a_whole_break_completes_it() {
+        let mut h = Harness::new(&config());
+        let effects = h.tick_after(60 + 20 + 10);
+        assert!(opened(&effects).is_some());
+        assert_eq!(ended(&effects), Some(false));
+        assert_eq!(h.remaining(), 50);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 1);
+    }
+
+    #[test]
+    fn deadline_is_the_break_start_and_end() {
+        let mut h = Harness::new(&config());
+        h.tick_each(45);
+        let start = h.clock.now();
+        assert_eq!(
+            h.scheduler.next_deadline(),
+            Some(start + Duration::from_secs(15))
+        );
+        h.tick_after(15);
+        assert_eq!(
+            h.scheduler.next_deadline(),
+            Some(start + Duration::from_secs(35))
+        );
+        h.handle(Input::Sleep);
+        assert_eq!(h.scheduler.next_deadline(), None);
+    }
+
+    #[test]
+    fn deadline_is_never_in_the_past() {
+        let mut h = Harness::new(&config());
+        h.tick_each(61);
+        h.handle(Input::Snooze);
+        assert_eq!(h.scheduler.next_deadline(), None);
+        h.tick_each(300);
+        assert!(!h.scheduler.state.is_paused);
+        assert_eq!(h.remaining(), 0);
+        let now = h.clock.now();
+        assert_eq!(
+            h.scheduler.next_deadline(),
+            Some(now + Duration::from_secs(1))
+        );
+        assert!(opened(&h.tick_after(1)).is_some());
+    }
+
+    #[test]
+    fn partial_seconds_carry_over_to_the_next_tick() {
+        let mut h = Harness::new(&config());
+        h.clock.0.lock().unwrap().0 += Duration::from_millis(700);
+        h.handle(Input::Tick);
+        assert_eq!(h.remaining(), 60);
+        h.clock.0.lock().unwrap().0 += Duration::from_millis(700);
+        h.handle(Input::Tick);
+        assert_eq!(h.remaining(), 59);
+    }
+
+    fn reminded(effects: &[Effect]) -> Vec<&str> {
+        effects
+            .iter()
+            .filter_map(|e| match e {
+                Effect::Remind { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn presentation_holds_a_due_break_until_it_ends() {
+        let mut h = Harness::new(&config());
+        h.handle(Input::Presentation(Some("Keynote slide show".into())));
+        assert!(opened(&h.tick_each(90)).is_none());
+        assert_eq!(h.remaining(), 0);
+        assert!(h.scheduler.break_deferred());
+        h.handle(Input::Presentation(None));
+        assert!(opened(&h.tick_each(1)).is_some());
+    }
+
+    #[test]
+    fn presentation_past_the_maximum_deferral_notifies_and_restarts_the_cycle() {
+        let mut config = config();
+        config.presentation_max_deferral_minutes = 1;
+        let mut h = Harness::new(&config);
+        h.handle(Input::Presentation(Some("Keynote slide show".into())));
+        let effects = h.tick_each(60 + 59);
+        assert!(opened(&effects).is_none());
+        assert!(reminded(&effects).is_empty());
+        assert_eq!(h.remaining(), 0);
+
+        let effects = h.tick_each(1);
+        assert_eq!(reminded(&effects), ["Break overdue"]);
+        assert_eq!(h.remaining(), 60);
+        assert!(!h.scheduler.break_deferred());
+
+        // Nothing is left pending: the presentation ending does not open the overlay, and the
+        // next break is a full cycle after the notification.
+        h.handle(Input::Presentation(None));
+        assert!(opened(&h.tick_each(59)).is_none());
+        assert!(opened(&h.tick_each(1)).is_some());
+    }
+
+    #[test]
+    fn presentation_keeps_notifying_once_per_held_break() {
+        let mut config = config();
+        config.presentation_max_deferral_minutes = 1;
+        let mut h = Harness::new(&config);
+        h.handle(Input::Presentation(Some("Zoom screen share".into())));
+        let effects = h.tick_each(2 * 120);
+        assert_eq!(reminded(&effects).len(), 2);
+        assert!(opened(&effects).is_none());
+    }
+
+    /// A state file path of its own for one test, in an empty directory.
+    fn state_file(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("twenty20-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("timer_state.json")
+    }
+
+    fn unix_now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn legacy_state_file_is_upgraded() {
+        let path = state_file("legacy");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let legacy = serde_json::json!({
+            "seconds_remaining": 600,
+            "saved_at": unix_now(),
+            "short_breaks_since_long": 2,
+        });
+        fs::write(&path, legacy.to_string()).unwrap();
+
+        let mut config = AppConfig::default();
+        config.strict_mode = true;
+        let restored = restore_or_create(&path, &config);
+        assert!((599..=600).contains(&restored.state.seconds_remaining));
+        assert_eq!(restored.state.short_breaks_since_long, 2);
+        assert_eq!(restored.state.work_interval_seconds, 20 * 60);
+        assert!(restored.state.is_strict_mode);
+        assert!(restored.active_break.is_none());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn strict_break_in_progress_is_resumed() {
+        let path = state_file("mid-break");
+        let mut config = AppConfig::default();
+        config.strict_mode = true;
+        let saved = PersistedBreak {
+            kind: BreakKind::Short,
+            duration: 20,
+            seconds_left: 15,
+            reminders: vec!["Stretch".into()],
+        };
+        persist_state(&path, &TimerState::new(&config), Some(saved.clone()));
+
+        let resumed = restore_or_create(&path, &config).active_break.unwrap();
+        assert_eq!(resumed.kind, BreakKind::Short);
+        assert!((14..=15).contains(&resumed.seconds_left));
+        assert_eq!(resumed.reminders, saved.reminders);
+
+        // Outside strict mode the interrupted break counts as skipped.
+        config.strict_mode = false;
+        let restored = restore_or_create(&path, &config);
+        assert!(restored.active_break.is_none());
+        assert_eq!(restored.state.seconds_remaining, 20 * 60);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn corrupt_or_truncated_state_file_starts_fresh() {
+        let path = state_file("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let config = AppConfig::default();
+        let mut state = TimerState::new(&config);
+        state.seconds_remaining = 300;
+        persist_state(&path, &state, None);
+        let full = fs::read_to_string(&path).unwrap();
+
+        for contents in [&full[..full.len() / 2], "", "not json", "{\"version\": 2}"] {
+            fs::write(&path, contents).unwrap();
+            let restored = restore_or_create(&path, &config);
+            assert_eq!(restored.state.seconds_remaining, 20 * 60, "{contents:?}");
+            assert!(restored.active_break.is_none());
+        }
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn saved_state_round_trips_through_the_file() {
+        let path = state_file("round-trip");
+        let config = AppConfig::default();
+        let mut state = TimerState::new(&config);
+        state.seconds_remaining = 321;
+        state.short_breaks_since_long = 3;
+        state.skips_used_today = 1;
+        persist_state(&path, &state, None);
+        // Written through a temporary file renamed into place.
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let loaded = PersistedTimer::load(&path, &config).unwrap();
+        assert_eq!(loaded.version, PERSIST_FORMAT_VERSION);
+        assert_eq!(
+            serde_json::to_value(&loaded.state).unwrap(),
+            serde_json::to_value(&state).unwrap()
+        );
+        assert!(loaded.active_break.is_none());
+        assert!(loaded.saved_at.abs_diff(unix_now()) <= 1);
+
+        // Saving again replaces the file.
+        state.seconds_remaining = 100;
+        persist_state(&path, &state, None);
+        let loaded = PersistedTimer::load(&path, &config).unwrap();
+        assert_eq!(loaded.state.seconds_remaining, 100);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn idle_pauses_the_countdown_until_input_returns() {
+        let mut config = config();
+        config.idle_pause_seconds = 10;
+        let mut h = Harness::new(&config);
+        h.tick_each(5);
+        assert_eq!(h.remaining(), 55);
+
+        h.handle(Input::Idle { seconds: 9 });
+        assert_eq!(h.scheduler.state.pause_reason, None);
+        h.handle(Input::Idle { seconds: 10 });
+        assert_eq!(h.scheduler.state.pause_reason, Some(PauseReason::Idle));
+        assert!(h.scheduler.state.is_paused);
+        h.tick_each(5);
+        h.handle(Input::Idle { seconds: 15 });
+        assert_eq!(h.remaining(), 55);
+
+        // Back after less than a break: the countdown carries on where it stopped.
+        h.handle(Input::Idle { seconds: 0 });
+        assert_eq!(h.scheduler.state.pause_reason, None);
+        assert!(!h.scheduler.state.is_paused);
+        h.tick_each(5);
+        assert_eq!(h.remaining(), 50);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 0);
+    }
+
+    #[test]
+    fn idle_longer_than_a_break_counts_as_one() {
+        let mut config = config();
+        config.idle_pause_seconds = 10;
+        config.long_break_every = 3;
+        config.long_break_duration_seconds = 120;
+        let mut h = Harness::new(&config);
+        h.tick_each(30);
+
+        h.handle(Input::Idle { seconds: 10 });
+        h.handle(Input::Idle { seconds: 21 });
+        let effects = h.handle(Input::Idle { seconds: 0 });
+        assert!(effects.contains(&Effect::Persist));
+        assert_eq!(h.remaining(), 60);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 1);
+
+        // A long break is due after two short ones; a short absence does not stand in for it,
+        // one as long as the long break does.
+        h.scheduler.state.short_breaks_since_long = 2;
+        h.handle(Input::Idle { seconds: 60 });
+        h.handle(Input::Idle { seconds: 0 });
+        assert_eq!(h.remaining(), 60);
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 2);
+        h.handle(Input::Idle { seconds: 120 });
+        h.handle(Input::Idle { seconds: 0 });
+        assert_eq!(h.scheduler.state.short_breaks_since_long, 0);
+    }
+
+    #[test]
+    fn idle_is_ignored_when_off_or_during_a_break() {
+        let mut h = Harness::new(&config());
+        h.handle(Input::Idle { seconds: 600 });
+        assert_eq!(h.scheduler.state.pause_reason, None);
+
+        let mut config = config();
+        config.idle_pause_seconds = 10;
+        let mut h = Harness::new(&config);
+        assert!(opened(&h.tick_each(60)).is_some());
+        h.handle(Input::Idle { seconds: 30 });
+        assert_eq!(h.scheduler.state.pause_reason, None);
+    }
+
+    /// [`config`] with working hours on Monday until 10:00, when the tests start, and on
+    /// Wednesday; Tuesday is a day off.
+    fn scheduled_config() -> AppConfig {
+        let mut config = config();
+        config.schedule.enabled = true;
+        config.schedule.days = crate::config::WeekDays {
+            monday: vec!["09:00-10:00".into()],
+            wednesday: vec!["09:00-17:00".into()],
+            ..Default::default()
+        };
+        config
+    }
+
+    fn local(d: u32, h: u32, mi: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn outside_working_hours_pauses_until_the_next_range() {
+        let mut h = Harness::new(&scheduled_config());
+        h.tick_after(1);
+        let status = h.scheduler.status();
+        assert_eq!(status.pause_reason, Some(PauseReason::OutsideSchedule));
+        assert_eq!(status.off_until, Some(local(4, 9, 0)));
+        assert!(status.work_late_available);
+        let effects = h.tick_each(120);
+        assert!(opened(&effects).is_none());
+        assert_eq!(h.remaining(), 60);
+
+        // Wednesday morning: a fresh cycle starts with working hours.
+        let until_wednesday = (local(4, 8, 59) - h.clock.local_now()).num_seconds() as u64;
+        h.tick_after(until_wednesday);
+        assert_eq!(
+            h.scheduler.state.pause_reason,
+            Some(PauseReason::OutsideSchedule)
+        );
+        h.tick_each(59);
+        assert_eq!(h.remaining(), 60);
+        h.tick_each(1);
+        assert_eq!(h.scheduler.state.pause_reason, None);
+        assert_eq!(h.scheduler.status().off_until, None);
+        assert_eq!(h.remaining(), 59);
+        assert!(opened(&h.tick_each(60)).is_some());
+    }
+
+    #[test]
+    fn working_late_ignores_the_schedule_until_midnight() {
+        let mut h = Harness::new(&scheduled_config());
+        h.tick_after(1);
+        h.handle(Input::WorkLate);
+        let status = h.scheduler.status();
+        assert_eq!(status.pause_reason, None);
+        assert!(!status.work_late_available);
+        assert_eq!(h.remaining(), 60);
+        assert!(opened(&h.tick_each(60)).is_some());
+
+        // After midnight, Tuesday's day off applies again once the current break is over.
+        let until_midnight = (local(3, 0, 0) - h.clock.local_now()).num_seconds() as u64;
+        h.tick_after(until_midnight);
+        h.tick_each(30);
+        assert_eq!(h.scheduler.phase, Phase::Working);
+        let status = h.scheduler.status();
+        assert_eq!(status.pause_reason, Some(PauseReason::OutsideSchedule));
+        assert_eq!(status.off_until, Some(local(4, 9, 0)));
+        assert!(status.work_late_available);
+    }
+
+    #[test]
+    fn work_late_does_nothing_without_a_schedule() {
+        let mut h = Harness::new(&config());
+        assert!(!h.scheduler.status().work_late_available);
+        assert!(h.handle(Input::WorkLate).is_empty());
+    }
+
+    fn snooze_config(minutes: u32, limit: u32, shrinks: bool) -> AppConfig {
+        let mut config = config();
+        config.snooze_minutes = minutes;
+        config.snooze_limit = limit;
+        config.snooze_shrinks = shrinks;
+        config
+    }
+
+    #[test]
+    fn snoozing_is_limited_per_cycle() {
+        let mut h = Harness::new(&snooze_config(4, 2, false));
+        assert!(opened(&h.tick_each(60)).is_some());
+        assert_eq!(h.scheduler.status().snoozes_left, 2);
+
+        let effects = h.scheduler.snooze().expect("first snooze");
+        assert!(effects.contains(&Effect::CloseOverlays));
+        assert!(h.scheduler.current_break().is_none());
+        assert_eq!(h.scheduler.state.pause_reason, Some(PauseReason::Snoozed));
+        assert_eq!(h.scheduler.status().snoozes_left, 1);
+        // The break reopens on the first second after the snooze.
+        assert!(opened(&h.tick_each(240)).is_none());
+        assert!(opened(&h.tick_each(1)).is_some());
+
+        h.scheduler.snooze().expect("second snooze");
+        assert_eq!(h.scheduler.status().snoozes_left, 0);
+        assert!(opened(&h.tick_each(241)).is_some());
+        assert_eq!(h.scheduler.snooze_seconds(), 0);
+        assert_eq!(
+            h.scheduler.snooze(),
+            Err("Snooze is not available".to_string())
+        );
+        assert!(h.scheduler.current_break().is_some());
+
+        // The next cycle starts with a fresh allowance.
+        h.tick_each(20);
+        assert_eq!(h.scheduler.snooze_seconds(), 240);
+        assert_eq!(h.scheduler.status().snoozes_left, 2);
+    }
+
+    #[test]
+    fn shrinking_snoozes_halve_down_to_a_minute() {
+        let mut h = Harness::new(&snooze_config(4, 5, true));
+        h.tick_each(60);
+        for want in [240, 120, 60, 60] {
+            assert_eq!(h.scheduler.snooze_seconds(), want);
+            h.scheduler.snooze().expect("snooze on offer");
+            assert!(opened(&h.tick_each(want as u64)).is_none());
+            assert!(opened(&h.tick_each(1)).is_some(), "after {want} s");
+        }
+        assert_eq!(h.scheduler.snooze_seconds(), 60);
+
+        let mut h = Harness::new(&snooze_config(4, 5, false));
+        h.tick_each(60);
+        h.scheduler.snooze().unwrap();
+        h.tick_each(241);
+        assert_eq!(h.scheduler.snooze_seconds(), 240);
+    }
+
+    #[test]
+    fn snoozing_is_unavailable_in_strict_mode_or_while_paused() {
+        let mut config = snooze_config(4, 2, false);
+        config.strict_mode = true;
+        let mut h = Harness::new(&config);
+        h.tick_each(60);
+        assert_eq!(h.scheduler.snooze_seconds(), 0);
+        assert!(h.scheduler.snooze().is_err());
+        assert!(h.scheduler.current_break().is_some());
+
+        let mut h = Harness::new(&snooze_config(4, 2, false));
+        h.handle(Input::Pause { seconds: 300 });
+        assert!(h.scheduler.snooze().is_err());
+        assert_eq!(h.scheduler.state.pause_reason, Some(PauseReason::Manual));
+        assert_eq!(h.scheduler.status().snoozes_left, 2);
+    }
+
+    fn reminder(name: &str, minutes: u32, delivery: ReminderDelivery) -> ReminderConfig {
+        ReminderConfig {
+            name: name.into(),
+            interval_minutes: minutes,
+            duration_seconds: 30,
+            delivery,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn overlay_reminder_due_within_a_minute_of_the_eye_break_is_folded_into_it() {
+        // Due a minute before, and together with, the eye break at 20 minutes.
+        for minutes in [19, 20] {
+            let mut config = config();
+            config.work_interval_minutes = 20;
+            config.reminders = vec![reminder("Stretch", minutes, ReminderDelivery::Overlay)];
+            let mut h = Harness::new(&config);
+            assert!(opened(&h.tick_each(1199)).is_none(), "{minutes} min");
+            let effects = h.tick_each(1);
+            let info = opened(&effects).expect("eye break");
+            assert_eq!(info.kind, BreakKind::Short);
+            assert_eq!(info.reminders, ["Time to stretch"]);
+            assert_eq!(info.duration, 30, "the longer of the two durations");
+            assert_eq!(
+                h.scheduler.reminder_statuses()[0].seconds_remaining,
+                minutes * 60
+            );
+        }
+    }
+
+    #[test]
+    fn overlay_reminder_further_from_the_eye_break_opens_on_its_own() {
+        let mut config = config();
+        config.work_interval_minutes = 20;
+        config.reminders = vec![reminder("Stretch", 22, ReminderDelivery::Overlay)];
+        let mut h = Harness::new(&config);
+        let info = opened(&h.tick_each(1200)).cloned().expect("eye break");
+        assert!(info.reminders.is_empty());
+        assert_eq!(info.duration, 20);
+        h.tick_each(20);
+
+        // Reminders count working time only, so about two minutes are left after the eye break.
+        let left = h.scheduler.reminder_statuses()[0].seconds_remaining;
+        assert!((120..=121).contains(&left), "{left}");
+        assert!(opened(&h.tick_each(left as u64 - 1)).is_none());
+        let effects = h.tick_each(1);
+        let info = opened(&effects).expect("reminder break");
+        assert_eq!(info.kind, BreakKind::Reminder);
+        assert_eq!(info.reminders, ["Time to stretch"]);
+        assert_eq!(info.duration, 30);
+        assert_eq!(h.remaining(), 1200 - left);
+    }
+
+    #[test]
+    fn each_reminder_fires_on_its_own_interval() {
+        let mut config = config();
+        config.work_interval_minutes = 20;
+        config.reminders = vec![
+            reminder("Water", 2, ReminderDelivery::Notification),
+            reminder("Posture", 3, ReminderDelivery::Notification),
+            ReminderConfig {
+                enabled: false,
+                ..reminder("Off", 1, ReminderDelivery::Notification)
+            },
+        ];
+        let mut h = Harness::new(&config);
+        assert!(reminded(&h.tick_each(119)).is_empty());
+        assert_eq!(reminded(&h.tick_each(1)), ["Water"]);
+        assert!(reminded(&h.tick_each(59)).is_empty());
+        assert_eq!(reminded(&h.tick_each(1)), ["Posture"]);
+        assert_eq!(reminded(&h.tick_each(60)), ["Water"]);
+        assert_eq!(reminded(&h.tick_each(120)), ["Water", "Posture"]);
+
+        let effects = h.tick_each(1199 - 360);
+        let count = |name| reminded(&effects).iter().filter(|&&n| n == name).count();
+        assert_eq!((count("Water"), count("Posture")), (6, 4));
+        assert!(opened(&effects).is_none());
+    }
 }
@@ -1,8 +1,8 @@
 use crate::commands::AppState;
-use crate::timer::PauseReason;
+use crate::timer::{Input, ReminderStatus, TrayIconState};
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     App, Manager,
 };
@@ -18,9 +18,13 @@ macro_rules! lock {
 ///
 /// The menu includes:
 /// - "Next break in..." (disabled, used for status)
+/// - One disabled status item per enabled reminder schedule ("Stretch at 14:35")
 /// - "Skip next break"
+/// - "Snooze break" (label shows the snooze length and how many are left)
 /// - "Pause for 30 min"
 /// - "Pause for 1 hr"
+/// - "Work late tonight" (enabled when a working-hours schedule is configured)
+/// - "Profile" submenu ("Automatic" plus one entry per configured profile)
 /// - Separator
 /// - "Settings…"
 /// - "Quit Twenty20"
@@ -28,8 +32,12 @@ pub fn setup_tray(app: &mut App) -> tauri::Result<()> {
     let next_break_item =
         MenuItem::with_id(app, "next_break", "Next break in...", false, None::<&str>)?;
     let skip_item = MenuItem::with_id(app, "skip", "Skip next break", true, None::<&str>)?;
+    let snooze_item = MenuItem::with_id(app, "snooze", "Snooze break", true, None::<&str>)?;
     let pause_30_item = MenuItem::with_id(app, "pause_30", "Pause for 30 min", true, None::<&str>)?;
     let pause_1h_item = MenuItem::with_id(app, "pause_1h", "Pause for 1 hr", true, None::<&str>)?;
+    let work_late_item =
+        MenuItem::with_id(app, "work_late", "Work late tonight", false, None::<&str>)?;
+    let profile_menu = Submenu::with_id(app, "profile", "Profile", true)?;
     let separator = PredefinedMenuItem::separator(app)?;
     let settings_item = MenuItem::with_id(app, "settings", "Settings…", true, None::<&str>)?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit Twenty20", true, None::<&str>)?;
@@ -39,8 +47,11 @@ pub fn setup_tray(app: &mut App) -> tauri::Result<()> {
         &[
             &next_break_item,
             &skip_item,
+            &snooze_item,
             &pause_30_item,
             &pause_1h_item,
+            &work_late_item,
+            &profile_menu,
             &separator,
             &settings_item,
             &quit_item,
@@ -49,6 +60,22 @@ pub fn setup_tray(app: &mut App) -> tauri::Result<()> {
 
     {
         let state = app.state::<AppState>();
+        let reminders: Vec<String> = lock!(state.config)
+            .reminders
+            .iter()
+            .filter(|r| r.enabled)
+            .map(|r| r.name.clone())
+            .collect();
+        for (i, name) in reminders.iter().enumerate() {
+            let item = MenuItem::with_id(
+                app,
+                reminder_item_id(name),
+                format!("{name} at --:--"),
+                false,
+                None::<&str>,
+            )?;
+            menu.insert(&item, i + 1)?;
+        }
         *state.tray_menu.lock().unwrap() = Some(menu.clone());
     }
 
@@ -70,7 +97,11 @@ pub fn setup_tray(app: &mut App) -> tauri::Result<()> {
                 // Ensure timer state is saved before quitting.
                 let state = app.state::<AppState>();
                 let ts = lock!(state.timer);
-                crate::timer::persist_state(&ts);
+                crate::timer::persist_state(
+                    &crate::timer::state_path(),
+                    &ts.state,
+                    ts.active_break(),
+                );
                 app.exit(0);
             }
             "settings" => {
@@ -78,33 +109,33 @@ pub fn setup_tray(app: &mut App) -> tauri::Result<()> {
             }
             "skip" => {
                 let state = app.state::<AppState>();
-                let mut ts = lock!(state.timer);
-                if !ts.is_strict_mode {
-                    ts.seconds_remaining = ts.work_interval_seconds;
-                    ts.is_paused = false;
-                    ts.pause_reason = None;
-                    log::info!("Break skipped via tray");
-                }
+                let effects = lock!(state.timer).handle(Input::Skip);
+                crate::apply_effects(app, effects);
+            }
+            "snooze" => {
+                let state = app.state::<AppState>();
+                let effects = lock!(state.timer).handle(Input::Snooze);
+                crate::apply_effects(app, effects);
+            }
+            "work_late" => {
+                let state = app.state::<AppState>();
+                let effects = lock!(state.timer).handle(Input::WorkLate);
+                crate::apply_effects(app, effects);
             }
             "pause_30" => {
                 let state = app.state::<AppState>();
-                let mut ts = lock!(state.timer);
-                if !ts.is_strict_mode {
-                    ts.is_paused = true;
-                    ts.pause_reason = Some(PauseReason::Manual);
-                    ts.manual_pause_seconds_remaining = Some(30 * 60);
-                    log::info!("Timer paused for 30 min via tray");
-                }
+                let effects = lock!(state.timer).handle(Input::Pause { seconds: 30 * 60 });
+                crate::apply_effects(app, effects);
             }
             "pause_1h" => {
                 let state = app.state::<AppState>();
-                let mut ts = lock!(state.timer);
-                if !ts.is_strict_mode {
-                    ts.is_paused = true;
-                    ts.pause_reason = Some(PauseReason::Manual);
-                    ts.manual_pause_seconds_remaining = Some(60 * 60);
-                    log::info!("Timer paused for 1 hr via tray");
-                }
+                let effects = lock!(state.timer).handle(Input::Pause { seconds: 60 * 60 });
+                crate::apply_effects(app, effects);
+            }
+            id if id.starts_with(PROFILE_ITEM_PREFIX) => {
+                let name = &id[PROFILE_ITEM_PREFIX.len()..];
+                let name = (id != AUTOMATIC_PROFILE_ITEM_ID).then(|| name.to_string());
+                crate::profiles::select(app, name);
             }
             _ => {}
         })
@@ -124,18 +155,107 @@ pub fn setup_tray(app: &mut App) -> tauri::Result<()> {
     Ok(())
 }
 
+/// Menu item id of the status line for the reminder schedule `name`.
+fn reminder_item_id(name: &str) -> String {
+    format!("reminder:{name}")
+}
+
+/// Refreshes the per-reminder status lines with the wall-clock time each schedule is next due.
+pub fn update_reminder_items(app: &tauri::AppHandle, reminders: &[ReminderStatus]) {
+    use tauri::menu::MenuItemKind;
+
+    if reminders.is_empty() {
+        return;
+    }
+    let state = app.state::<AppState>();
+    let menu = match &*lock!(state.tray_menu) {
+        Some(m) => m.clone(),
+        None => return,
+    };
+    let now = chrono::Local::now();
+    for r in reminders {
+        if let Some(MenuItemKind::MenuItem(item)) = menu.get(&reminder_item_id(&r.name)) {
+            let due = now + chrono::Duration::seconds(i64::from(r.seconds_remaining));
+            let _ = item.set_text(format!("{} at {}", r.name, due.format("%H:%M")));
+        }
+    }
+}
+
+const PROFILE_ITEM_PREFIX: &str = "profile:";
+/// Distinct from every profile item id, which carry a name after the prefix.
+const AUTOMATIC_PROFILE_ITEM_ID: &str = "profile:";
+
+/// Rebuilds the "Profile" submenu: "Automatic" (naming the profile it picked) and one check
+/// item per configured profile, the pinned one checked.
+pub fn update_profile_items(app: &tauri::AppHandle) {
+    use std::sync::{Mutex, OnceLock};
+    use tauri::menu::{CheckMenuItem, MenuItemKind};
+
+    /// Labels of the last build, to skip rebuilding an unchanged (possibly open) menu.
+    static LAST: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+
+    let state = app.state::<AppState>();
+    let (names, pinned) = {
+        let base = lock!(state.base_config);
+        let names: Vec<String> = base.profiles.iter().map(|p| p.name.clone()).collect();
+        (names, base.active_profile.clone())
+    };
+    let in_effect = lock!(state.active_profile).clone();
+    let automatic = match (&pinned, &in_effect) {
+        (None, Some(name)) => format!("Automatic ({name})"),
+        _ => "Automatic".to_string(),
+    };
+    let mut labels = vec![automatic.clone(), pinned.clone().unwrap_or_default()];
+    labels.extend(names.iter().cloned());
+    {
+        let mut last = lock!(LAST.get_or_init(|| Mutex::new(Vec::new())));
+        if *last == labels {
+            return;
+        }
+        *last = labels;
+    }
+
+    let menu = match &*lock!(state.tray_menu) {
+        Some(m) => m.clone(),
+        None => return,
+    };
+    let Some(MenuItemKind::Submenu(submenu)) = menu.get("profile") else {
+        return;
+    };
+    if let Ok(items) = submenu.items() {
+        for item in items {
+            let _ = submenu.remove(&item);
+        }
+    }
+    if let Ok(item) = CheckMenuItem::with_id(
+        app,
+        AUTOMATIC_PROFILE_ITEM_ID,
+        automatic,
+        !names.is_empty(),
+        pinned.is_none(),
+        None::<&str>,
+    ) {
+        let _ = submenu.append(&item);
+    }
+    for name in &names {
+        if let Ok(item) = CheckMenuItem::with_id(
+            app,
+            format!("{PROFILE_ITEM_PREFIX}{name}"),
+            name,
+            true,
+            pinned.as_deref() == Some(name.as_str()),
+            None::<&str>,
+        ) {
+            let _ = submenu.append(&item);
+        }
+    }
+}
+
 /// Opens the settings window, creating it if it doesn't exist.
 fn open_settings(app: &tauri::AppHandle) {
     crate::settings_window::show_settings(app);
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum TrayIconState {
-    Open,
-    Blink,
-    Rest,
-}
-
 pub fn update_icon(app: &tauri::AppHandle, state: TrayIconState) {
     let icon_bytes = match state {
         TrayIconState::Open => include_bytes!("../icons/eye_openTemplate.png").as_slice(),